@@ -0,0 +1,284 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use crate::pulse::PulseReading;
+use crate::Error;
+
+const ALARM_LOG_PATH: &str = "./data/alarm_log.jsonl";
+const MAX_BPM: u32 = 250;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AlarmThresholds {
+    pub upper_bpm: u16,
+    pub lower_bpm: u16,
+    pub min_spo2: u8,
+    pub bpm_hysteresis: u16,
+    pub spo2_hysteresis: u8,
+}
+
+impl Default for AlarmThresholds {
+    fn default() -> Self {
+        AlarmThresholds {
+            upper_bpm: 160,
+            lower_bpm: 40,
+            min_spo2: 90,
+            bpm_hysteresis: 5,
+            spo2_hysteresis: 2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ThresholdField {
+    UpperBpm,
+    LowerBpm,
+    MinSpo2,
+    BpmHysteresis,
+    Spo2Hysteresis,
+}
+
+impl ThresholdField {
+    pub const ALL: [ThresholdField; 5] = [
+        ThresholdField::UpperBpm,
+        ThresholdField::LowerBpm,
+        ThresholdField::MinSpo2,
+        ThresholdField::BpmHysteresis,
+        ThresholdField::Spo2Hysteresis,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThresholdField::UpperBpm => "Upper BPM",
+            ThresholdField::LowerBpm => "Lower BPM",
+            ThresholdField::MinSpo2 => "Min SpO2 %",
+            ThresholdField::BpmHysteresis => "BPM Hysteresis",
+            ThresholdField::Spo2Hysteresis => "SpO2 Hysteresis",
+        }
+    }
+}
+
+impl AlarmThresholds {
+    pub fn get(&self, field: ThresholdField) -> u32 {
+        match field {
+            ThresholdField::UpperBpm => self.upper_bpm as u32,
+            ThresholdField::LowerBpm => self.lower_bpm as u32,
+            ThresholdField::MinSpo2 => self.min_spo2 as u32,
+            ThresholdField::BpmHysteresis => self.bpm_hysteresis as u32,
+            ThresholdField::Spo2Hysteresis => self.spo2_hysteresis as u32,
+        }
+    }
+
+    // Allowed values for a field. The BPM bounds can't meet or cross, or a
+    // reading could be too high and too low at once.
+    pub fn range(&self, field: ThresholdField) -> (u32, u32) {
+        match field {
+            ThresholdField::UpperBpm => ((self.lower_bpm as u32 + 1).min(MAX_BPM), MAX_BPM),
+            ThresholdField::LowerBpm => (0, (self.upper_bpm as u32).saturating_sub(1)),
+            ThresholdField::MinSpo2 => (0, 100),
+            ThresholdField::BpmHysteresis | ThresholdField::Spo2Hysteresis => (0, 20),
+        }
+    }
+
+    pub fn set(&mut self, field: ThresholdField, value: u32) {
+        let (min, max) = self.range(field);
+        let value = value.clamp(min, max);
+        match field {
+            ThresholdField::UpperBpm => self.upper_bpm = value as u16,
+            ThresholdField::LowerBpm => self.lower_bpm = value as u16,
+            ThresholdField::MinSpo2 => self.min_spo2 = value as u8,
+            ThresholdField::BpmHysteresis => self.bpm_hysteresis = value as u16,
+            ThresholdField::Spo2Hysteresis => self.spo2_hysteresis = value as u8,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlarmKind {
+    HighBpm,
+    LowBpm,
+    LowSpo2,
+}
+
+impl AlarmKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlarmKind::HighBpm => "HEART RATE HIGH",
+            AlarmKind::LowBpm => "HEART RATE LOW",
+            AlarmKind::LowSpo2 => "BLOOD OXYGEN LOW",
+        }
+    }
+}
+
+// Whether a log line records the alarm going off or someone dismissing it.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlarmLogKind {
+    Raised,
+    Acknowledged,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AlarmEvent {
+    pub kind: AlarmKind,
+    pub bpm: u16,
+    pub spo2: Option<u8>,
+    pub at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+}
+
+// Tracks which alarms are tripped so a reading hovering around a threshold
+// only raises once until it has moved back past the hysteresis band.
+#[derive(Default)]
+pub struct AlarmMonitor {
+    high_bpm: bool,
+    low_bpm: bool,
+    low_spo2: bool,
+}
+
+impl AlarmMonitor {
+    pub fn check(&mut self, reading: &PulseReading, thresholds: &AlarmThresholds) -> Vec<AlarmEvent> {
+        let mut raised = Vec::new();
+        let mut raise = |kind| {
+            raised.push(AlarmEvent {
+                kind,
+                bpm: reading.bpm,
+                spo2: reading.spo2,
+                at: reading.at,
+                acknowledged_at: None,
+            })
+        };
+
+        if !self.high_bpm && reading.bpm > thresholds.upper_bpm {
+            self.high_bpm = true;
+            raise(AlarmKind::HighBpm);
+        } else if self.high_bpm && reading.bpm <= thresholds.upper_bpm.saturating_sub(thresholds.bpm_hysteresis) {
+            self.high_bpm = false;
+        }
+
+        if !self.low_bpm && reading.bpm < thresholds.lower_bpm {
+            self.low_bpm = true;
+            raise(AlarmKind::LowBpm);
+        } else if self.low_bpm && reading.bpm >= thresholds.lower_bpm + thresholds.bpm_hysteresis {
+            self.low_bpm = false;
+        }
+
+        if let Some(spo2) = reading.spo2 {
+            if !self.low_spo2 && spo2 < thresholds.min_spo2 {
+                self.low_spo2 = true;
+                raise(AlarmKind::LowSpo2);
+            } else if self.low_spo2 && spo2 >= thresholds.min_spo2.saturating_add(thresholds.spo2_hysteresis) {
+                self.low_spo2 = false;
+            }
+        }
+
+        raised
+    }
+}
+
+#[derive(Serialize)]
+struct AlarmLogEntry<'a> {
+    entry: AlarmLogKind,
+    #[serde(flatten)]
+    event: &'a AlarmEvent,
+}
+
+pub fn log_alarm_event(event: &AlarmEvent, entry: AlarmLogKind) -> Result<(), Error> {
+    log_alarm_event_to(ALARM_LOG_PATH, event, entry)
+}
+
+fn log_alarm_event_to(path: &str, event: &AlarmEvent, entry: AlarmLogKind) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(&AlarmLogEntry { entry, event })?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+
+    fn reading(bpm: u16, spo2: Option<u8>) -> PulseReading {
+        PulseReading { bpm, spo2, at: Utc.with_ymd_and_hms(2026, 10, 19, 7, 0, 0).unwrap() }
+    }
+
+    // Feeds readings through a fresh monitor and lists what each one raised.
+    fn run(readings: &[(u16, Option<u8>)]) -> Vec<Vec<AlarmKind>> {
+        let mut monitor = AlarmMonitor::default();
+        let thresholds = AlarmThresholds::default();
+        readings
+            .iter()
+            .map(|&(bpm, spo2)| monitor.check(&reading(bpm, spo2), &thresholds).iter().map(|a| a.kind).collect())
+            .collect()
+    }
+
+    #[test]
+    fn high_rate_raises_once_until_it_drops_past_the_band() {
+        // Upper 160 with 5 BPM of hysteresis: re-arms at 155 or below.
+        let raised = run(&[(150, None), (161, None), (170, None), (158, None), (162, None), (155, None), (161, None)]);
+        let high = vec![AlarmKind::HighBpm];
+        assert_eq!(raised, vec![vec![], high.clone(), vec![], vec![], vec![], vec![], high]);
+    }
+
+    #[test]
+    fn low_rate_and_oxygen_have_their_own_bands() {
+        let raised = run(&[(39, Some(89)), (42, Some(91)), (38, Some(88)), (45, Some(92)), (39, None), (60, Some(89))]);
+        assert_eq!(
+            raised,
+            vec![
+                vec![AlarmKind::LowBpm, AlarmKind::LowSpo2],
+                vec![],
+                vec![],
+                vec![],
+                vec![AlarmKind::LowBpm],
+                vec![AlarmKind::LowSpo2],
+            ]
+        );
+    }
+
+    #[test]
+    fn bpm_bounds_cannot_cross() {
+        let mut thresholds = AlarmThresholds::default();
+        thresholds.set(ThresholdField::LowerBpm, 200);
+        assert_eq!(thresholds.lower_bpm, 159);
+        thresholds.set(ThresholdField::UpperBpm, 100);
+        assert_eq!(thresholds.upper_bpm, 160);
+        assert_eq!(thresholds.range(ThresholdField::UpperBpm), (160, MAX_BPM));
+
+        thresholds.set(ThresholdField::LowerBpm, 0);
+        thresholds.set(ThresholdField::UpperBpm, 1);
+        assert_eq!((thresholds.lower_bpm, thresholds.upper_bpm), (0, 1));
+        thresholds.set(ThresholdField::MinSpo2, 150);
+        assert_eq!(thresholds.min_spo2, 100);
+    }
+
+    #[test]
+    fn acknowledgements_are_logged_as_their_own_entries() {
+        let dir = scratch_dir("alarms-log");
+        let path = dir.join("alarm_log.jsonl");
+        let path = path.to_str().unwrap();
+
+        let mut alarm = AlarmMonitor::default().check(&reading(170, Some(97)), &AlarmThresholds::default()).remove(0);
+        log_alarm_event_to(path, &alarm, AlarmLogKind::Raised).unwrap();
+        alarm.acknowledged_at = Some(alarm.at + chrono::Duration::seconds(12));
+        log_alarm_event_to(path, &alarm, AlarmLogKind::Acknowledged).unwrap();
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["entry"], "Raised");
+        assert_eq!(lines[0]["kind"], "HighBpm");
+        assert!(lines[0]["acknowledged_at"].is_null());
+        assert_eq!(lines[1]["entry"], "Acknowledged");
+        assert_eq!(lines[1]["bpm"], 170);
+        assert_eq!(lines[1]["acknowledged_at"], "2026-10-19T07:00:12Z");
+    }
+}
@@ -0,0 +1,633 @@
+use chrono::prelude::*;
+use chrono::Duration;
+
+// Sun and moon from the low-precision series in Meeus, "Astronomical
+// Algorithms" (chapters 12, 22, 25, 47 and 48). Good to a minute or so on
+// rise and set times, which is as close as the horizon ever is anyway.
+// Universal time is used as if it were dynamical time; the ~70 s between
+// them moves the moon by less than its own width.
+
+// Apparent altitude of the sun's centre at rise and set: refraction plus the
+// radius of the disc.
+const SUNRISE_ALTITUDE: f64 = -0.8333;
+const CIVIL_ALTITUDE: f64 = -6.0;
+const NAUTICAL_ALTITUDE: f64 = -12.0;
+const EARTH_RADIUS_KM: f64 = 6378.14;
+const AU_KM: f64 = 149_597_870.7;
+const SYNODIC_MONTH_DAYS: f64 = 29.530589;
+// Rise and set are found by stepping through the day at this interval and
+// bisecting wherever the altitude crosses the horizon.
+const SCAN_STEP_MIN: i64 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyPosition {
+    // Degrees clockwise from true north.
+    pub azimuth: f64,
+    // Degrees above the horizon, no refraction.
+    pub elevation: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SunTimes {
+    pub nautical_dawn: Option<DateTime<Utc>>,
+    pub civil_dawn: Option<DateTime<Utc>>,
+    pub sunrise: Option<DateTime<Utc>>,
+    pub solar_noon: Option<DateTime<Utc>>,
+    pub sunset: Option<DateTime<Utc>>,
+    pub civil_dusk: Option<DateTime<Utc>>,
+    pub nautical_dusk: Option<DateTime<Utc>>,
+    // Whether the sun was up at the start of the day, for telling midnight
+    // sun from polar night when there's no sunrise or sunset.
+    pub up_at_start: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MoonTimes {
+    pub moonrise: Option<DateTime<Utc>>,
+    pub moonset: Option<DateTime<Utc>>,
+    pub up_at_start: bool,
+}
+
+// Rise and set times for one local day at one spot. Each is a scan of the
+// whole day, so these are kept until the day changes or you've moved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DayTimes {
+    pub day: (DateTime<Utc>, DateTime<Utc>),
+    pub coordinates: [f64; 2],
+    pub sun: SunTimes,
+    pub moon: MoonTimes,
+}
+
+impl DayTimes {
+    pub fn new(day: (DateTime<Utc>, DateTime<Utc>), coordinates: [f64; 2]) -> DayTimes {
+        DayTimes { day, coordinates, sun: sun_times(day, coordinates), moon: moon_times(day, coordinates) }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoonPhase {
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MoonPhase::New => "New Moon",
+            MoonPhase::WaxingCrescent => "Waxing Crescent",
+            MoonPhase::FirstQuarter => "First Quarter",
+            MoonPhase::WaxingGibbous => "Waxing Gibbous",
+            MoonPhase::Full => "Full Moon",
+            MoonPhase::WaningGibbous => "Waning Gibbous",
+            MoonPhase::LastQuarter => "Last Quarter",
+            MoonPhase::WaningCrescent => "Waning Crescent",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoonIllumination {
+    pub phase: MoonPhase,
+    // Lit fraction of the disc, 0 to 1.
+    pub fraction: f64,
+    // Days since the last new moon.
+    pub age_days: f64,
+}
+
+// Right ascension and declination in degrees, and distance in km.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Equatorial {
+    right_ascension: f64,
+    declination: f64,
+    distance_km: f64,
+}
+
+fn julian_day(at: DateTime<Utc>) -> f64 {
+    at.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5
+}
+
+fn centuries_since_j2000(jd: f64) -> f64 {
+    (jd - 2_451_545.0) / 36_525.0
+}
+
+fn sin_deg(degrees: f64) -> f64 {
+    degrees.to_radians().sin()
+}
+
+fn cos_deg(degrees: f64) -> f64 {
+    degrees.to_radians().cos()
+}
+
+// Longitude of the moon's ascending node and the obliquity, both with the
+// largest nutation term, which is all the precision here calls for.
+fn nutation(t: f64) -> (f64, f64) {
+    let omega = 125.04452 - 1934.136261 * t;
+    let mean_obliquity = 23.439_291_1 - 0.013_004_2 * t;
+    let longitude = -0.004778 * sin_deg(omega);
+    let obliquity = mean_obliquity + 0.002_56 * cos_deg(omega);
+    (longitude, obliquity)
+}
+
+fn to_equatorial(longitude: f64, latitude: f64, obliquity: f64, distance_km: f64) -> Equatorial {
+    let right_ascension = (sin_deg(longitude) * cos_deg(obliquity) - latitude.to_radians().tan() * sin_deg(obliquity))
+        .atan2(cos_deg(longitude))
+        .to_degrees()
+        .rem_euclid(360.0);
+    let declination = (sin_deg(latitude) * cos_deg(obliquity) + cos_deg(latitude) * sin_deg(obliquity) * sin_deg(longitude))
+        .asin()
+        .to_degrees();
+    Equatorial { right_ascension, declination, distance_km }
+}
+
+// Apparent ecliptic longitude and distance of the sun (chapter 25).
+fn sun_ecliptic(jd: f64) -> (f64, f64) {
+    let t = centuries_since_j2000(jd);
+    let mean_longitude = 280.46646 + 36000.76983 * t + 0.0003032 * t * t;
+    let anomaly = 357.52911 + 35999.05029 * t - 0.0001537 * t * t;
+    let eccentricity = 0.016708634 - 0.000042037 * t - 0.0000001267 * t * t;
+    let center = (1.914602 - 0.004817 * t - 0.000014 * t * t) * sin_deg(anomaly)
+        + (0.019993 - 0.000101 * t) * sin_deg(2.0 * anomaly)
+        + 0.000289 * sin_deg(3.0 * anomaly);
+    let true_longitude = mean_longitude + center;
+    let distance_au = 1.000001018 * (1.0 - eccentricity * eccentricity) / (1.0 + eccentricity * cos_deg(anomaly + center));
+    let omega = 125.04 - 1934.136 * t;
+    let apparent = true_longitude - 0.00569 - 0.00478 * sin_deg(omega);
+    (apparent.rem_euclid(360.0), distance_au * AU_KM)
+}
+
+fn sun_equatorial(jd: f64) -> Equatorial {
+    let (longitude, distance_km) = sun_ecliptic(jd);
+    let (_, obliquity) = nutation(centuries_since_j2000(jd));
+    to_equatorial(longitude, 0.0, obliquity, distance_km)
+}
+
+// Periodic terms for the moon: multiples of D, M, M' and F, then the
+// longitude (1e-6 degrees) and distance (metres) coefficients. The largest
+// rows of Meeus table 47.A.
+const MOON_LONGITUDE_DISTANCE: [(f64, f64, f64, f64, f64, f64); 35] = [
+    (0.0, 0.0, 1.0, 0.0, 6288774.0, -20905355.0),
+    (2.0, 0.0, -1.0, 0.0, 1274027.0, -3699111.0),
+    (2.0, 0.0, 0.0, 0.0, 658314.0, -2955968.0),
+    (0.0, 0.0, 2.0, 0.0, 213618.0, -569925.0),
+    (0.0, 1.0, 0.0, 0.0, -185116.0, 48888.0),
+    (0.0, 0.0, 0.0, 2.0, -114332.0, -3149.0),
+    (2.0, 0.0, -2.0, 0.0, 58793.0, 246158.0),
+    (2.0, -1.0, -1.0, 0.0, 57066.0, -152138.0),
+    (2.0, 0.0, 1.0, 0.0, 53322.0, -170733.0),
+    (2.0, -1.0, 0.0, 0.0, 45758.0, -204586.0),
+    (0.0, 1.0, -1.0, 0.0, -40923.0, -129620.0),
+    (1.0, 0.0, 0.0, 0.0, -34720.0, 108743.0),
+    (0.0, 1.0, 1.0, 0.0, -30383.0, 104755.0),
+    (2.0, 0.0, 0.0, -2.0, 15327.0, 10321.0),
+    (0.0, 0.0, 1.0, 2.0, -12528.0, 0.0),
+    (0.0, 0.0, 1.0, -2.0, 10980.0, 79661.0),
+    (4.0, 0.0, -1.0, 0.0, 10675.0, -34782.0),
+    (0.0, 0.0, 3.0, 0.0, 10034.0, -23210.0),
+    (4.0, 0.0, -2.0, 0.0, 8548.0, -21636.0),
+    (2.0, 1.0, -1.0, 0.0, -7888.0, 24208.0),
+    (2.0, 1.0, 0.0, 0.0, -6766.0, 30824.0),
+    (1.0, 0.0, -1.0, 0.0, -5163.0, -8379.0),
+    (1.0, 1.0, 0.0, 0.0, 4987.0, -16675.0),
+    (2.0, -1.0, 1.0, 0.0, 4036.0, -12831.0),
+    (2.0, 0.0, 2.0, 0.0, 3994.0, -10445.0),
+    (4.0, 0.0, 0.0, 0.0, 3861.0, -11650.0),
+    (2.0, 0.0, -3.0, 0.0, 3665.0, 14403.0),
+    (0.0, 1.0, -2.0, 0.0, -2689.0, -7003.0),
+    (2.0, 0.0, -1.0, 2.0, -2602.0, 0.0),
+    (2.0, -1.0, -2.0, 0.0, 2390.0, 10056.0),
+    (1.0, 0.0, 1.0, 0.0, -2348.0, 6322.0),
+    (2.0, -2.0, 0.0, 0.0, 2236.0, -9884.0),
+    (0.0, 1.0, 2.0, 0.0, -2120.0, 5751.0),
+    (0.0, 2.0, 0.0, 0.0, -2069.0, 0.0),
+    (2.0, -2.0, -1.0, 0.0, 2048.0, -4950.0),
+];
+
+// The same for latitude, from table 47.B.
+const MOON_LATITUDE: [(f64, f64, f64, f64, f64); 30] = [
+    (0.0, 0.0, 0.0, 1.0, 5128122.0),
+    (0.0, 0.0, 1.0, 1.0, 280602.0),
+    (0.0, 0.0, 1.0, -1.0, 277693.0),
+    (2.0, 0.0, 0.0, -1.0, 173237.0),
+    (2.0, 0.0, -1.0, 1.0, 55413.0),
+    (2.0, 0.0, -1.0, -1.0, 46271.0),
+    (2.0, 0.0, 0.0, 1.0, 32573.0),
+    (0.0, 0.0, 2.0, 1.0, 17198.0),
+    (2.0, 0.0, 1.0, -1.0, 9266.0),
+    (0.0, 0.0, 2.0, -1.0, 8822.0),
+    (2.0, -1.0, 0.0, -1.0, 8216.0),
+    (2.0, 0.0, -2.0, -1.0, 4324.0),
+    (2.0, 0.0, 1.0, 1.0, 4200.0),
+    (2.0, 1.0, 0.0, -1.0, -3359.0),
+    (2.0, -1.0, -1.0, 1.0, 2463.0),
+    (2.0, -1.0, 0.0, 1.0, 2211.0),
+    (2.0, -1.0, -1.0, -1.0, 2065.0),
+    (0.0, 1.0, -1.0, -1.0, -1870.0),
+    (4.0, 0.0, -1.0, -1.0, 1828.0),
+    (0.0, 1.0, 0.0, 1.0, -1794.0),
+    (0.0, 0.0, 0.0, 3.0, -1749.0),
+    (0.0, 1.0, -1.0, 1.0, -1565.0),
+    (1.0, 0.0, 0.0, 1.0, -1491.0),
+    (0.0, 1.0, 1.0, 1.0, -1475.0),
+    (0.0, 1.0, 1.0, -1.0, -1410.0),
+    (0.0, 1.0, 0.0, -1.0, -1344.0),
+    (1.0, 0.0, 0.0, -1.0, -1335.0),
+    (0.0, 0.0, 3.0, 1.0, 1107.0),
+    (4.0, 0.0, 0.0, -1.0, 1021.0),
+    (4.0, 0.0, -1.0, 1.0, 833.0),
+];
+
+// Geocentric ecliptic longitude, latitude and distance of the moon
+// (chapter 47), before nutation.
+fn moon_ecliptic(jd: f64) -> (f64, f64, f64) {
+    let t = centuries_since_j2000(jd);
+    let (t2, t3, t4) = (t * t, t * t * t, t * t * t * t);
+    let mean_longitude = 218.3164477 + 481267.88123421 * t - 0.0015786 * t2 + t3 / 538841.0 - t4 / 65194000.0;
+    let elongation = 297.8501921 + 445267.1114034 * t - 0.0018819 * t2 + t3 / 545868.0 - t4 / 113065000.0;
+    let sun_anomaly = 357.5291092 + 35999.0502909 * t - 0.0001536 * t2 + t3 / 24490000.0;
+    let moon_anomaly = 134.9633964 + 477198.8675055 * t + 0.0087414 * t2 + t3 / 69699.0 - t4 / 14712000.0;
+    let latitude_argument = 93.2720950 + 483202.0175233 * t - 0.0036539 * t2 - t3 / 3526000.0 + t4 / 863310000.0;
+    // Terms involving the sun's anomaly shrink as the earth's orbit rounds out.
+    let eccentricity = 1.0 - 0.002516 * t - 0.0000074 * t2;
+    let a1 = 119.75 + 131.849 * t;
+    let a2 = 53.09 + 479264.290 * t;
+    let a3 = 313.45 + 481266.484 * t;
+
+    let argument = |d: f64, m: f64, mp: f64, f: f64| {
+        d * elongation + m * sun_anomaly + mp * moon_anomaly + f * latitude_argument
+    };
+    let damping = |m: f64| eccentricity.powi(m.abs() as i32);
+
+    let (mut sum_l, mut sum_r) = (0.0, 0.0);
+    for &(d, m, mp, f, l, r) in MOON_LONGITUDE_DISTANCE.iter() {
+        let angle = argument(d, m, mp, f);
+        sum_l += l * damping(m) * sin_deg(angle);
+        sum_r += r * damping(m) * cos_deg(angle);
+    }
+    let mut sum_b = 0.0;
+    for &(d, m, mp, f, b) in MOON_LATITUDE.iter() {
+        sum_b += b * damping(m) * sin_deg(argument(d, m, mp, f));
+    }
+    sum_l += 3958.0 * sin_deg(a1) + 1962.0 * sin_deg(mean_longitude - latitude_argument) + 318.0 * sin_deg(a2);
+    sum_b += -2235.0 * sin_deg(mean_longitude)
+        + 382.0 * sin_deg(a3)
+        + 175.0 * sin_deg(a1 - latitude_argument)
+        + 175.0 * sin_deg(a1 + latitude_argument)
+        + 127.0 * sin_deg(mean_longitude - moon_anomaly)
+        - 115.0 * sin_deg(mean_longitude + moon_anomaly);
+
+    (
+        (mean_longitude + sum_l / 1_000_000.0).rem_euclid(360.0),
+        sum_b / 1_000_000.0,
+        385_000.56 + sum_r / 1000.0,
+    )
+}
+
+fn moon_equatorial(jd: f64) -> Equatorial {
+    let (longitude, latitude, distance_km) = moon_ecliptic(jd);
+    let (nutation_longitude, obliquity) = nutation(centuries_since_j2000(jd));
+    to_equatorial(longitude + nutation_longitude, latitude, obliquity, distance_km)
+}
+
+// Greenwich mean sidereal time in degrees (chapter 12).
+fn sidereal_time(jd: f64) -> f64 {
+    let t = centuries_since_j2000(jd);
+    (280.46061837 + 360.98564736629 * (jd - 2_451_545.0) + 0.000387933 * t * t - t * t * t / 38_710_000.0)
+        .rem_euclid(360.0)
+}
+
+fn hour_angle(jd: f64, longitude: f64, body: Equatorial) -> f64 {
+    (sidereal_time(jd) + longitude - body.right_ascension + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn horizontal(jd: f64, coordinates: [f64; 2], body: Equatorial) -> SkyPosition {
+    let [lat, lon] = coordinates;
+    let h = hour_angle(jd, lon, body);
+    let elevation = (sin_deg(lat) * sin_deg(body.declination) + cos_deg(lat) * cos_deg(body.declination) * cos_deg(h))
+        .asin()
+        .to_degrees();
+    let azimuth = sin_deg(h)
+        .atan2(cos_deg(h) * sin_deg(lat) - body.declination.to_radians().tan() * cos_deg(lat))
+        .to_degrees();
+    SkyPosition { azimuth: (azimuth + 180.0).rem_euclid(360.0), elevation }
+}
+
+pub fn sun_position(at: DateTime<Utc>, coordinates: [f64; 2]) -> SkyPosition {
+    let jd = julian_day(at);
+    horizontal(jd, coordinates, sun_equatorial(jd))
+}
+
+pub fn moon_position(at: DateTime<Utc>, coordinates: [f64; 2]) -> SkyPosition {
+    let jd = julian_day(at);
+    horizontal(jd, coordinates, moon_equatorial(jd))
+}
+
+pub fn moon_illumination(at: DateTime<Utc>) -> MoonIllumination {
+    let jd = julian_day(at);
+    let (sun_longitude, sun_distance) = sun_ecliptic(jd);
+    let (moon_longitude, moon_latitude, moon_distance) = moon_ecliptic(jd);
+    let elongation = (moon_longitude - sun_longitude).rem_euclid(360.0);
+    // Sun-moon angle as seen from earth, then the phase angle at the moon
+    // (chapter 48).
+    let separation = (cos_deg(moon_latitude) * cos_deg(elongation)).acos();
+    let phase_angle = (sun_distance * separation.sin()).atan2(moon_distance - sun_distance * separation.cos());
+    const PHASES: [MoonPhase; 8] = [
+        MoonPhase::New,
+        MoonPhase::WaxingCrescent,
+        MoonPhase::FirstQuarter,
+        MoonPhase::WaxingGibbous,
+        MoonPhase::Full,
+        MoonPhase::WaningGibbous,
+        MoonPhase::LastQuarter,
+        MoonPhase::WaningCrescent,
+    ];
+    MoonIllumination {
+        phase: PHASES[((elongation + 22.5) / 45.0) as usize % 8],
+        fraction: (1.0 + phase_angle.cos()) / 2.0,
+        age_days: elongation / 360.0 * SYNODIC_MONTH_DAYS,
+    }
+}
+
+// Midnight to midnight of the calendar day `at` falls on in `tz`, in UTC.
+// A day with a clock change is 23 or 25 hours long.
+pub fn local_day<T: TimeZone>(at: DateTime<Utc>, tz: &T) -> (DateTime<Utc>, DateTime<Utc>) {
+    let date = at.with_timezone(tz).date_naive();
+    let midnight = |date: NaiveDate| {
+        let naive = date.and_hms_opt(0, 0, 0).expect("midnight exists");
+        tz.from_local_datetime(&naive)
+            .earliest()
+            .map(|local| local.with_timezone(&Utc))
+            // A zone that skips midnight itself; an hour either way is close enough.
+            .unwrap_or_else(|| Utc.from_utc_datetime(&naive))
+    };
+    (midnight(date), midnight(date + Duration::days(1)))
+}
+
+// Every time in [start, end) where `f` crosses zero, with the direction it
+// crossed in (true for rising).
+fn crossings(start: DateTime<Utc>, end: DateTime<Utc>, f: impl Fn(DateTime<Utc>) -> f64) -> Vec<(DateTime<Utc>, bool)> {
+    let step = Duration::minutes(SCAN_STEP_MIN);
+    let mut found = Vec::new();
+    let mut before = start;
+    let mut value_before = f(before);
+    while before < end {
+        let after = (before + step).min(end);
+        let value_after = f(after);
+        if (value_before < 0.0) != (value_after < 0.0) {
+            let rising = value_after >= 0.0;
+            let (mut low, mut high) = (before, after);
+            while high - low > Duration::seconds(1) {
+                let middle = low + (high - low) / 2;
+                if (f(middle) >= 0.0) == rising {
+                    high = middle;
+                } else {
+                    low = middle;
+                }
+            }
+            found.push((high, rising));
+        }
+        before = after;
+        value_before = value_after;
+    }
+    found
+}
+
+fn first(events: &[(DateTime<Utc>, bool)], rising: bool) -> Option<DateTime<Utc>> {
+    events.iter().find(|(_, r)| *r == rising).map(|(at, _)| *at)
+}
+
+// Sunrise, sunset, twilight and solar noon over one local day, as given by
+// `local_day`. Anything that doesn't happen that day is None.
+pub fn sun_times(day: (DateTime<Utc>, DateTime<Utc>), coordinates: [f64; 2]) -> SunTimes {
+    let (start, end) = day;
+    let elevation = |at: DateTime<Utc>| sun_position(at, coordinates).elevation;
+    let events_at = |altitude: f64| crossings(start, end, |at| elevation(at) - altitude);
+    let (nautical, civil, horizon) = (events_at(NAUTICAL_ALTITUDE), events_at(CIVIL_ALTITUDE), events_at(SUNRISE_ALTITUDE));
+    // Noon is when the sun crosses the meridian, going from east to west.
+    let noon = crossings(start, end, |at| {
+        let jd = julian_day(at);
+        hour_angle(jd, coordinates[1], sun_equatorial(jd))
+    });
+    SunTimes {
+        nautical_dawn: first(&nautical, true),
+        civil_dawn: first(&civil, true),
+        sunrise: first(&horizon, true),
+        solar_noon: first(&noon, true),
+        sunset: first(&horizon, false),
+        civil_dusk: first(&civil, false),
+        nautical_dusk: first(&nautical, false),
+        up_at_start: elevation(start) > SUNRISE_ALTITUDE,
+    }
+}
+
+pub fn moon_times(day: (DateTime<Utc>, DateTime<Utc>), coordinates: [f64; 2]) -> MoonTimes {
+    let (start, end) = day;
+    // The moon is close enough for parallax to matter: it rises when its
+    // centre is a little above the geometric horizon (chapter 15).
+    let above_horizon = |at: DateTime<Utc>| {
+        let jd = julian_day(at);
+        let moon = moon_equatorial(jd);
+        let parallax = (EARTH_RADIUS_KM / moon.distance_km).asin().to_degrees();
+        horizontal(jd, coordinates, moon).elevation - (0.7275 * parallax - 0.5667)
+    };
+    let events = crossings(start, end, above_horizon);
+    MoonTimes {
+        moonrise: first(&events, true),
+        moonset: first(&events, false),
+        up_at_start: above_horizon(start) > 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{America, Europe};
+
+    const LONDON: [f64; 2] = [51.5074, -0.1278];
+    const NEW_YORK: [f64; 2] = [40.7128, -74.0060];
+    const TROMSO: [f64; 2] = [69.6492, 18.9553];
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn julian(days: f64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(((days - 2_440_587.5) * 86_400_000.0).round() as i64).unwrap()
+    }
+
+    fn assert_close(got: f64, want: f64, tolerance: f64) {
+        assert!((got - want).abs() <= tolerance, "got {}, want {} ± {}", got, want, tolerance);
+    }
+
+    fn assert_local_time<T: TimeZone>(got: Option<DateTime<Utc>>, tz: &T, want: &str) {
+        let got = got.expect("event should happen that day");
+        let want = NaiveTime::parse_from_str(want, "%H:%M").unwrap();
+        let minutes = (got.with_timezone(tz).time() - want).num_seconds() as f64 / 60.0;
+        assert!(minutes.abs() <= 2.0, "got {} UTC, want {} local", got, want);
+    }
+
+    #[test]
+    fn julian_day_and_sidereal_time_match_meeus() {
+        // Examples 7.a and 12.a.
+        assert_close(julian_day(utc(1957, 10, 4, 0, 0)) + 0.81, 2_436_116.31, 1e-6);
+        assert_close(sidereal_time(2_446_895.5), 197.693195, 1e-5);
+    }
+
+    #[test]
+    fn sun_matches_meeus_example_25a() {
+        let sun = sun_equatorial(2_448_908.5);
+        assert_close(sun.right_ascension, 198.38083, 0.001);
+        assert_close(sun.declination, -7.78507, 0.001);
+    }
+
+    #[test]
+    fn moon_matches_meeus_example_47a() {
+        let (longitude, latitude, distance) = moon_ecliptic(2_448_724.5);
+        assert_close(longitude, 133.162655, 0.005);
+        assert_close(latitude, -3.229126, 0.005);
+        // The dropped small terms add up to a few km here, which moves the
+        // parallax by a hundredth of an arcminute.
+        assert_close(distance, 368_409.7, 20.0);
+        let moon = moon_equatorial(2_448_724.5);
+        assert_close(moon.right_ascension, 134.688470, 0.01);
+        assert_close(moon.declination, 13.768368, 0.01);
+    }
+
+    #[test]
+    fn illumination_matches_meeus_and_published_phases() {
+        // Example 48.a.
+        assert_close(moon_illumination(julian(2_448_724.5)).fraction, 0.6786, 0.002);
+
+        // New, first quarter and full moon of April 2024.
+        let new = moon_illumination(utc(2024, 4, 8, 18, 21));
+        assert_eq!(new.phase, MoonPhase::New);
+        assert!(new.fraction < 0.002 && new.age_days.min(SYNODIC_MONTH_DAYS - new.age_days) < 0.1, "{:?}", new);
+        let quarter = moon_illumination(utc(2024, 4, 15, 19, 13));
+        assert_eq!(quarter.phase, MoonPhase::FirstQuarter);
+        assert_close(quarter.fraction, 0.5, 0.01);
+        let full = moon_illumination(utc(2024, 4, 23, 23, 49));
+        assert_eq!(full.phase, MoonPhase::Full);
+        assert!(full.fraction > 0.998, "{:?}", full);
+        assert_eq!(moon_illumination(utc(2024, 4, 27, 12, 0)).phase, MoonPhase::WaningGibbous);
+    }
+
+    #[test]
+    fn sunrise_and_sunset_match_published_tables() {
+        let summer = sun_times(local_day(utc(2024, 6, 20, 12, 0), &Europe::London), LONDON);
+        assert_local_time(summer.sunrise, &Europe::London, "04:43");
+        assert_local_time(summer.sunset, &Europe::London, "21:21");
+        assert_local_time(summer.solar_noon, &Europe::London, "13:02");
+
+        let winter = sun_times(local_day(utc(2024, 12, 21, 12, 0), &Europe::London), LONDON);
+        assert_local_time(winter.sunrise, &Europe::London, "08:04");
+        assert_local_time(winter.sunset, &Europe::London, "15:53");
+
+        let new_york = sun_times(local_day(utc(2024, 6, 20, 12, 0), &America::New_York), NEW_YORK);
+        assert_local_time(new_york.sunrise, &America::New_York, "05:25");
+        assert_local_time(new_york.sunset, &America::New_York, "20:31");
+    }
+
+    #[test]
+    fn twilight_brackets_the_day() {
+        let day = sun_times(local_day(utc(2024, 3, 20, 12, 0), &America::New_York), NEW_YORK);
+        let order = [day.nautical_dawn, day.civil_dawn, day.sunrise, day.solar_noon, day.sunset, day.civil_dusk, day.nautical_dusk];
+        let order: Vec<DateTime<Utc>> = order.iter().map(|t| t.expect("every event happens at the equinox")).collect();
+        assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", order);
+        // Civil twilight lasts about 25 minutes at this latitude around the equinox.
+        let civil = (order[2] - order[1]).num_minutes();
+        assert!((22..=30).contains(&civil), "{}", civil);
+    }
+
+    #[test]
+    fn polar_days_have_no_sunrise() {
+        let summer = sun_times(local_day(utc(2024, 6, 21, 12, 0), &Europe::Oslo), TROMSO);
+        assert_eq!((summer.sunrise, summer.sunset), (None, None));
+        assert!(summer.up_at_start);
+        let winter = sun_times(local_day(utc(2024, 12, 21, 12, 0), &Europe::Oslo), TROMSO);
+        assert_eq!((winter.sunrise, winter.sunset), (None, None));
+        assert!(!winter.up_at_start);
+        // Civil twilight still happens at midday in the polar night.
+        assert!(winter.civil_dawn.is_some());
+    }
+
+    #[test]
+    fn sun_position_at_noon_faces_south() {
+        let noon = sun_times(local_day(utc(2024, 6, 20, 12, 0), &Europe::London), LONDON).solar_noon.unwrap();
+        let sun = sun_position(noon, LONDON);
+        assert_close(sun.azimuth, 180.0, 0.1);
+        // 90 - latitude + declination at the solstice.
+        assert_close(sun.elevation, 90.0 - 51.5074 + 23.44, 0.05);
+    }
+
+    #[test]
+    fn rise_and_set_search_matches_meeus_example_15a() {
+        // Venus from Boston on 1988 March 20, from the example's positions
+        // for 0h TD on the 19th, 20th and 21st, interpolated as in chapter 3.
+        // Meeus has it rising at 12:25:26 and setting at 02:54:26 UT.
+        const BOSTON: [f64; 2] = [42.3333, -71.0833];
+        let table = [[40.68021, 18.04761], [41.73129, 18.44092], [42.78204, 18.82742]];
+        let start = utc(1988, 3, 20, 0, 0);
+        let venus = |at: DateTime<Utc>| {
+            // Days from the middle row, in dynamical time 56 s ahead of UT.
+            let n = julian_day(at) - julian_day(start) + 56.0 / 86_400.0;
+            let interpolate = |i: usize| {
+                let (a, b) = (table[1][i] - table[0][i], table[2][i] - table[1][i]);
+                table[1][i] + n / 2.0 * (a + b + n * (b - a))
+            };
+            Equatorial { right_ascension: interpolate(0), declination: interpolate(1), distance_km: 0.0 }
+        };
+        let events = crossings(start, start + Duration::days(1), |at| {
+            horizontal(julian_day(at), BOSTON, venus(at)).elevation + 0.5667
+        });
+
+        let off_by = |got: Option<DateTime<Utc>>, want: DateTime<Utc>| (got.unwrap() - want).num_seconds().abs();
+        assert!(off_by(first(&events, true), utc(1988, 3, 20, 12, 25) + Duration::seconds(26)) <= 30, "{:?}", events);
+        assert!(off_by(first(&events, false), utc(1988, 3, 20, 2, 54) + Duration::seconds(26)) <= 30, "{:?}", events);
+    }
+
+    // Apparent altitude of the moon's upper limb, worked out the long way:
+    // topocentric place from chapter 40, the true semidiameter and 34' of
+    // refraction, rather than the single h0 correction moon_times uses.
+    fn moon_upper_limb(at: DateTime<Utc>, coordinates: [f64; 2]) -> f64 {
+        let jd = julian_day(at);
+        let moon = moon_equatorial(jd);
+        let [lat, lon] = coordinates;
+        let u = (0.99664719 * lat.to_radians().tan()).atan();
+        let (rho_sin, rho_cos) = (0.99664719 * u.sin(), u.cos());
+        let sin_parallax = EARTH_RADIUS_KM / moon.distance_km;
+        let h = hour_angle(jd, lon, moon);
+        let shift = (-rho_cos * sin_parallax * sin_deg(h))
+            .atan2(cos_deg(moon.declination) - rho_cos * sin_parallax * cos_deg(h))
+            .to_degrees();
+        let declination = ((sin_deg(moon.declination) - rho_sin * sin_parallax) * cos_deg(shift))
+            .atan2(cos_deg(moon.declination) - rho_cos * sin_parallax * cos_deg(h))
+            .to_degrees();
+        let topocentric = Equatorial { right_ascension: moon.right_ascension + shift, declination, distance_km: moon.distance_km };
+        let semidiameter = 358_473_400.0 / moon.distance_km / 3600.0;
+        horizontal(jd, coordinates, topocentric).elevation + semidiameter + 34.0 / 60.0
+    }
+
+    #[test]
+    fn moonrise_and_moonset_put_the_upper_limb_on_the_horizon() {
+        // A full moon, a waxing crescent far north of the equator and a
+        // waning one far south of it, in both hemispheres.
+        let cases = [
+            (utc(2024, 4, 24, 12, 0), LONDON),
+            (utc(2024, 6, 10, 12, 0), LONDON),
+            (utc(2024, 9, 27, 12, 0), NEW_YORK),
+            (utc(2024, 4, 24, 12, 0), [-33.8688, 151.2093]),
+        ];
+        let two_minutes = Duration::minutes(2);
+        for (at, place) in cases {
+            let moon = moon_times(local_day(at, &Utc), place);
+            let (rise, set) = (moon.moonrise.expect("moonrise"), moon.moonset.expect("moonset"));
+            assert!(moon_upper_limb(rise - two_minutes, place) < 0.0 && moon_upper_limb(rise + two_minutes, place) > 0.0, "rise {} at {:?}", rise, place);
+            assert!(moon_upper_limb(set - two_minutes, place) > 0.0 && moon_upper_limb(set + two_minutes, place) < 0.0, "set {} at {:?}", set, place);
+        }
+    }
+}
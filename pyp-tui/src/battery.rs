@@ -0,0 +1,314 @@
+use hal::gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+use hal::i2cdev::core::I2CDevice;
+use hal::i2cdev::linux::LinuxI2CDevice;
+use std::error::Error as StdError;
+use std::io;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::Event;
+
+const GPIO_CHIP: &str = "/dev/gpiochip0";
+// PowerBoost 1000 LBO pin, pulled low once the cell drops below ~3.2V.
+const LBO_PIN: u32 = 16;
+const I2C_DEVICE: &str = "/dev/i2c-1";
+const FUEL_GAUGE_ADDR: u16 = 0x36;
+
+const BATTERY_MAH: f32 = 10050.0;
+// Rough draw of a Pi Zero 2 plus the HDMI panel through the boost converter.
+const NOMINAL_DRAW_MA: f32 = 900.0;
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const LBO_GRACE: Duration = Duration::from_secs(120);
+
+const LOW_PERCENT: u8 = 15;
+const CRITICAL_PERCENT: u8 = 5;
+// One bad I2C read shouldn't shut the device down, so the gauge has to say
+// critical this many times running.
+const CRITICAL_READINGS: u32 = 3;
+// Time to plug in a charger between the warning and the power going off.
+pub const SHUTDOWN_COUNTDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BatteryLevel {
+    Ok,
+    Low,
+    Critical,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BatteryStatus {
+    pub percent: Option<u8>,
+    pub voltage: Option<f32>,
+    pub low_battery_pin: bool,
+    pub runtime: Option<Duration>,
+    pub level: BatteryLevel,
+}
+
+impl BatteryStatus {
+    pub fn summary(&self) -> String {
+        let charge = match self.percent {
+            Some(p) => format!("{}%", p),
+            None if self.low_battery_pin => "LOW".to_string(),
+            None => "OK".to_string(),
+        };
+        match self.runtime {
+            Some(runtime) => {
+                let minutes = runtime.as_secs() / 60;
+                format!("BAT {} ~{}h{:02}m", charge, minutes / 60, minutes % 60)
+            }
+            None => format!("BAT {}", charge),
+        }
+    }
+}
+
+pub struct RawBatteryReading {
+    pub percent: Option<f32>,
+    pub voltage: Option<f32>,
+    pub low_battery_pin: bool,
+}
+
+pub trait BatteryBackend: Send {
+    fn read(&mut self) -> Result<RawBatteryReading, Box<dyn StdError>>;
+}
+
+pub struct PowerBoostBackend {
+    lbo: LineHandle,
+    gauge: Option<LinuxI2CDevice>,
+}
+
+impl PowerBoostBackend {
+    pub fn new() -> Result<Self, Box<dyn StdError>> {
+        let lbo = Chip::new(GPIO_CHIP)?
+            .get_line(LBO_PIN)?
+            .request(LineRequestFlags::INPUT, 1, "pyp-tui-lbo")?;
+        // The fuel gauge is optional; probe its VERSION register to see if one answers.
+        let gauge = LinuxI2CDevice::new(I2C_DEVICE, FUEL_GAUGE_ADDR)
+            .ok()
+            .and_then(|mut gauge| {
+                Self::read_gauge_register(&mut gauge, 0x08)?;
+                Some(gauge)
+            });
+        Ok(PowerBoostBackend { lbo, gauge })
+    }
+
+    fn read_gauge_register(gauge: &mut LinuxI2CDevice, register: u8) -> Option<u16> {
+        let mut buf = [0u8; 2];
+        gauge.write(&[register]).ok()?;
+        gauge.read(&mut buf).ok()?;
+        Some(u16::from_be_bytes(buf))
+    }
+}
+
+impl BatteryBackend for PowerBoostBackend {
+    fn read(&mut self) -> Result<RawBatteryReading, Box<dyn StdError>> {
+        let low_battery_pin = self.lbo.get_value()? == 0;
+
+        // MAX17048: VCELL at 0x02 (78.125uV/LSB), SOC at 0x04 (1/256 % per LSB).
+        let (voltage, percent) = match self.gauge.as_mut() {
+            Some(gauge) => (
+                Self::read_gauge_register(gauge, 0x02).map(|v| v as f32 * 78.125 / 1_000_000.0),
+                Self::read_gauge_register(gauge, 0x04).map(|v| (v as f32 / 256.0).min(100.0)),
+            ),
+            None => (None, None),
+        };
+
+        Ok(RawBatteryReading { percent, voltage, low_battery_pin })
+    }
+}
+
+// Drains a virtual cell so the warnings and shutdown path can be exercised
+// on a desktop without the PowerBoost attached.
+pub struct SimulatedBackend {
+    percent: f32,
+    drain_per_read: f32,
+}
+
+impl SimulatedBackend {
+    pub fn new(start_percent: f32, drain_per_read: f32) -> Self {
+        SimulatedBackend { percent: start_percent, drain_per_read }
+    }
+}
+
+impl BatteryBackend for SimulatedBackend {
+    fn read(&mut self) -> Result<RawBatteryReading, Box<dyn StdError>> {
+        self.percent = (self.percent - self.drain_per_read).max(0.0);
+        Ok(RawBatteryReading {
+            percent: Some(self.percent),
+            voltage: Some(3.2 + self.percent / 100.0),
+            low_battery_pin: self.percent < 3.0,
+        })
+    }
+}
+
+pub struct BatteryTracker {
+    first: Option<(Instant, f32)>,
+    lbo_since: Option<Instant>,
+    critical_readings: u32,
+}
+
+impl BatteryTracker {
+    pub fn new() -> Self {
+        BatteryTracker { first: None, lbo_since: None, critical_readings: 0 }
+    }
+
+    pub fn update(&mut self, raw: &RawBatteryReading, now: Instant) -> BatteryStatus {
+        if raw.low_battery_pin {
+            self.lbo_since.get_or_insert(now);
+        } else {
+            self.lbo_since = None;
+        }
+        let lbo_expired = self.lbo_since.is_some_and(|since| now.duration_since(since) >= LBO_GRACE);
+
+        let percent = raw.percent.map(|p| p.round() as u8);
+        if percent.is_some_and(|p| p <= CRITICAL_PERCENT) {
+            self.critical_readings += 1;
+        } else {
+            self.critical_readings = 0;
+        }
+        let level = match percent {
+            _ if lbo_expired => BatteryLevel::Critical,
+            _ if self.critical_readings >= CRITICAL_READINGS => BatteryLevel::Critical,
+            Some(p) if p <= LOW_PERCENT => BatteryLevel::Low,
+            _ if raw.low_battery_pin => BatteryLevel::Low,
+            _ => BatteryLevel::Ok,
+        };
+
+        let runtime = raw.percent.map(|p| {
+            let first = *self.first.get_or_insert((now, p));
+            let elapsed = now.duration_since(first.0).as_secs_f32();
+            let dropped = first.1 - p;
+            if elapsed > 600.0 && dropped > 0.5 {
+                Duration::from_secs_f32(p / dropped * elapsed)
+            } else {
+                Duration::from_secs_f32(BATTERY_MAH * p / 100.0 / NOMINAL_DRAW_MA * 3600.0)
+            }
+        });
+
+        BatteryStatus {
+            percent,
+            voltage: raw.voltage,
+            low_battery_pin: raw.low_battery_pin,
+            runtime,
+            level,
+        }
+    }
+}
+
+pub fn spawn_battery_monitor<I: Send + 'static>(tx: Sender<Event<I>>, simulated: bool, enabled: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        let mut backend: Box<dyn BatteryBackend> = if simulated {
+            Box::new(SimulatedBackend::new(40.0, 0.5))
+        } else {
+            match PowerBoostBackend::new() {
+                Ok(backend) => Box::new(backend),
+                Err(_) => return,
+            }
+        };
+        let mut tracker = BatteryTracker::new();
+
+        loop {
+            if !enabled.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+            if let Ok(raw) = backend.read() {
+                let status = tracker.update(&raw, Instant::now());
+                if tx.send(Event::Battery(status)).is_err() {
+                    return;
+                }
+            }
+            thread::sleep(if simulated { Duration::from_secs(1) } else { POLL_INTERVAL });
+        }
+    });
+}
+
+pub fn power_off() -> io::Result<()> {
+    Command::new("systemctl").arg("poweroff").status().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(backend: &mut dyn BatteryBackend, tracker: &mut BatteryTracker, reads: usize) -> Vec<(u8, BatteryLevel)> {
+        let start = Instant::now();
+        (0..reads)
+            .map(|i| {
+                let raw = backend.read().unwrap();
+                let status = tracker.update(&raw, start + POLL_INTERVAL * i as u32);
+                (status.percent.unwrap(), status.level)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn simulated_cell_drains_through_the_levels() {
+        let mut backend = SimulatedBackend::new(18.0, 1.0);
+        let mut tracker = BatteryTracker::new();
+        let seen = levels(&mut backend, &mut tracker, 16);
+
+        assert_eq!(seen[0], (17, BatteryLevel::Ok));
+        assert_eq!(seen[2], (15, BatteryLevel::Low));
+        // 5% is critical by the gauge, but only once it's said so three times.
+        assert_eq!(seen[12], (5, BatteryLevel::Low));
+        assert_eq!(seen[13], (4, BatteryLevel::Low));
+        assert_eq!(seen[14], (3, BatteryLevel::Critical));
+        // The simulated LBO pin drops below 3%, which doesn't undo it.
+        assert_eq!(seen[15], (2, BatteryLevel::Critical));
+    }
+
+    #[test]
+    fn one_bad_gauge_read_is_not_critical() {
+        let mut tracker = BatteryTracker::new();
+        let now = Instant::now();
+        let reading = |percent: f32| RawBatteryReading { percent: Some(percent), voltage: None, low_battery_pin: false };
+
+        assert_eq!(tracker.update(&reading(60.0), now).level, BatteryLevel::Ok);
+        assert_eq!(tracker.update(&reading(0.0), now).level, BatteryLevel::Low);
+        assert_eq!(tracker.update(&reading(0.0), now).level, BatteryLevel::Low);
+        assert_eq!(tracker.update(&reading(60.0), now).level, BatteryLevel::Ok);
+        assert_eq!(tracker.update(&reading(0.0), now).level, BatteryLevel::Low);
+    }
+
+    #[test]
+    fn lbo_pin_alone_goes_critical_after_its_grace() {
+        let mut tracker = BatteryTracker::new();
+        let start = Instant::now();
+        let low = RawBatteryReading { percent: None, voltage: None, low_battery_pin: true };
+
+        let status = tracker.update(&low, start);
+        assert_eq!((status.level, status.summary()), (BatteryLevel::Low, "BAT LOW".to_string()));
+        assert_eq!(tracker.update(&low, start + LBO_GRACE - Duration::from_secs(1)).level, BatteryLevel::Low);
+        assert_eq!(tracker.update(&low, start + LBO_GRACE).level, BatteryLevel::Critical);
+
+        // A blip back up restarts the grace period.
+        let ok = RawBatteryReading { low_battery_pin: false, ..low };
+        assert_eq!(tracker.update(&ok, start + LBO_GRACE).level, BatteryLevel::Ok);
+        assert_eq!(tracker.update(&low, start + LBO_GRACE * 2).level, BatteryLevel::Low);
+    }
+
+    #[test]
+    fn runtime_switches_to_the_measured_drain() {
+        let mut backend = SimulatedBackend::new(51.0, 1.0);
+        let mut tracker = BatteryTracker::new();
+        let start = Instant::now();
+
+        // Until there's ten minutes of history, the nominal draw is all there is.
+        let status = tracker.update(&backend.read().unwrap(), start);
+        let nominal = BATTERY_MAH * 0.5 / NOMINAL_DRAW_MA * 3600.0;
+        assert!((status.runtime.unwrap().as_secs_f32() - nominal).abs() < 1.0);
+        assert_eq!(status.summary(), "BAT 50% ~5h35m");
+
+        // 1% per minute from then on leaves 39 minutes at 39%.
+        for _ in 0..10 {
+            backend.read().unwrap();
+        }
+        let status = tracker.update(&backend.read().unwrap(), start + Duration::from_secs(660));
+        assert_eq!(status.percent, Some(39));
+        assert!((status.runtime.unwrap().as_secs_f32() - 39.0 * 60.0).abs() < 1.0);
+    }
+}
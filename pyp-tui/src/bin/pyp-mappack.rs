@@ -0,0 +1,824 @@
+// Packs an OSM extract into the tiled map file the MAP tab reads. Runs on a
+// desktop; copy the output to data/map.pypmap on the SD card.
+use flate2::read::ZlibDecoder;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::process;
+
+use pyp_tui::mapdata::{FeatureKind, MapData, MAGIC, MICRODEGREES};
+
+const USAGE: &str = "usage: pyp-mappack <extract.osm.pbf|extract.geojson> --bbox SOUTH,WEST,NORTH,EAST \
+[--tile DEGREES] [--buildings] [-o OUTPUT]";
+const DEFAULT_OUTPUT: &str = "./data/map.pypmap";
+// About a kilometre; small enough that a screen only decodes a handful.
+const DEFAULT_TILE_DEG: f64 = 0.01;
+
+const ROADS: [&str; 18] = [
+    "motorway", "trunk", "primary", "secondary", "tertiary", "unclassified", "residential", "service",
+    "living_street", "road", "motorway_link", "trunk_link", "primary_link", "secondary_link", "tertiary_link",
+    "busway", "raceway", "construction",
+];
+const PATHS: [&str; 8] = ["footway", "path", "track", "cycleway", "bridleway", "steps", "pedestrian", "via_ferrata"];
+const WATERWAYS: [&str; 6] = ["river", "stream", "canal", "drain", "ditch", "riverbank"];
+const POI_KEYS: [&str; 7] = ["amenity", "shop", "tourism", "historic", "leisure", "place", "natural"];
+// The PBF spec's limits. Sizes come straight from the file, so they're checked
+// before anything is allocated for them.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const MAX_BLOB_BYTES: usize = 32 * 1024 * 1024;
+
+struct Options {
+    input: String,
+    output: String,
+    bbox: [f64; 4],
+    tile_deg: f64,
+    buildings: bool,
+}
+
+fn parse_args() -> Result<Options, Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let mut input = None;
+    let mut output = DEFAULT_OUTPUT.to_string();
+    let mut bbox = None;
+    let mut tile_deg = DEFAULT_TILE_DEG;
+    let mut buildings = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bbox" => {
+                let parts: Vec<f64> = args
+                    .next()
+                    .ok_or("--bbox needs a value")?
+                    .split(',')
+                    .map(|p| p.trim().parse())
+                    .collect::<Result<_, _>>()?;
+                let [south, west, north, east] = parts[..] else {
+                    return Err("--bbox takes SOUTH,WEST,NORTH,EAST".into());
+                };
+                if south >= north || west >= east {
+                    return Err("--bbox must go south-west to north-east".into());
+                }
+                bbox = Some([south, west, north, east]);
+            }
+            "--tile" => tile_deg = args.next().ok_or("--tile needs a value")?.parse()?,
+            "--buildings" => buildings = true,
+            "-o" | "--output" => output = args.next().ok_or("-o needs a value")?,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if input.is_none() && !arg.starts_with('-') => input = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg).into()),
+        }
+    }
+
+    if !tile_deg.is_finite() || tile_deg <= 0.0 {
+        return Err("--tile must be positive".into());
+    }
+    Ok(Options {
+        input: input.ok_or(USAGE)?,
+        output,
+        bbox: bbox.ok_or("--bbox is required")?,
+        tile_deg,
+        buildings,
+    })
+}
+
+fn classify<'a>(tag: impl Fn(&str) -> Option<&'a str>, is_point: bool, buildings: bool) -> Option<FeatureKind> {
+    if is_point {
+        let named = tag("name").is_some();
+        return (named && POI_KEYS.iter().any(|key| tag(key).is_some())).then_some(FeatureKind::Poi);
+    }
+    if let Some(highway) = tag("highway") {
+        if ROADS.contains(&highway) {
+            return Some(FeatureKind::Road);
+        }
+        if PATHS.contains(&highway) {
+            return Some(FeatureKind::Path);
+        }
+    }
+    let is_water = tag("waterway").is_some_and(|w| WATERWAYS.contains(&w))
+        || matches!(tag("natural"), Some("water" | "coastline"))
+        || tag("landuse") == Some("reservoir");
+    if is_water {
+        return Some(FeatureKind::Water);
+    }
+    if buildings && tag("building").is_some_and(|b| b != "no") {
+        return Some(FeatureKind::Building);
+    }
+    None
+}
+
+fn to_micro(degrees: f64) -> i32 {
+    (degrees * MICRODEGREES).round() as i32
+}
+
+// Kind, optional name and points in microdegrees.
+type PackedFeature = (FeatureKind, Option<String>, Vec<[i32; 2]>);
+
+struct TileGrid {
+    origin: [i32; 2],
+    size: i32,
+    cols: u16,
+    rows: u16,
+    tiles: Vec<Vec<PackedFeature>>,
+}
+
+impl TileGrid {
+    fn new(bbox: [f64; 4], tile_deg: f64) -> Result<Self, Box<dyn Error>> {
+        // Whole microdegrees, so 0.02 / 0.01 comes out as two tiles, not three.
+        let size = to_micro(tile_deg).max(1);
+        let span = |from: f64, to: f64| ((to_micro(to) as i64 - to_micro(from) as i64) as f64 / size as f64).ceil();
+        let cols = span(bbox[1], bbox[3]);
+        let rows = span(bbox[0], bbox[2]);
+        if cols * rows > 1_000_000.0 || cols > u16::MAX as f64 || rows > u16::MAX as f64 {
+            return Err("too many tiles; use a bigger --tile or a smaller --bbox".into());
+        }
+        Ok(TileGrid {
+            origin: [to_micro(bbox[0]), to_micro(bbox[1])],
+            size,
+            cols: cols as u16,
+            rows: rows as u16,
+            tiles: vec![Vec::new(); cols as usize * rows as usize],
+        })
+    }
+
+    fn cell(&self, micro: i32, axis: usize) -> i64 {
+        (micro as i64 - self.origin[axis] as i64).div_euclid(self.size as i64)
+    }
+
+    fn contains(&self, point: [i32; 2]) -> bool {
+        (0..self.rows as i64).contains(&self.cell(point[0], 0)) && (0..self.cols as i64).contains(&self.cell(point[1], 1))
+    }
+
+    fn add_point(&mut self, kind: FeatureKind, name: Option<String>, point: [i32; 2]) {
+        if self.contains(point) {
+            let tile = self.cell(point[0], 0) as usize * self.cols as usize + self.cell(point[1], 1) as usize;
+            self.tiles[tile].push((kind, name, vec![point]));
+        }
+    }
+
+    // Cuts a line into per-tile runs. A segment goes to every tile its bounding
+    // box touches, so nothing falls through the cracks at tile edges; the
+    // reader clips whatever pokes out.
+    fn add_line(&mut self, kind: FeatureKind, points: &[[i32; 2]]) {
+        let mut runs: HashMap<usize, Vec<[i32; 2]>> = HashMap::new();
+        for segment in points.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            if a == b {
+                continue;
+            }
+            let rows = self.cell(a[0].min(b[0]), 0).max(0)..=self.cell(a[0].max(b[0]), 0).min(self.rows as i64 - 1);
+            let cols = self.cell(a[1].min(b[1]), 1).max(0)..=self.cell(a[1].max(b[1]), 1).min(self.cols as i64 - 1);
+            for row in rows {
+                for col in cols.clone() {
+                    let tile = row as usize * self.cols as usize + col as usize;
+                    let run = runs.entry(tile).or_default();
+                    if run.last() != Some(&a) {
+                        if run.len() >= 2 {
+                            self.tiles[tile].push((kind, None, std::mem::take(run)));
+                        }
+                        run.clear();
+                        run.push(a);
+                    }
+                    run.push(b);
+                }
+            }
+        }
+        for (tile, run) in runs {
+            if run.len() >= 2 {
+                self.tiles[tile].push((kind, None, run));
+            }
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut blobs = Vec::new();
+        let mut index = Vec::new();
+        for (tile, features) in self.tiles.iter().enumerate() {
+            let start = blobs.len();
+            if !features.is_empty() {
+                let row = (tile / self.cols as usize) as i64;
+                let col = (tile % self.cols as usize) as i64;
+                let tile_origin = [
+                    self.origin[0] as i64 + row * self.size as i64,
+                    self.origin[1] as i64 + col * self.size as i64,
+                ];
+                put_varint(&mut blobs, features.len() as u64);
+                for (kind, name, points) in features {
+                    // Discriminants line up with FeatureKind::from_byte.
+                    blobs.push(*kind as u8);
+                    let name = name.as_deref().unwrap_or("");
+                    put_varint(&mut blobs, name.len() as u64);
+                    blobs.extend_from_slice(name.as_bytes());
+                    put_varint(&mut blobs, points.len() as u64);
+                    let mut last = tile_origin;
+                    for point in points {
+                        let point = [point[0] as i64, point[1] as i64];
+                        put_zigzag(&mut blobs, point[0] - last[0]);
+                        put_zigzag(&mut blobs, point[1] - last[1]);
+                        last = point;
+                    }
+                }
+            }
+            index.push((start as u32, (blobs.len() - start) as u32));
+        }
+
+        let mut out = Vec::with_capacity(24 + index.len() * 8 + blobs.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&self.origin[0].to_le_bytes());
+        out.extend_from_slice(&self.origin[1].to_le_bytes());
+        out.extend_from_slice(&(self.size as u32).to_le_bytes());
+        out.extend_from_slice(&self.cols.to_le_bytes());
+        out.extend_from_slice(&self.rows.to_le_bytes());
+        for (offset, len) in index {
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&len.to_le_bytes());
+        }
+        out.extend_from_slice(&blobs);
+        out
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_zigzag(out: &mut Vec<u8>, value: i64) {
+    put_varint(out, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+// Just enough protobuf to walk the OSM PBF messages.
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+struct Protobuf<'a> {
+    buf: &'a [u8],
+    at: usize,
+}
+
+fn malformed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed PBF")
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "PBF block larger than the format allows")
+}
+
+impl<'a> Protobuf<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Protobuf { buf, at: 0 }
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.at).ok_or_else(malformed)?;
+            self.at += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(malformed())
+    }
+
+    fn skip(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let end = self.at.checked_add(n).ok_or_else(malformed)?;
+        let bytes = self.buf.get(self.at..end).ok_or_else(malformed)?;
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn next_field(&mut self) -> io::Result<Option<(u64, Field<'a>)>> {
+        if self.at >= self.buf.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = match key & 7 {
+            0 => Field::Varint(self.varint()?),
+            1 => {
+                self.skip(8)?;
+                Field::Fixed
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Field::Bytes(self.skip(len)?)
+            }
+            5 => {
+                self.skip(4)?;
+                Field::Fixed
+            }
+            _ => return Err(malformed()),
+        };
+        Ok(Some((key >> 3, field)))
+    }
+}
+
+fn packed(bytes: &[u8]) -> io::Result<Vec<u64>> {
+    let mut reader = Protobuf::new(bytes);
+    let mut values = Vec::new();
+    while reader.at < bytes.len() {
+        values.push(reader.varint()?);
+    }
+    Ok(values)
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+// Delta-coded packed sint64, as used for dense node ids/coordinates and way refs.
+fn packed_deltas(bytes: &[u8]) -> io::Result<Vec<i64>> {
+    let mut total = 0i64;
+    Ok(packed(bytes)?
+        .into_iter()
+        .map(|v| {
+            total += unzigzag(v);
+            total
+        })
+        .collect())
+}
+
+struct Extract {
+    grid: TileGrid,
+    buildings: bool,
+    // Only nodes inside the box are kept; ways that leave it break into pieces.
+    nodes: HashMap<i64, [i32; 2]>,
+    counts: HashMap<&'static str, usize>,
+}
+
+impl Extract {
+    fn count(&mut self, kind: FeatureKind) {
+        let label = match kind {
+            FeatureKind::Road => "roads",
+            FeatureKind::Path => "paths",
+            FeatureKind::Water => "water",
+            FeatureKind::Building => "buildings",
+            FeatureKind::Poi => "points of interest",
+        };
+        *self.counts.entry(label).or_default() += 1;
+    }
+
+    fn node(&mut self, id: i64, point: [i32; 2], tags: &[(&str, &str)]) {
+        if !self.grid.contains(point) {
+            return;
+        }
+        self.nodes.insert(id, point);
+        let tag = |key: &str| tags.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+        if let Some(kind) = classify(tag, true, self.buildings) {
+            self.grid.add_point(kind, tag("name").map(str::to_string), point);
+            self.count(kind);
+        }
+    }
+
+    fn way(&mut self, refs: &[i64], tags: &[(&str, &str)]) {
+        let tag = |key: &str| tags.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+        let Some(kind) = classify(tag, false, self.buildings) else {
+            return;
+        };
+        let mut counted = false;
+        for piece in refs.split(|id| !self.nodes.contains_key(id)) {
+            let points: Vec<[i32; 2]> = piece.iter().map(|id| self.nodes[id]).collect();
+            if points.len() >= 2 {
+                self.grid.add_line(kind, &points);
+                counted = true;
+            }
+        }
+        if counted {
+            self.count(kind);
+        }
+    }
+}
+
+fn read_blob(blob: &[u8]) -> io::Result<Vec<u8>> {
+    let mut reader = Protobuf::new(blob);
+    let mut raw_size = 0;
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Field::Bytes(raw)) => return Ok(raw.to_vec()),
+            (2, Field::Varint(size)) => raw_size = size as usize,
+            (3, Field::Bytes(zlib)) => {
+                let mut data = Vec::with_capacity(raw_size.min(MAX_BLOB_BYTES));
+                ZlibDecoder::new(zlib).take(MAX_BLOB_BYTES as u64 + 1).read_to_end(&mut data)?;
+                if data.len() > MAX_BLOB_BYTES {
+                    return Err(too_large());
+                }
+                return Ok(data);
+            }
+            (4 | 6 | 7, Field::Bytes(_)) => {
+                return Err(io::Error::new(io::ErrorKind::Unsupported, "only raw and zlib PBF blobs are supported"))
+            }
+            _ => {}
+        }
+    }
+    Err(malformed())
+}
+
+fn read_primitive_block(block: &[u8], extract: &mut Extract) -> io::Result<()> {
+    let mut strings: Vec<String> = Vec::new();
+    let mut groups = Vec::new();
+    let (mut granularity, mut lat_offset, mut lon_offset) = (100i64, 0i64, 0i64);
+
+    let mut reader = Protobuf::new(block);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Field::Bytes(table)) => {
+                let mut table = Protobuf::new(table);
+                while let Some((field, value)) = table.next_field()? {
+                    if let (1, Field::Bytes(s)) = (field, value) {
+                        strings.push(String::from_utf8_lossy(s).into_owned());
+                    }
+                }
+            }
+            (2, Field::Bytes(group)) => groups.push(group),
+            (17, Field::Varint(v)) => granularity = v as i64,
+            (19, Field::Varint(v)) => lat_offset = v as i64,
+            (20, Field::Varint(v)) => lon_offset = v as i64,
+            _ => {}
+        }
+    }
+
+    let string = |index: u64| strings.get(index as usize).map(String::as_str).unwrap_or("");
+    // Nanodegrees to microdegrees.
+    let coordinate = |offset: i64, value: i64| ((offset + granularity * value) / 1000) as i32;
+
+    for group in groups {
+        let mut reader = Protobuf::new(group);
+        while let Some((field, value)) = reader.next_field()? {
+            let Field::Bytes(message) = value else {
+                continue;
+            };
+            let mut message_reader = Protobuf::new(message);
+            match field {
+                // Plain nodes are rare in the wild but still valid.
+                1 => {
+                    let (mut id, mut lat, mut lon) = (0, 0, 0);
+                    let (mut keys, mut vals) = (Vec::new(), Vec::new());
+                    while let Some((field, value)) = message_reader.next_field()? {
+                        match (field, value) {
+                            (1, Field::Varint(v)) => id = unzigzag(v),
+                            (2, Field::Bytes(b)) => keys = packed(b)?,
+                            (3, Field::Bytes(b)) => vals = packed(b)?,
+                            (8, Field::Varint(v)) => lat = unzigzag(v),
+                            (9, Field::Varint(v)) => lon = unzigzag(v),
+                            _ => {}
+                        }
+                    }
+                    let tags: Vec<(&str, &str)> = keys.iter().zip(&vals).map(|(&k, &v)| (string(k), string(v))).collect();
+                    extract.node(id, [coordinate(lat_offset, lat), coordinate(lon_offset, lon)], &tags);
+                }
+                2 => {
+                    let (mut ids, mut lats, mut lons, mut keys_vals) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+                    while let Some((field, value)) = message_reader.next_field()? {
+                        match (field, value) {
+                            (1, Field::Bytes(b)) => ids = packed_deltas(b)?,
+                            (8, Field::Bytes(b)) => lats = packed_deltas(b)?,
+                            (9, Field::Bytes(b)) => lons = packed_deltas(b)?,
+                            (10, Field::Bytes(b)) => keys_vals = packed(b)?,
+                            _ => {}
+                        }
+                    }
+                    // keys_vals runs key, value, key, value... with a 0 closing each node.
+                    let mut tag_stream = keys_vals.into_iter();
+                    for ((&id, &lat), &lon) in ids.iter().zip(&lats).zip(&lons) {
+                        let mut tags = Vec::new();
+                        while let Some(key) = tag_stream.next().filter(|&k| k != 0) {
+                            tags.push((string(key), string(tag_stream.next().unwrap_or(0))));
+                        }
+                        extract.node(id, [coordinate(lat_offset, lat), coordinate(lon_offset, lon)], &tags);
+                    }
+                }
+                3 => {
+                    let (mut keys, mut vals, mut refs) = (Vec::new(), Vec::new(), Vec::new());
+                    while let Some((field, value)) = message_reader.next_field()? {
+                        match (field, value) {
+                            (2, Field::Bytes(b)) => keys = packed(b)?,
+                            (3, Field::Bytes(b)) => vals = packed(b)?,
+                            (8, Field::Bytes(b)) => refs = packed_deltas(b)?,
+                            _ => {}
+                        }
+                    }
+                    let tags: Vec<(&str, &str)> = keys.iter().zip(&vals).map(|(&k, &v)| (string(k), string(v))).collect();
+                    extract.way(&refs, &tags);
+                }
+                // Relations (multipolygon lakes and the like) aren't packed.
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// Blob framing: a big-endian length, a BlobHeader naming the blob's type and
+// size, then the blob. Nodes come before ways in a sorted extract, which is
+// what lets this run in one pass.
+fn read_pbf(path: &str, extract: &mut Extract) -> Result<(), Box<dyn Error>> {
+    let mut file = BufReader::new(File::open(path)?);
+    loop {
+        let mut len = [0u8; 4];
+        match file.read_exact(&mut len) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            result => result?,
+        }
+        let header_len = u32::from_be_bytes(len) as usize;
+        if header_len > MAX_HEADER_BYTES {
+            return Err(too_large().into());
+        }
+        let mut header = vec![0u8; header_len];
+        file.read_exact(&mut header)?;
+
+        let (mut kind, mut size) = (String::new(), 0usize);
+        let mut reader = Protobuf::new(&header);
+        while let Some((field, value)) = reader.next_field()? {
+            match (field, value) {
+                (1, Field::Bytes(b)) => kind = String::from_utf8_lossy(b).into_owned(),
+                (3, Field::Varint(v)) => size = v as usize,
+                _ => {}
+            }
+        }
+        if size > MAX_BLOB_BYTES {
+            return Err(too_large().into());
+        }
+        let mut blob = vec![0u8; size];
+        file.read_exact(&mut blob)?;
+        if kind == "OSMData" {
+            read_primitive_block(&read_blob(&blob)?, extract)?;
+        }
+    }
+}
+
+fn read_geojson(path: &str, extract: &mut Extract) -> Result<(), Box<dyn Error>> {
+    let collection: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let features = collection["features"].as_array().ok_or("expected a GeoJSON FeatureCollection")?;
+    // GeoJSON positions are [lon, lat].
+    let point = |position: &Value| -> Option<[i32; 2]> {
+        Some([to_micro(position.get(1)?.as_f64()?), to_micro(position.get(0)?.as_f64()?)])
+    };
+    let line = |positions: &Value| -> Vec<[i32; 2]> {
+        positions.as_array().map(|ps| ps.iter().filter_map(point).collect()).unwrap_or_default()
+    };
+
+    for feature in features {
+        let properties = &feature["properties"];
+        let tag = |key: &str| properties[key].as_str();
+        let geometry = &feature["geometry"];
+        let coordinates = &geometry["coordinates"];
+        let lines: Vec<Vec<[i32; 2]>> = match geometry["type"].as_str() {
+            Some("Point") => {
+                if let (Some(kind), Some(p)) = (classify(tag, true, extract.buildings), point(coordinates)) {
+                    if extract.grid.contains(p) {
+                        extract.grid.add_point(kind, tag("name").map(str::to_string), p);
+                        extract.count(kind);
+                    }
+                }
+                continue;
+            }
+            Some("LineString") => vec![line(coordinates)],
+            Some("MultiLineString" | "Polygon") => coordinates.as_array().into_iter().flatten().map(line).collect(),
+            Some("MultiPolygon") => coordinates
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_array)
+                .flatten()
+                .map(line)
+                .collect(),
+            _ => continue,
+        };
+        let Some(kind) = classify(tag, false, extract.buildings) else {
+            continue;
+        };
+        let inside: Vec<_> = lines.iter().filter(|l| l.iter().any(|&p| extract.grid.contains(p))).collect();
+        for points in &inside {
+            extract.grid.add_line(kind, points);
+        }
+        if !inside.is_empty() {
+            extract.count(kind);
+        }
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let options = parse_args()?;
+    let mut extract = Extract {
+        grid: TileGrid::new(options.bbox, options.tile_deg)?,
+        buildings: options.buildings,
+        nodes: HashMap::new(),
+        counts: HashMap::new(),
+    };
+
+    let lower = options.input.to_lowercase();
+    if lower.ends_with(".pbf") {
+        read_pbf(&options.input, &mut extract)?;
+    } else if lower.ends_with(".geojson") || lower.ends_with(".json") {
+        read_geojson(&options.input, &mut extract)?;
+    } else {
+        return Err("input must be an .osm.pbf or .geojson file".into());
+    }
+
+    let bytes = extract.grid.encode();
+    // Read it back before writing so a bad pack never replaces a good one.
+    let mut packed = MapData::parse(bytes.clone())?;
+    let (south_west, north_east) = packed.bounds();
+    let stored = packed.features_in(south_west, north_east).len();
+    fs::write(&options.output, &bytes)?;
+
+    let mut counts: Vec<_> = extract.counts.into_iter().collect();
+    counts.sort();
+    for (label, count) in counts {
+        println!("{:>8} {}", count, label);
+    }
+    println!(
+        "wrote {} ({} x {} tiles, {} pieces, {:.1} KiB)",
+        options.output,
+        extract.grid.cols,
+        extract.grid.rows,
+        stored,
+        bytes.len() as f64 / 1024.0
+    );
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("pyp-mappack: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two by two tiles of 0.01°, south-west corner at 35.80, -115.44.
+    const BBOX: [f64; 4] = [35.80, -115.44, 35.82, -115.42];
+
+    fn grid() -> TileGrid {
+        TileGrid::new(BBOX, 0.01).unwrap()
+    }
+
+    fn micro(lat: f64, lon: f64) -> [i32; 2] {
+        [to_micro(lat), to_micro(lon)]
+    }
+
+    fn degrees(points: &[[i32; 2]]) -> Vec<[f64; 2]> {
+        points.iter().map(|p| [p[0] as f64 / MICRODEGREES, p[1] as f64 / MICRODEGREES]).collect()
+    }
+
+    fn extract() -> Extract {
+        Extract { grid: grid(), buildings: false, nodes: HashMap::new(), counts: HashMap::new() }
+    }
+
+    #[test]
+    fn packed_features_read_back() {
+        let mut grid = grid();
+        let road = [micro(35.801, -115.439), micro(35.802, -115.4385), micro(35.8035, -115.438)];
+        grid.add_line(FeatureKind::Road, &road);
+        grid.add_point(FeatureKind::Poi, Some("RED ROCKET".into()), micro(35.815, -115.425));
+        // Outside the box, so dropped.
+        grid.add_point(FeatureKind::Poi, Some("FAR AWAY".into()), micro(36.0, -115.0));
+
+        let mut map = MapData::parse(grid.encode()).unwrap();
+        let (south_west, north_east) = map.bounds();
+        let corners = [south_west[0], south_west[1], north_east[0], north_east[1]];
+        assert!(corners.iter().zip(BBOX).all(|(a, b)| (a - b).abs() < 1e-9), "{:?}", corners);
+
+        let south_west: Vec<_> = map.features_in([35.80, -115.44], [35.805, -115.435]).into_iter().cloned().collect();
+        assert_eq!(south_west.len(), 1);
+        assert_eq!(south_west[0].kind, FeatureKind::Road);
+        assert_eq!(south_west[0].points, degrees(&road));
+
+        let north_east = map.features_in([35.811, -115.429], [35.819, -115.421]);
+        assert_eq!(north_east.len(), 1);
+        assert_eq!(north_east[0].kind, FeatureKind::Poi);
+        assert_eq!(north_east[0].name.as_deref(), Some("RED ROCKET"));
+        assert_eq!(north_east[0].points, vec![[35.815, -115.425]]);
+    }
+
+    #[test]
+    fn lines_crossing_tile_edges_land_in_every_tile() {
+        let mut grid = grid();
+        // West to east across the middle column line, then north into the
+        // top-right tile.
+        let line = [micro(35.805, -115.435), micro(35.805, -115.425), micro(35.815, -115.425)];
+        grid.add_line(FeatureKind::Water, &line);
+
+        let mut map = MapData::parse(grid.encode()).unwrap();
+        let west = map.features_in([35.801, -115.439], [35.809, -115.431]);
+        assert_eq!(west.len(), 1);
+        assert_eq!(west[0].points, degrees(&line[..2]));
+
+        // The bottom-right tile holds both segments as one run.
+        let east = map.features_in([35.801, -115.429], [35.809, -115.421]);
+        assert_eq!(east.len(), 1);
+        assert_eq!(east[0].points, degrees(&line));
+
+        let north_east = map.features_in([35.811, -115.429], [35.819, -115.421]);
+        assert_eq!(north_east.len(), 1);
+        assert_eq!(north_east[0].points, degrees(&line[1..]));
+
+        assert!(map.features_in([35.811, -115.439], [35.819, -115.431]).is_empty());
+    }
+
+    #[test]
+    fn reads_geojson_features() {
+        let path = std::env::temp_dir().join(format!("pyp-mappack-geojson-{}.geojson", process::id()));
+        let collection = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "properties": { "highway": "residential", "name": "MAIN ST" },
+                    "geometry": { "type": "LineString", "coordinates": [[-115.439, 35.801], [-115.421, 35.819]] }
+                },
+                {
+                    "properties": { "amenity": "bar", "name": "PROSPECTOR SALOON" },
+                    "geometry": { "type": "Point", "coordinates": [-115.425, 35.815] }
+                },
+                {
+                    "properties": { "amenity": "bench" },
+                    "geometry": { "type": "Point", "coordinates": [-115.425, 35.815] }
+                },
+                {
+                    "properties": { "building": "yes" },
+                    "geometry": { "type": "Polygon", "coordinates": [[[-115.43, 35.81], [-115.43, 35.811], [-115.431, 35.81]]] }
+                },
+                {
+                    "properties": { "natural": "water" },
+                    "geometry": { "type": "Polygon", "coordinates": [[[-115.0, 36.0], [-115.0, 36.1], [-115.1, 36.0]]] }
+                }
+            ]
+        });
+        fs::write(&path, collection.to_string()).unwrap();
+
+        let mut extract = extract();
+        read_geojson(path.to_str().unwrap(), &mut extract).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let mut counts: Vec<_> = extract.counts.into_iter().collect();
+        counts.sort();
+        assert_eq!(counts, vec![("points of interest", 1), ("roads", 1)]);
+
+        let mut map = MapData::parse(extract.grid.encode()).unwrap();
+        let (south_west, north_east) = map.bounds();
+        let features = map.features_in(south_west, north_east);
+        let poi: Vec<_> = features.iter().filter(|f| f.kind == FeatureKind::Poi).collect();
+        assert_eq!(poi.len(), 1);
+        assert_eq!(poi[0].name.as_deref(), Some("PROSPECTOR SALOON"));
+        // The diagonal road clips through three of the four tiles' boxes.
+        assert!(features.iter().filter(|f| f.kind == FeatureKind::Road).count() >= 2);
+        assert!(features.iter().all(|f| f.kind != FeatureKind::Building && f.kind != FeatureKind::Water));
+    }
+
+    #[test]
+    fn truncated_packs_are_rejected() {
+        let mut grid = grid();
+        grid.add_point(FeatureKind::Poi, Some("VAULT 21".into()), micro(35.815, -115.425));
+        let bytes = grid.encode();
+        assert!(MapData::parse(bytes.clone()).is_ok());
+        for len in 0..bytes.len() {
+            assert!(MapData::parse(bytes[..len].to_vec()).is_err(), "{} of {} bytes", len, bytes.len());
+        }
+    }
+
+    #[test]
+    fn lengths_past_the_end_are_malformed() {
+        // Field 1, length-delimited, with a length of u64::MAX.
+        let mut field = vec![0x0a];
+        field.extend([0xff; 9]);
+        field.push(0x01);
+        let result = Protobuf::new(&field).next_field();
+        assert!(result.is_err_and(|e| e.kind() == io::ErrorKind::InvalidData));
+        assert!(Protobuf::new(&[0x0a, 0x05, 1, 2]).next_field().is_err());
+    }
+
+    #[test]
+    fn oversized_blocks_are_refused_before_reading() {
+        let path = std::env::temp_dir().join(format!("pyp-mappack-oversized-{}.osm.pbf", process::id()));
+        // A BlobHeader for an "OSMData" blob of 1 GiB, and no blob after it.
+        let mut header = vec![0x0a, 7];
+        header.extend(b"OSMData");
+        header.extend([0x18, 0x80, 0x80, 0x80, 0x80, 0x04]);
+        let mut file = (header.len() as u32).to_be_bytes().to_vec();
+        file.extend(&header);
+        fs::write(&path, &file).unwrap();
+        let blob_error = read_pbf(path.to_str().unwrap(), &mut extract()).unwrap_err();
+
+        fs::write(&path, u32::MAX.to_be_bytes()).unwrap();
+        let header_error = read_pbf(path.to_str().unwrap(), &mut extract()).unwrap_err();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(blob_error.to_string(), too_large().to_string());
+        assert_eq!(header_error.to_string(), too_large().to_string());
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::Error;
+
+const CHARACTER_PATH: &str = "./data/character.json";
+const DEFAULT_PROFILE: &str = "default";
+
+const XP_PER_100M: u32 = 10;
+const XP_PER_QUEST: u32 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Special {
+    pub strength: u8,
+    pub perception: u8,
+    pub endurance: u8,
+    pub charisma: u8,
+    pub intelligence: u8,
+    pub agility: u8,
+    pub luck: u8,
+}
+
+impl Default for Special {
+    fn default() -> Self {
+        Special {
+            strength: 5,
+            perception: 5,
+            endurance: 5,
+            charisma: 5,
+            intelligence: 5,
+            agility: 5,
+            luck: 5,
+        }
+    }
+}
+
+impl Special {
+    pub const NAMES: [&'static str; 7] = [
+        "STRENGTH",
+        "PERCEPTION",
+        "ENDURANCE",
+        "CHARISMA",
+        "INTELLIGENCE",
+        "AGILITY",
+        "LUCK",
+    ];
+
+    pub fn get(&self, index: usize) -> u8 {
+        match index {
+            0 => self.strength,
+            1 => self.perception,
+            2 => self.endurance,
+            3 => self.charisma,
+            4 => self.intelligence,
+            5 => self.agility,
+            _ => self.luck,
+        }
+    }
+
+    pub fn set(&mut self, index: usize, value: u8) {
+        let value = value.clamp(1, 10);
+        match index {
+            0 => self.strength = value,
+            1 => self.perception = value,
+            2 => self.endurance = value,
+            3 => self.charisma = value,
+            4 => self.intelligence = value,
+            5 => self.agility = value,
+            _ => self.luck = value,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum XpSource {
+    Distance(f64),
+    QuestCompleted,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Character {
+    pub name: String,
+    pub level: u32,
+    pub xp: u32,
+    pub special: Special,
+    pub perks: Vec<String>,
+    #[serde(default)]
+    pub distance_walked_m: f64,
+    #[serde(default)]
+    pub quests_completed: u32,
+}
+
+impl Default for Character {
+    fn default() -> Self {
+        Character {
+            name: "VAULT DWELLER".to_string(),
+            level: 1,
+            xp: 0,
+            special: Special::default(),
+            perks: Vec::new(),
+            distance_walked_m: 0.0,
+            quests_completed: 0,
+        }
+    }
+}
+
+// Same curve as Fallout 4: each level needs 75 more XP than the last.
+pub fn xp_to_next_level(level: u32) -> u32 {
+    200 + level.saturating_sub(1) * 75
+}
+
+impl Character {
+    // Returns true when the award pushed the character up at least one level.
+    pub fn award_xp(&mut self, source: XpSource) -> bool {
+        let amount = match source {
+            XpSource::Distance(meters) => {
+                let before = (self.distance_walked_m / 100.0) as u32;
+                self.distance_walked_m += meters.max(0.0);
+                ((self.distance_walked_m / 100.0) as u32 - before) * XP_PER_100M
+            }
+            XpSource::QuestCompleted => {
+                self.quests_completed += 1;
+                XP_PER_QUEST
+            }
+        };
+
+        self.xp += amount;
+        let mut leveled = false;
+        while self.xp >= xp_to_next_level(self.level) {
+            self.xp -= xp_to_next_level(self.level);
+            self.level += 1;
+            leveled = true;
+        }
+        leveled
+    }
+}
+
+fn character_path(profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        CHARACTER_PATH.to_string()
+    } else {
+        format!("./data/character_{}.json", profile)
+    }
+}
+
+pub fn read_character(profile: &str) -> Result<Character, Error> {
+    match fs::read_to_string(character_path(profile)) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Character::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_character(profile: &str, character: &Character) -> Result<(), Error> {
+    fs::write(character_path(profile), &serde_json::to_vec(character)?)?;
+    Ok(())
+}
+
+pub fn award_and_save(profile: &str, character: &mut Character, source: XpSource) -> Result<bool, Error> {
+    let leveled = character.award_xp(source);
+    write_character(profile, character)?;
+    Ok(leveled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walking_and_quests_pay_and_level() {
+        let mut character = Character::default();
+        assert!(!character.award_xp(XpSource::Distance(99.0)));
+        assert_eq!(character.xp, 0);
+        // Only whole 100 m steps pay, counted across awards.
+        assert!(!character.award_xp(XpSource::Distance(150.0)));
+        assert_eq!(character.xp, 20);
+        assert!(!character.award_xp(XpSource::Distance(-50.0)));
+        assert_eq!(character.xp, 20);
+
+        assert!(!character.award_xp(XpSource::QuestCompleted));
+        assert!(character.award_xp(XpSource::QuestCompleted));
+        assert_eq!((character.level, character.xp, character.quests_completed), (2, 20, 2));
+    }
+
+    #[test]
+    fn saves_with_an_item_count_still_load() {
+        let old = r#"{"name":"NATE","level":3,"xp":40,"special":{"strength":5,"perception":5,"endurance":5,"charisma":5,"intelligence":5,"agility":5,"luck":5},"perks":[],"items_crafted":7}"#;
+        let character: Character = serde_json::from_str(old).unwrap();
+        assert_eq!((character.name.as_str(), character.level, character.xp), ("NATE", 3, 40));
+        assert!(!serde_json::to_string(&character).unwrap().contains("items"));
+    }
+}
@@ -0,0 +1,609 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::settings::Units;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoordinateFormat {
+    Decimal,
+    Dms,
+    Utm,
+    Mgrs,
+    Maidenhead,
+}
+
+impl CoordinateFormat {
+    pub const ALL: [CoordinateFormat; 5] = [
+        CoordinateFormat::Decimal,
+        CoordinateFormat::Dms,
+        CoordinateFormat::Utm,
+        CoordinateFormat::Mgrs,
+        CoordinateFormat::Maidenhead,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CoordinateFormat::Decimal => "Decimal",
+            CoordinateFormat::Dms => "DMS",
+            CoordinateFormat::Utm => "UTM",
+            CoordinateFormat::Mgrs => "MGRS",
+            CoordinateFormat::Maidenhead => "Maidenhead",
+        }
+    }
+
+    // Shown on the entry prompt, so it has to fit the keyboard title.
+    pub fn example(&self) -> &'static str {
+        match self {
+            CoordinateFormat::Decimal => "N35.83 W115.43",
+            CoordinateFormat::Dms => "35 49 57N 115 26 04W",
+            CoordinateFormat::Utm => "11S 641398 3966502",
+            CoordinateFormat::Mgrs => "11S PV 41398 66501",
+            CoordinateFormat::Maidenhead => "DM25gt",
+        }
+    }
+}
+
+fn to_dms(value: f64, positive: char, negative: char) -> String {
+    let hemisphere = if value < 0.0 { negative } else { positive };
+    let total_seconds = (value.abs() * 3600.0 * 10.0).round() / 10.0;
+    let degrees = (total_seconds / 3600.0).floor();
+    let minutes = ((total_seconds - degrees * 3600.0) / 60.0).floor();
+    let seconds = total_seconds - degrees * 3600.0 - minutes * 60.0;
+    format!("{}°{:02}'{:04.1}\"{}", degrees, minutes, seconds, hemisphere)
+}
+
+// Formats a [lat, lon] pair for display.
+pub fn format_coordinates(coordinates: [f64; 2], format: CoordinateFormat) -> String {
+    // UTM and MGRS stop at 80S and 84N; the poles use a different grid.
+    let polar = || "-- (polar)".to_string();
+    match format {
+        CoordinateFormat::Decimal => format!("{:.6}, {:.6}", coordinates[0], coordinates[1]),
+        CoordinateFormat::Dms => format!(
+            "{} {}",
+            to_dms(coordinates[0], 'N', 'S'),
+            to_dms(coordinates[1], 'E', 'W')
+        ),
+        CoordinateFormat::Utm => Utm::from_lat_lon(coordinates).map_or_else(polar, |utm| utm.to_string()),
+        CoordinateFormat::Mgrs => Utm::from_lat_lon(coordinates).map_or_else(polar, |utm| utm.to_mgrs()),
+        CoordinateFormat::Maidenhead => to_maidenhead(coordinates),
+    }
+}
+
+// Reads a typed position in the given format. Decimal and DMS share a parser,
+// so either kind of degrees is accepted under both.
+pub fn parse_coordinates(text: &str, format: CoordinateFormat) -> Option<[f64; 2]> {
+    match format {
+        CoordinateFormat::Decimal | CoordinateFormat::Dms => parse_degrees(text),
+        CoordinateFormat::Utm => Utm::parse(text).map(|utm| utm.to_lat_lon()),
+        CoordinateFormat::Mgrs => Utm::parse_mgrs(text).map(|utm| utm.to_lat_lon()),
+        CoordinateFormat::Maidenhead => parse_maidenhead(text),
+    }
+}
+
+// WGS84, which is what every receiver reports in.
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const UTM_SCALE: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+// Eight degrees each from 80S; X is stretched to 84N.
+const UTM_BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWXX";
+const MGRS_COLUMNS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+const MGRS_ROWS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Utm {
+    pub zone: u8,
+    pub band: char,
+    pub easting: f64,
+    pub northing: f64,
+}
+
+// Krüger's series to sixth order, good to well under a millimetre across a
+// zone. Returns (n, A, alpha, beta) for the WGS84 ellipsoid.
+fn kruger_series() -> (f64, f64, [f64; 6], [f64; 6]) {
+    let n = WGS84_F / (2.0 - WGS84_F);
+    let (n2, n3, n4, n5, n6) = (n.powi(2), n.powi(3), n.powi(4), n.powi(5), n.powi(6));
+    let a = WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n4 / 64.0 + n6 / 256.0);
+    let alpha = [
+        n / 2.0 - 2.0 / 3.0 * n2 + 5.0 / 16.0 * n3 + 41.0 / 180.0 * n4 - 127.0 / 288.0 * n5 + 7891.0 / 37800.0 * n6,
+        13.0 / 48.0 * n2 - 3.0 / 5.0 * n3 + 557.0 / 1440.0 * n4 + 281.0 / 630.0 * n5 - 1983433.0 / 1935360.0 * n6,
+        61.0 / 240.0 * n3 - 103.0 / 140.0 * n4 + 15061.0 / 26880.0 * n5 + 167603.0 / 181440.0 * n6,
+        49561.0 / 161280.0 * n4 - 179.0 / 168.0 * n5 + 6601661.0 / 7257600.0 * n6,
+        34729.0 / 80640.0 * n5 - 3418889.0 / 1995840.0 * n6,
+        212378941.0 / 319334400.0 * n6,
+    ];
+    let beta = [
+        n / 2.0 - 2.0 / 3.0 * n2 + 37.0 / 96.0 * n3 - 1.0 / 360.0 * n4 - 81.0 / 512.0 * n5 + 96199.0 / 604800.0 * n6,
+        1.0 / 48.0 * n2 + 1.0 / 15.0 * n3 - 437.0 / 1440.0 * n4 + 46.0 / 105.0 * n5 - 1118711.0 / 3870720.0 * n6,
+        17.0 / 480.0 * n3 - 37.0 / 840.0 * n4 - 209.0 / 4480.0 * n5 + 5569.0 / 90720.0 * n6,
+        4397.0 / 161280.0 * n4 - 11.0 / 504.0 * n5 - 830251.0 / 7257600.0 * n6,
+        4583.0 / 161280.0 * n5 - 108847.0 / 3991680.0 * n6,
+        20648693.0 / 638668800.0 * n6,
+    ];
+    (n, a, alpha, beta)
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    zone as f64 * 6.0 - 183.0
+}
+
+fn eccentricity() -> f64 {
+    (WGS84_F * (2.0 - WGS84_F)).sqrt()
+}
+
+// Conformal latitude as a tangent, the bit both directions need.
+fn conformal_tan(tau: f64, e: f64) -> f64 {
+    let sigma = (e * (e * tau / (1.0 + tau * tau).sqrt()).atanh()).sinh();
+    tau * (1.0 + sigma * sigma).sqrt() - sigma * (1.0 + tau * tau).sqrt()
+}
+
+// Easting and northing of a point in a given zone, before the false origin
+// is applied to the northing.
+fn project(coordinates: [f64; 2], zone: u8) -> (f64, f64) {
+    let (_, a, alpha, _) = kruger_series();
+    let phi = coordinates[0].to_radians();
+    let lambda = (coordinates[1] - central_meridian(zone)).to_radians();
+    let tau_p = conformal_tan(phi.tan(), eccentricity());
+    let xi_p = tau_p.atan2(lambda.cos());
+    let eta_p = (lambda.sin() / (tau_p * tau_p + lambda.cos().powi(2)).sqrt()).asinh();
+    let (mut xi, mut eta) = (xi_p, eta_p);
+    for (j, alpha) in alpha.iter().enumerate() {
+        let k = 2.0 * (j + 1) as f64;
+        xi += alpha * (k * xi_p).sin() * (k * eta_p).cosh();
+        eta += alpha * (k * xi_p).cos() * (k * eta_p).sinh();
+    }
+    (UTM_SCALE * a * eta + UTM_FALSE_EASTING, UTM_SCALE * a * xi)
+}
+
+fn unproject(zone: u8, easting: f64, northing: f64) -> [f64; 2] {
+    let (_, a, _, beta) = kruger_series();
+    let e = eccentricity();
+    let eta = (easting - UTM_FALSE_EASTING) / (UTM_SCALE * a);
+    let xi = northing / (UTM_SCALE * a);
+    let (mut xi_p, mut eta_p) = (xi, eta);
+    for (j, beta) in beta.iter().enumerate() {
+        let k = 2.0 * (j + 1) as f64;
+        xi_p -= beta * (k * xi).sin() * (k * eta).cosh();
+        eta_p -= beta * (k * xi).cos() * (k * eta).sinh();
+    }
+    let tau_p = xi_p.sin() / (eta_p.sinh().powi(2) + xi_p.cos().powi(2)).sqrt();
+    // No closed form back from the conformal latitude; Newton gets there in
+    // three or four steps.
+    let mut tau = tau_p;
+    for _ in 0..10 {
+        let tau_i = conformal_tan(tau, e);
+        let delta = (tau_p - tau_i) / (1.0 + tau_i * tau_i).sqrt() * (1.0 + (1.0 - e * e) * tau * tau)
+            / ((1.0 - e * e) * (1.0 + tau * tau).sqrt());
+        tau += delta;
+        if delta.abs() < 1e-12 {
+            break;
+        }
+    }
+    let lambda = eta_p.sinh().atan2(xi_p.cos());
+    let lon = (lambda.to_degrees() + central_meridian(zone) + 180.0).rem_euclid(360.0) - 180.0;
+    [tau.atan().to_degrees(), lon]
+}
+
+fn utm_zone(lat: f64, lon: f64, band: char) -> u8 {
+    let zone = (((lon + 180.0) / 6.0).floor() as i32).clamp(0, 59) as u8 + 1;
+    // The two places the grid bends around land: southern Norway and Svalbard.
+    match band {
+        'V' if zone == 31 && lon >= 3.0 => 32,
+        'X' if (0.0..42.0).contains(&lon) && lat >= 72.0 => match lon {
+            lon if lon < 9.0 => 31,
+            lon if lon < 21.0 => 33,
+            lon if lon < 33.0 => 35,
+            _ => 37,
+        },
+        _ => zone,
+    }
+}
+
+impl Utm {
+    pub fn from_lat_lon(coordinates: [f64; 2]) -> Option<Utm> {
+        let [lat, lon] = coordinates;
+        if !(-80.0..=84.0).contains(&lat) || !lon.is_finite() {
+            return None;
+        }
+        let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+        let band = UTM_BANDS[(((lat + 80.0) / 8.0).floor() as usize).min(UTM_BANDS.len() - 1)] as char;
+        let zone = utm_zone(lat, lon, band);
+        let (easting, northing) = project([lat, lon], zone);
+        let northing = if lat < 0.0 { northing + UTM_FALSE_NORTHING_SOUTH } else { northing };
+        Some(Utm { zone, band, easting, northing })
+    }
+
+    pub fn is_south(&self) -> bool {
+        self.band < 'N'
+    }
+
+    pub fn to_lat_lon(self) -> [f64; 2] {
+        let northing = if self.is_south() { self.northing - UTM_FALSE_NORTHING_SOUTH } else { self.northing };
+        unproject(self.zone, self.easting, northing)
+    }
+
+    // Zone, band, easting and northing: "31U 448252 5411933", with the band
+    // allowed to stand apart from the zone.
+    pub fn parse(text: &str) -> Option<Utm> {
+        let upper = text.to_ascii_uppercase();
+        let parts: Vec<&str> = upper
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        let (zone_band, easting, northing) = match parts[..] {
+            [zone_band, easting, northing] => (zone_band.to_string(), easting, northing),
+            [zone, band, easting, northing] => (format!("{}{}", zone, band), easting, northing),
+            _ => return None,
+        };
+        let (zone, band) = parse_zone_band(&zone_band)?;
+        let easting: f64 = easting.parse().ok()?;
+        let northing: f64 = northing.parse().ok()?;
+        let in_range = (100_000.0..1_000_000.0).contains(&easting) && (0.0..=UTM_FALSE_NORTHING_SOUTH).contains(&northing);
+        in_range.then_some(Utm { zone, band, easting, northing })
+    }
+
+    // Military grid reference: the same grid with the 100 km square named by
+    // two letters, and the digits truncated rather than rounded.
+    pub fn to_mgrs(self) -> String {
+        let column = MGRS_COLUMNS[(self.zone as usize - 1) % 3][(self.easting / 100_000.0).floor() as usize - 1] as char;
+        let row_offset = if self.zone % 2 == 0 { 5 } else { 0 };
+        let row = MGRS_ROWS[((self.northing / 100_000.0).floor() as usize + row_offset) % MGRS_ROWS.len()] as char;
+        format!(
+            "{}{} {}{} {:05} {:05}",
+            self.zone,
+            self.band,
+            column,
+            row,
+            (self.easting % 100_000.0).floor() as u32,
+            (self.northing % 100_000.0).floor() as u32
+        )
+    }
+
+    // "31U DQ 48251 11932", spaced or not, with anywhere from one to five
+    // digits a side. The point comes back at the centre of the square the
+    // digits describe.
+    pub fn parse_mgrs(text: &str) -> Option<Utm> {
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+        let letters_at = compact.find(|c: char| c.is_ascii_alphabetic())?;
+        let (zone, band) = parse_zone_band(compact.get(..letters_at + 1)?)?;
+        let square = compact.get(letters_at + 1..letters_at + 3)?.as_bytes();
+        let digits = compact.get(letters_at + 3..)?;
+        if digits.is_empty() || digits.len() % 2 != 0 || digits.len() > 10 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let precision = digits.len() / 2;
+        let cell = 10f64.powi(5 - precision as i32);
+        let offset = |part: &str| part.parse::<f64>().ok().map(|value| value * cell + cell / 2.0);
+        let (east_digits, north_digits) = digits.split_at(precision);
+
+        let column = MGRS_COLUMNS[(zone as usize - 1) % 3].iter().position(|&c| c == square[0])?;
+        let row_offset = if zone % 2 == 0 { 5 } else { 0 };
+        let row = MGRS_ROWS.iter().position(|&c| c == square[1])?;
+        let easting = (column + 1) as f64 * 100_000.0 + offset(east_digits)?;
+        let northing = ((row + MGRS_ROWS.len() - row_offset) % MGRS_ROWS.len()) as f64 * 100_000.0 + offset(north_digits)?;
+
+        // The row letters repeat every 2,000 km, so the band says which lap
+        // this is: step up from the bottom of the band until the square lands
+        // in it.
+        let band_index = UTM_BANDS.iter().position(|&b| b as char == band)?;
+        let band_bottom = band_index as f64 * 8.0 - 80.0;
+        let (_, bottom) = project([band_bottom, central_meridian(zone)], zone);
+        let bottom = if band_bottom < 0.0 { bottom + UTM_FALSE_NORTHING_SOUTH } else { bottom };
+        let bottom = (bottom / 100_000.0).floor() * 100_000.0;
+        let mut northing = northing;
+        while northing < bottom {
+            northing += 2_000_000.0;
+        }
+        Some(Utm { zone, band, easting, northing })
+    }
+}
+
+impl fmt::Display for Utm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{} {:.0} {:.0}", self.zone, self.band, self.easting, self.northing)
+    }
+}
+
+fn parse_zone_band(text: &str) -> Option<(u8, char)> {
+    let band = text.chars().last()?;
+    let zone: u8 = text.get(..text.len() - band.len_utf8())?.parse().ok()?;
+    let valid = (1..=60).contains(&zone) && band.is_ascii() && UTM_BANDS.contains(&(band as u8));
+    valid.then_some((zone, band))
+}
+
+// Six-character locator, the usual precision on the air (about 5 x 2.5 km).
+pub fn to_maidenhead(coordinates: [f64; 2]) -> String {
+    let lon = (coordinates[1] + 180.0).clamp(0.0, 359.999_999);
+    let lat = (coordinates[0] + 90.0).clamp(0.0, 179.999_999);
+    let letter = |base: u8, index: f64| (base + index as u8) as char;
+    [
+        letter(b'A', lon / 20.0),
+        letter(b'A', lat / 10.0),
+        letter(b'0', (lon % 20.0) / 2.0),
+        letter(b'0', lat % 10.0),
+        letter(b'a', (lon % 2.0) * 12.0),
+        letter(b'a', (lat % 1.0) * 24.0),
+    ]
+    .iter()
+    .collect()
+}
+
+// Takes two to eight characters ("DM", "DM25", "DM25vt", "DM25vt41") and
+// returns the middle of the square they name.
+pub fn parse_maidenhead(text: &str) -> Option<[f64; 2]> {
+    let locator = text.trim().to_ascii_uppercase();
+    let chars = locator.as_bytes();
+    if chars.is_empty() || chars.len() > 8 || chars.len() % 2 != 0 {
+        return None;
+    }
+    // Each pair splits the previous square: fields, squares, subsquares, then
+    // extended squares.
+    const STEPS: [(u8, u8, f64, f64); 4] = [
+        (b'A', 18, 20.0, 10.0),
+        (b'0', 10, 2.0, 1.0),
+        (b'A', 24, 2.0 / 24.0, 1.0 / 24.0),
+        (b'0', 10, 2.0 / 240.0, 1.0 / 240.0),
+    ];
+    let (mut lon, mut lat) = (0.0, 0.0);
+    let (mut lon_size, mut lat_size) = (360.0, 180.0);
+    for (pair, &(base, count, width, height)) in chars.chunks(2).zip(STEPS.iter()) {
+        let lon_index = pair[0].checked_sub(base).filter(|&i| i < count)?;
+        let lat_index = pair[1].checked_sub(base).filter(|&i| i < count)?;
+        lon += lon_index as f64 * width;
+        lat += lat_index as f64 * height;
+        (lon_size, lat_size) = (width, height);
+    }
+    Some([lat + lat_size / 2.0 - 90.0, lon + lon_size / 2.0 - 180.0])
+}
+
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+// Great-circle distance between two [lat, lon] points.
+pub fn distance_m(from: [f64; 2], to: [f64; 2]) -> f64 {
+    let (lat1, lat2) = (from[0].to_radians(), to[0].to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (to[1] - from[1]).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+// Initial bearing in degrees clockwise from true north.
+pub fn bearing_deg(from: [f64; 2], to: [f64; 2]) -> f64 {
+    let (lat1, lat2) = (from[0].to_radians(), to[0].to_radians());
+    let dlon = (to[1] - from[1]).to_radians();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+pub fn compass_point(bearing: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((bearing.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
+}
+
+pub fn arrow_for_bearing(bearing: f64) -> &'static str {
+    const ARROWS: [&str; 8] = ["↑", "↗", "→", "↘", "↓", "↙", "←", "↖"];
+    ARROWS[((bearing.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
+}
+
+pub fn format_distance(meters: f64, units: Units) -> String {
+    match units {
+        Units::Metric if meters < 1000.0 => format!("{:.0} m", meters),
+        Units::Metric => format!("{:.1} km", meters / 1000.0),
+        Units::Imperial if meters < 1609.344 => format!("{:.0} ft", meters * 3.28084),
+        Units::Imperial => format!("{:.1} mi", meters / 1609.344),
+    }
+}
+
+pub fn format_height(meters: f64, units: Units) -> String {
+    match units {
+        Units::Metric => format!("{:.0} m", meters),
+        Units::Imperial => format!("{:.0} ft", meters * 3.28084),
+    }
+}
+
+pub fn format_speed(meters_per_second: f64, units: Units) -> String {
+    match units {
+        Units::Metric => format!("{:.1} km/h", meters_per_second * 3.6),
+        Units::Imperial => format!("{:.1} mph", meters_per_second * 2.236936),
+    }
+}
+
+// Reads a typed "lat, lon" pair in degrees. Signed decimals
+// ("35.8325, -115.4345"), hemisphere letters in front of or behind each axis
+// ("N35.8325 W115.4345"), and degrees with minutes and seconds
+// ("35 49 57N 115 25 48W", or the symbols from the DMS display) all work.
+fn parse_degrees(text: &str) -> Option<[f64; 2]> {
+    let mut spaced = String::new();
+    for c in text.to_ascii_uppercase().chars() {
+        match c {
+            '°' | '\'' | '"' | '′' | '″' | ',' => spaced.push(' '),
+            'N' | 'S' | 'E' | 'W' => spaced.extend([' ', c, ' ']),
+            _ => spaced.push(c),
+        }
+    }
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let is_letter = |token: &&str| matches!(*token, "N" | "S" | "E" | "W");
+    let letters: Vec<usize> = (0..tokens.len()).filter(|&i| is_letter(&tokens[i])).collect();
+
+    let (first, second) = match letters[..] {
+        // No hemispheres: the numbers split evenly between the two axes.
+        [] if tokens.len() % 2 == 0 => {
+            let (lat, lon) = tokens.split_at(tokens.len() / 2);
+            (parse_axis(None, lat)?, parse_axis(None, lon)?)
+        }
+        // Letters in front: "N 35 49 57 W 115 25 48".
+        [0, middle] => (
+            parse_axis(Some(tokens[0]), &tokens[1..middle])?,
+            parse_axis(Some(tokens[middle]), &tokens[middle + 1..])?,
+        ),
+        // Letters behind: "35 49 57 N 115 25 48 W".
+        [middle, last] if last == tokens.len() - 1 => (
+            parse_axis(Some(tokens[middle]), &tokens[..middle])?,
+            parse_axis(Some(tokens[last]), &tokens[middle + 1..last])?,
+        ),
+        _ => return None,
+    };
+    let ((lat, lat_letter), (lon, lon_letter)) = match (first.1, second.1) {
+        (Some("E" | "W"), _) => (second, first),
+        _ => (first, second),
+    };
+    let axes_match = matches!(lat_letter, None | Some("N" | "S")) && matches!(lon_letter, None | Some("E" | "W"));
+    (axes_match && lat.abs() <= 90.0 && lon.abs() <= 180.0).then_some([lat, lon])
+}
+
+// One axis from its hemisphere letter and one to three numbers: degrees,
+// then optionally minutes and seconds.
+fn parse_axis<'a>(letter: Option<&'a str>, numbers: &[&str]) -> Option<(f64, Option<&'a str>)> {
+    if numbers.is_empty() || numbers.len() > 3 {
+        return None;
+    }
+    let values: Vec<f64> = numbers.iter().map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    if values.iter().any(|v| !v.is_finite()) || values[1..].iter().any(|&v| !(0.0..60.0).contains(&v)) {
+        return None;
+    }
+    let magnitude = values[0].abs() + values.get(1).unwrap_or(&0.0) / 60.0 + values.get(2).unwrap_or(&0.0) / 3600.0;
+    let sign = match letter {
+        // A hemisphere letter and a minus sign together are ambiguous.
+        Some(_) if values[0].is_sign_negative() => return None,
+        Some("S" | "W") => -1.0,
+        Some(_) => 1.0,
+        None if values[0].is_sign_negative() => -1.0,
+        None => 1.0,
+    };
+    Some((sign * magnitude, letter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EIFFEL_TOWER: [f64; 2] = [48.8582, 2.2945];
+
+    fn assert_near(got: [f64; 2], want: [f64; 2], tolerance_m: f64) {
+        let error = distance_m(got, want);
+        assert!(error < tolerance_m, "{:?} is {:.2} m from {:?}", got, error, want);
+    }
+
+    #[test]
+    fn utm_matches_reference_points() {
+        let utm = Utm::from_lat_lon(EIFFEL_TOWER).unwrap();
+        assert_eq!(utm.to_string(), "31U 448252 5411933");
+        assert_eq!(utm.to_mgrs(), "31U DQ 48251 11932");
+
+        // Where the equator crosses the Greenwich meridian, half a zone west
+        // of zone 31's central meridian.
+        let origin = Utm::from_lat_lon([0.0, 0.0]).unwrap();
+        assert_eq!((origin.zone, origin.band), (31, 'N'));
+        assert!((origin.easting - 166_021.443).abs() < 0.01, "{}", origin.easting);
+        assert!(origin.northing.abs() < 0.001);
+    }
+
+    #[test]
+    fn southern_hemisphere_mirrors_the_north() {
+        let north = Utm::from_lat_lon([33.8568, 151.2153]).unwrap();
+        let south = Utm::from_lat_lon([-33.8568, 151.2153]).unwrap();
+        assert_eq!((south.zone, south.band), (56, 'H'));
+        assert!((south.easting - north.easting).abs() < 1e-6);
+        assert!((south.northing - (10_000_000.0 - north.northing)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn utm_zone_exceptions() {
+        // Bergen sits in the widened zone 32, Longyearbyen in Svalbard's 33.
+        assert_eq!(Utm::from_lat_lon([60.39, 5.32]).unwrap().zone, 32);
+        assert_eq!(Utm::from_lat_lon([78.22, 15.65]).unwrap().zone, 33);
+        assert_eq!(Utm::from_lat_lon([78.22, 8.5]).unwrap().zone, 31);
+        assert!(Utm::from_lat_lon([84.5, 0.0]).is_none());
+        assert_eq!(format_coordinates([-85.0, 0.0], CoordinateFormat::Mgrs), "-- (polar)");
+    }
+
+    #[test]
+    fn maidenhead_matches_reference_locators() {
+        assert_eq!(to_maidenhead([48.14666, 11.60833]), "JN58td");
+        assert_eq!(to_maidenhead([41.714775, -72.727260]), "FN31pr");
+        assert_eq!(to_maidenhead([38.8977, -77.0365]), "FM18lv");
+        assert_eq!(to_maidenhead([-34.91, -56.21166]), "GF15vc");
+    }
+
+    #[test]
+    fn maidenhead_parses_to_square_centres() {
+        assert_eq!(parse_maidenhead("JN"), Some([45.0, 10.0]));
+        assert_eq!(parse_maidenhead("JN58"), Some([48.5, 11.0]));
+        assert_near(parse_maidenhead("jn58td").unwrap(), [48.145833, 11.625], 1.0);
+        assert_near(parse_maidenhead("JN58TD47").unwrap(), [48.15625, 11.620833], 1.0);
+        assert_eq!(parse_maidenhead("JN5"), None);
+        assert_eq!(parse_maidenhead("SN58"), None);
+        assert_eq!(parse_maidenhead("JN58zz"), None);
+    }
+
+    #[test]
+    fn parses_degree_notations() {
+        let want = [48.858194, 2.294500];
+        for text in [
+            "48.858194, 2.2945",
+            "N48.858194 E2.2945",
+            "48.858194N 2.2945E",
+            "48 51 29.5 N 2 17 40.2 E",
+            "48°51'29.5\"N 2°17'40.2\"E",
+            "E2°17'40.2\" N48°51'29.5\"",
+        ] {
+            assert_near(parse_coordinates(text, CoordinateFormat::Dms).unwrap(), want, 1.0);
+        }
+        assert_near(parse_coordinates("-33 51 24, 151 12 55", CoordinateFormat::Decimal).unwrap(), [-33.856667, 151.215278], 1.0);
+        for text in ["", "48.85", "N-48.8 E2.2", "48 61 0 N 2 0 0 E", "N48 N2", "95, 10", "48.8 X 2.2"] {
+            assert_eq!(parse_coordinates(text, CoordinateFormat::Decimal), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_grid_notations() {
+        assert_near(parse_coordinates("31U 448252 5411933", CoordinateFormat::Utm).unwrap(), EIFFEL_TOWER, 1.0);
+        assert_near(parse_coordinates("31 u 448252 5411933", CoordinateFormat::Utm).unwrap(), EIFFEL_TOWER, 1.0);
+        assert_near(parse_coordinates("31UDQ4825111932", CoordinateFormat::Mgrs).unwrap(), EIFFEL_TOWER, 1.5);
+        assert_near(parse_coordinates("31U DQ 482 119", CoordinateFormat::Mgrs).unwrap(), EIFFEL_TOWER, 100.0);
+        for (text, format) in [
+            ("61U 448252 5411933", CoordinateFormat::Utm),
+            ("31U 448252", CoordinateFormat::Utm),
+            ("31U DQ 4825 11932", CoordinateFormat::Mgrs),
+            ("31U IQ 48251 11932", CoordinateFormat::Mgrs),
+        ] {
+            assert_eq!(parse_coordinates(text, format), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let places = [
+            EIFFEL_TOWER,
+            [35.8325, -115.4345],
+            [-33.8568, 151.2153],
+            [-54.8019, -68.3030],
+            [60.39, 5.32],
+            [78.22, 15.65],
+            [0.5, -179.9],
+            [-79.9, 0.1],
+        ];
+        for place in places {
+            for (format, tolerance_m) in [
+                (CoordinateFormat::Decimal, 0.2),
+                (CoordinateFormat::Dms, 2.0),
+                (CoordinateFormat::Utm, 1.0),
+                (CoordinateFormat::Mgrs, 2.0),
+                (CoordinateFormat::Maidenhead, 6000.0),
+            ] {
+                let text = format_coordinates(place, format);
+                let parsed = parse_coordinates(&text, format).unwrap_or_else(|| panic!("{} did not parse", text));
+                assert_near(parsed, place, tolerance_m);
+            }
+        }
+    }
+
+    #[test]
+    fn examples_parse() {
+        for format in CoordinateFormat::ALL {
+            assert_near(parse_coordinates(format.example(), format).unwrap(), [35.8325, -115.4345], 6000.0);
+        }
+    }
+}
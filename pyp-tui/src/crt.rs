@@ -0,0 +1,237 @@
+use crossterm::event::{self, Event};
+use rand::prelude::*;
+use ratatui::buffer::Buffer;
+use ratatui::prelude::*;
+use ratatui::widgets::Paragraph;
+use std::io;
+use std::time::Duration;
+
+use crate::theme::Theme;
+
+const SCANLINE_LEVEL: f32 = 0.72;
+const BAND_LEVEL: f32 = 1.25;
+const BAND_HEIGHT: u16 = 3;
+const FLICKER_CHANCE: f64 = 0.08;
+
+const BOOT_LINES: [&str; 10] = [
+    "*************** PIP-OS(R) V7.1.0.8 ***************",
+    "",
+    "COPYRIGHT 2075 ROBCO(R)",
+    "LOADER V1.1",
+    "EXEC VERSION 41.10",
+    "64K RAM SYSTEM",
+    "NO HOLOTAPE FOUND",
+    "LOAD ROM(1): DEITRIX 303",
+    "INITIALIZING SENSOR ARRAY...",
+    "PIP-BOY 3000 MARK IV READY",
+];
+
+const ROBCO_LOGO: [&str; 7] = [
+    " ____       _      ____        ",
+    "|  _ \\ ___ | |__  / ___|___    ",
+    "| |_) / _ \\| '_ \\| |   / _ \\   ",
+    "|  _ < (_) | |_) | |__| (_) |  ",
+    "|_| \\_\\___/|_.__/ \\____\\___/   ",
+    "",
+    "R O B C O   I N D U S T R I E S",
+];
+
+// Post-processing over the finished frame buffer. The frame counter only moves
+// on ticks so redraws triggered by input don't speed up the rolling band.
+pub struct CrtEffects {
+    frame: u64,
+    flicker: f32,
+}
+
+impl Default for CrtEffects {
+    fn default() -> Self {
+        CrtEffects { frame: 0, flicker: 1.0 }
+    }
+}
+
+impl CrtEffects {
+    pub fn advance(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+        self.flicker = if thread_rng().gen_bool(FLICKER_CHANCE) { 0.88 } else { 1.0 };
+    }
+
+    pub fn apply(&self, buf: &mut Buffer) {
+        let area = buf.area;
+        if area.height == 0 {
+            return;
+        }
+        let band_top = area.top() + (self.frame % (area.height + BAND_HEIGHT) as u64) as u16;
+
+        for y in area.top()..area.bottom() {
+            let scanline = (y - area.top()) % 2 == 1;
+            let in_band = y + BAND_HEIGHT > band_top && y <= band_top;
+            let mut level = self.flicker;
+            if scanline {
+                level *= SCANLINE_LEVEL;
+            }
+            if in_band {
+                level *= BAND_LEVEL;
+            }
+
+            for x in area.left()..area.right() {
+                if let Some(cell) = buf.cell_mut((x, y)) {
+                    match cell.fg {
+                        Color::Rgb(..) => {
+                            cell.fg = shade(cell.fg, level);
+                            cell.bg = shade(cell.bg, level);
+                        }
+                        // Palette colors can't be scaled, so fall back to the
+                        // terminal's half-bright and bold attributes.
+                        _ if scanline && !in_band => {
+                            cell.modifier.insert(Modifier::DIM);
+                        }
+                        _ if in_band => {
+                            cell.modifier.insert(Modifier::BOLD);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn shade(color: Color, level: f32) -> Color {
+    match color {
+        Color::Rgb(r, g, b) => {
+            let scale = |c: u8| (c as f32 * level).round().min(255.0) as u8;
+            Color::Rgb(scale(r), scale(g), scale(b))
+        }
+        other => other,
+    }
+}
+
+// Waits for the given delay, returning true if a key was pressed to skip.
+fn skip_requested(delay: Duration) -> io::Result<bool> {
+    if event::poll(delay).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))? {
+        if let Event::Key(_) = event::read().map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn draw_boot_frame<B: Backend>(
+    terminal: &mut Terminal<B>,
+    lines: Vec<Line>,
+    alignment: Alignment,
+    theme: &Theme,
+    effects: Option<&CrtEffects>,
+) -> io::Result<()> {
+    terminal.draw(|f| {
+        let area = f.area();
+        let paragraph = Paragraph::new(lines)
+            .alignment(alignment)
+            .style(Style::default().fg(theme.fg));
+        f.render_widget(paragraph, area);
+        if let Some(effects) = effects {
+            effects.apply(f.buffer_mut());
+        }
+    })?;
+    Ok(())
+}
+
+pub fn play_boot_sequence<B: Backend>(terminal: &mut Terminal<B>, theme: &Theme, crt_effects: bool) -> io::Result<()> {
+    let mut crt = CrtEffects::default();
+    terminal.clear()?;
+
+    const MEMORY_BYTES: u32 = 38911;
+    for step in 0..=20 {
+        crt.advance();
+        let checked = MEMORY_BYTES * step / 20;
+        let lines = vec![Line::from(format!("MEMORY CHECK ... {:>5} BYTES OK", checked))];
+        draw_boot_frame(terminal, lines, Alignment::Left, theme, crt_effects.then_some(&crt))?;
+        if skip_requested(Duration::from_millis(40))? {
+            return Ok(());
+        }
+    }
+
+    let mut shown: Vec<Line> = vec![Line::from(format!("MEMORY CHECK ... {:>5} BYTES OK", MEMORY_BYTES)), Line::from("")];
+    for line in BOOT_LINES {
+        shown.push(Line::from(line));
+        crt.advance();
+        draw_boot_frame(terminal, shown.clone(), Alignment::Left, theme, crt_effects.then_some(&crt))?;
+        if skip_requested(Duration::from_millis(180))? {
+            return Ok(());
+        }
+    }
+
+    for _ in 0..12 {
+        crt.advance();
+        let area = terminal.size()?;
+        let padding = (area.height as usize).saturating_sub(ROBCO_LOGO.len()) / 2;
+        let mut lines = vec![Line::from(""); padding];
+        lines.extend(
+            ROBCO_LOGO
+                .iter()
+                .map(|l| Line::from(Span::styled(*l, Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD)))),
+        );
+        draw_boot_frame(terminal, lines, Alignment::Center, theme, crt_effects.then_some(&crt))?;
+        if skip_requested(Duration::from_millis(120))? {
+            return Ok(());
+        }
+    }
+
+    terminal.clear()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A frame counter that puts the rolling band below a buffer this tall.
+    const NO_BAND: u64 = 8;
+
+    fn filled(fg: Color, bg: Color) -> Buffer {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 2, 6));
+        for cell in buf.content.iter_mut() {
+            cell.set_fg(fg).set_bg(bg);
+        }
+        buf
+    }
+
+    #[test]
+    fn rgb_scanlines_are_shaded_darker() {
+        let mut buf = filled(Color::Rgb(100, 200, 50), Color::Rgb(10, 20, 0));
+        CrtEffects { frame: NO_BAND, flicker: 1.0 }.apply(&mut buf);
+
+        let even = &buf[(0, 0)];
+        assert_eq!((even.fg, even.bg), (Color::Rgb(100, 200, 50), Color::Rgb(10, 20, 0)));
+        let odd = &buf[(1, 1)];
+        assert_eq!((odd.fg, odd.bg), (Color::Rgb(72, 144, 36), Color::Rgb(7, 14, 0)));
+        // Shading is done with the colour itself, not the dim attribute.
+        assert!(!odd.modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn palette_scanlines_are_dimmed_instead() {
+        let mut buf = filled(Color::Green, Color::Reset);
+        CrtEffects { frame: NO_BAND, flicker: 1.0 }.apply(&mut buf);
+
+        let even = &buf[(0, 0)];
+        assert_eq!((even.fg, even.bg), (Color::Green, Color::Reset));
+        assert!(!even.modifier.contains(Modifier::DIM));
+        let odd = &buf[(0, 1)];
+        assert_eq!((odd.fg, odd.bg), (Color::Green, Color::Reset));
+        assert!(odd.modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn the_band_brightens_rgb_and_bolds_palette_colours() {
+        // Frame 0 puts the bottom of the band on the top row.
+        let mut rgb = filled(Color::Rgb(100, 220, 0), Color::Reset);
+        CrtEffects::default().apply(&mut rgb);
+        assert_eq!(rgb[(0, 0)].fg, Color::Rgb(125, 255, 0));
+        assert_eq!(rgb[(0, 0)].bg, Color::Reset);
+
+        let mut palette = filled(Color::Indexed(46), Color::Reset);
+        CrtEffects::default().apply(&mut palette);
+        assert!(palette[(0, 0)].modifier.contains(Modifier::BOLD));
+        assert!(!palette[(0, 2)].modifier.contains(Modifier::BOLD));
+    }
+}
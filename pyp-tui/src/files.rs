@@ -0,0 +1,31 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Written aside and renamed over, so a crash mid-write can't leave half a file.
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+
+    #[test]
+    fn replaces_the_file_and_leaves_nothing_aside() {
+        let dir = scratch_dir("files");
+        let path = dir.join("db.json");
+
+        write_atomic(&path, b"[1]").unwrap();
+        write_atomic(&path, b"[1,2]").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"[1,2]");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
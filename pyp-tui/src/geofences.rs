@@ -0,0 +1,399 @@
+use chrono::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+use crate::coords::distance_m;
+use crate::quests::QuestLog;
+use crate::Error;
+
+const GEOFENCES_PATH: &str = "./data/geofences.json";
+const GEOFENCE_LOG_PATH: &str = "./data/geofence_log.jsonl";
+// Entering counts as soon as the fix is over the line, but leaving needs
+// this much clearance, so a receiver wandering along the edge doesn't fire
+// enter/exit over and over.
+const EXIT_MARGIN_M: f64 = 10.0;
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum FenceShape {
+    Circle { center: [f64; 2], radius_m: f64 },
+    Polygon { vertices: Vec<[f64; 2]> },
+}
+
+impl FenceShape {
+    pub fn describe(&self) -> String {
+        match self {
+            FenceShape::Circle { radius_m, .. } => format!("Circle, {:.0} m radius", radius_m),
+            FenceShape::Polygon { vertices } => format!("Polygon, {} corners", vertices.len()),
+        }
+    }
+}
+
+// The quest objective ticked off the first time the fence is entered.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObjectiveLink {
+    pub quest: usize,
+    pub objective: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Geofence {
+    pub id: usize,
+    pub name: String,
+    pub shape: FenceShape,
+    pub objective: Option<ObjectiveLink>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Geofence {
+    pub fn new(name: String, shape: FenceShape) -> Self {
+        Geofence {
+            id: thread_rng().gen_range(0, 9999999),
+            name,
+            shape,
+            objective: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    // Metres from `position` to the boundary, negative when inside.
+    pub fn signed_distance_m(&self, position: [f64; 2]) -> f64 {
+        match &self.shape {
+            FenceShape::Circle { center, radius_m } => distance_m(position, *center) - radius_m,
+            FenceShape::Polygon { vertices } => polygon_signed_distance_m(vertices, position),
+        }
+    }
+}
+
+// Fences are a few hundred metres across at most, so a flat projection
+// centred on the position is plenty: it becomes the origin, and the corners
+// become metres east and north of it.
+fn polygon_signed_distance_m(vertices: &[[f64; 2]], position: [f64; 2]) -> f64 {
+    let lon_scale = position[0].to_radians().cos() * METERS_PER_DEGREE;
+    let points: Vec<(f64, f64)> = vertices
+        .iter()
+        .map(|v| ((v[1] - position[1]) * lon_scale, (v[0] - position[0]) * METERS_PER_DEGREE))
+        .collect();
+    if points.len() < 3 {
+        return f64::INFINITY;
+    }
+
+    let mut inside = false;
+    let mut nearest = f64::INFINITY;
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(i + 1) % points.len()];
+        // Even-odd rule: count the edges a ray going east from the origin crosses.
+        if (y1 > 0.0) != (y2 > 0.0) && x1 + (0.0 - y1) * (x2 - x1) / (y2 - y1) > 0.0 {
+            inside = !inside;
+        }
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length_sq = dx * dx + dy * dy;
+        let t = if length_sq > 0.0 { (-(x1 * dx + y1 * dy) / length_sq).clamp(0.0, 1.0) } else { 0.0 };
+        nearest = nearest.min((x1 + t * dx).hypot(y1 + t * dy));
+    }
+    if inside { -nearest } else { nearest }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GeofenceEventKind {
+    Enter,
+    Exit,
+}
+
+impl GeofenceEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GeofenceEventKind::Enter => "ENTERED",
+            GeofenceEventKind::Exit => "LEFT",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GeofenceEvent {
+    pub fence: usize,
+    pub name: String,
+    pub kind: GeofenceEventKind,
+    pub position: [f64; 2],
+    pub at: DateTime<Utc>,
+}
+
+// Remembers which side of each fence the last fix was on. The first fix
+// only sets that up: starting the day inside a fence isn't entering it.
+#[derive(Default)]
+pub struct GeofenceMonitor {
+    inside: HashMap<usize, bool>,
+}
+
+// Shared by the UI, which edits the fences and shows which side of each the
+// last fix was on, and the position service, which checks every fix.
+#[derive(Default)]
+pub struct GeofenceWatch {
+    pub fences: Vec<Geofence>,
+    pub monitor: GeofenceMonitor,
+}
+
+impl GeofenceMonitor {
+    pub fn check(&mut self, fences: &[Geofence], position: [f64; 2], at: DateTime<Utc>) -> Vec<GeofenceEvent> {
+        let mut raised = Vec::new();
+        for fence in fences {
+            let distance = fence.signed_distance_m(position);
+            let kind = match self.inside.get(&fence.id) {
+                None => {
+                    self.inside.insert(fence.id, distance <= 0.0);
+                    continue;
+                }
+                Some(false) if distance <= 0.0 => GeofenceEventKind::Enter,
+                Some(true) if distance > EXIT_MARGIN_M => GeofenceEventKind::Exit,
+                Some(_) => continue,
+            };
+            self.inside.insert(fence.id, kind == GeofenceEventKind::Enter);
+            raised.push(GeofenceEvent {
+                fence: fence.id,
+                name: fence.name.clone(),
+                kind,
+                position,
+                at,
+            });
+        }
+        raised
+    }
+
+    pub fn is_inside(&self, fence: &Geofence) -> Option<bool> {
+        self.inside.get(&fence.id).copied()
+    }
+}
+
+// Ticks off the objective `fence` is linked to, if it's still open. Returns
+// the objective's text and whether that finished its quest.
+pub fn complete_linked_objective(fence: &Geofence, quest_log: &mut QuestLog) -> Option<(String, bool)> {
+    let link = fence.objective?;
+    let quest = quest_log.quests.iter_mut().find(|q| q.id == link.quest)?;
+    let completed = quest.complete_objective(link.objective)?;
+    Some((quest.objectives[link.objective].text.clone(), completed))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GeofenceAction {
+    LinkObjective,
+    UnlinkObjective,
+    ChangeRadius,
+    Rename,
+    Delete,
+}
+
+impl GeofenceAction {
+    pub fn available(fence: &Geofence) -> Vec<GeofenceAction> {
+        let mut actions = vec![if fence.objective.is_some() {
+            GeofenceAction::UnlinkObjective
+        } else {
+            GeofenceAction::LinkObjective
+        }];
+        if matches!(fence.shape, FenceShape::Circle { .. }) {
+            actions.push(GeofenceAction::ChangeRadius);
+        }
+        actions.extend([GeofenceAction::Rename, GeofenceAction::Delete]);
+        actions
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GeofenceAction::LinkObjective => "Complete Objective On Entry",
+            GeofenceAction::UnlinkObjective => "Unlink Objective",
+            GeofenceAction::ChangeRadius => "Change Radius",
+            GeofenceAction::Rename => "Rename",
+            GeofenceAction::Delete => "Delete",
+        }
+    }
+}
+
+pub fn read_geofences() -> Result<Vec<Geofence>, Error> {
+    match fs::read_to_string(GEOFENCES_PATH) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_geofences(fences: &[Geofence]) -> Result<(), Error> {
+    fs::write(GEOFENCES_PATH, &serde_json::to_vec(fences)?)?;
+    Ok(())
+}
+
+pub fn log_geofence_event(event: &GeofenceEvent) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(GEOFENCE_LOG_PATH)?
+        .write_all(&line)?;
+    Ok(())
+}
+
+// The newest `limit` events, oldest first. A line that doesn't parse is
+// skipped rather than losing the rest of the history.
+pub fn read_geofence_log(limit: usize) -> Result<Vec<GeofenceEvent>, Error> {
+    let content = match fs::read_to_string(GEOFENCE_LOG_PATH) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let events: Vec<GeofenceEvent> = content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect();
+    Ok(events[events.len().saturating_sub(limit)..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmea::NmeaReceiver;
+    use crate::quests::{Quest, QuestState};
+    use crate::tracks::{parse_gpx, TrackPoint};
+
+    // A walk east through a 40 m circle around CENTER that stands on the
+    // near edge for a minute and then hangs about just past the far edge
+    // before walking off.
+    const FENCE_WALK: &str = include_str!("../testdata/fence_walk.gpx");
+    const NEO6M_WALK: &str = include_str!("../testdata/neo6m_walk.nmea");
+    const CENTER: [f64; 2] = [35.8325, -115.4345];
+
+    fn offset(east_m: f64, north_m: f64) -> [f64; 2] {
+        [
+            CENTER[0] + north_m / METERS_PER_DEGREE,
+            CENTER[1] + east_m / (METERS_PER_DEGREE * CENTER[0].to_radians().cos()),
+        ]
+    }
+
+    fn walk() -> Vec<TrackPoint> {
+        let mut lines = parse_gpx(FENCE_WALK).unwrap();
+        lines.remove(0).1
+    }
+
+    fn replay(fences: &[Geofence], points: &[([f64; 2], DateTime<Utc>)]) -> Vec<(String, GeofenceEventKind)> {
+        let mut monitor = GeofenceMonitor::default();
+        points
+            .iter()
+            .flat_map(|&(position, at)| monitor.check(fences, position, at))
+            .map(|event| (event.name, event.kind))
+            .collect()
+    }
+
+    fn track_fixes(points: &[TrackPoint]) -> Vec<([f64; 2], DateTime<Utc>)> {
+        points.iter().map(|p| (p.position, p.at.unwrap())).collect()
+    }
+
+    #[test]
+    fn circle_distance_is_signed() {
+        let fence = Geofence::new("CAMP".into(), FenceShape::Circle { center: CENTER, radius_m: 40.0 });
+        assert!((fence.signed_distance_m(CENTER) + 40.0).abs() < 0.01);
+        assert!((fence.signed_distance_m(offset(0.0, 100.0)) - 60.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn polygon_distance_is_signed() {
+        let square = Geofence::new(
+            "YARD".into(),
+            FenceShape::Polygon { vertices: vec![offset(-30.0, -30.0), offset(30.0, -30.0), offset(30.0, 30.0), offset(-30.0, 30.0)] },
+        );
+        assert!((square.signed_distance_m(CENTER) + 30.0).abs() < 0.1);
+        assert!((square.signed_distance_m(offset(20.0, 0.0)) + 10.0).abs() < 0.1);
+        assert!((square.signed_distance_m(offset(50.0, 0.0)) - 20.0).abs() < 0.1);
+        // Past a corner the nearest point is the corner itself.
+        assert!((square.signed_distance_m(offset(33.0, 34.0)) - 5.0).abs() < 0.1);
+
+        // A concave L: the notch is outside.
+        let ell = Geofence::new(
+            "ELL".into(),
+            FenceShape::Polygon {
+                vertices: vec![offset(0.0, 0.0), offset(40.0, 0.0), offset(40.0, 10.0), offset(10.0, 10.0), offset(10.0, 40.0), offset(0.0, 40.0)],
+            },
+        );
+        assert!(ell.signed_distance_m(offset(5.0, 30.0)) < 0.0);
+        assert!(ell.signed_distance_m(offset(30.0, 30.0)) > 0.0);
+    }
+
+    #[test]
+    fn loitering_on_the_edge_fires_once() {
+        let fences = [Geofence::new("CAMP".into(), FenceShape::Circle { center: CENTER, radius_m: 40.0 })];
+        let events = replay(&fences, &track_fixes(&walk()));
+        assert_eq!(
+            events,
+            vec![("CAMP".to_string(), GeofenceEventKind::Enter), ("CAMP".to_string(), GeofenceEventKind::Exit)]
+        );
+    }
+
+    #[test]
+    fn events_carry_the_fix_time() {
+        let fences = [Geofence::new("CAMP".into(), FenceShape::Circle { center: CENTER, radius_m: 40.0 })];
+        let points = walk();
+        let mut monitor = GeofenceMonitor::default();
+        let events: Vec<GeofenceEvent> = points.iter().flat_map(|p| monitor.check(&fences, p.position, p.at.unwrap())).collect();
+        let entered = &events[0];
+        let fix = points.iter().find(|p| p.at == Some(entered.at)).unwrap();
+        assert_eq!(entered.position, fix.position);
+        assert!(fences[0].signed_distance_m(entered.position) <= 0.0);
+        assert!(fences[0].signed_distance_m(events[1].position) > EXIT_MARGIN_M);
+        assert_eq!(monitor.is_inside(&fences[0]), Some(false));
+    }
+
+    #[test]
+    fn starting_inside_is_not_an_entry() {
+        let fences = [
+            Geofence::new("START".into(), FenceShape::Circle { center: offset(-200.0, 0.0), radius_m: 25.0 }),
+            Geofence::new("ELSEWHERE".into(), FenceShape::Circle { center: offset(0.0, 500.0), radius_m: 25.0 }),
+            Geofence::new(
+                "YARD".into(),
+                FenceShape::Polygon { vertices: vec![offset(60.0, -20.0), offset(120.0, -20.0), offset(120.0, 20.0), offset(60.0, 20.0)] },
+            ),
+        ];
+        let events = replay(&fences, &track_fixes(&walk()));
+        assert_eq!(
+            events,
+            vec![
+                ("START".to_string(), GeofenceEventKind::Exit),
+                ("YARD".to_string(), GeofenceEventKind::Enter),
+                ("YARD".to_string(), GeofenceEventKind::Exit),
+            ]
+        );
+    }
+
+    #[test]
+    fn works_on_a_replayed_receiver_log() {
+        let mut receiver = NmeaReceiver::default();
+        let mut fixes = Vec::new();
+        for line in NEO6M_WALK.lines() {
+            if let Ok(true) = receiver.feed_line(line) {
+                if let Some(position) = receiver.fix.coordinates() {
+                    fixes.push((position, receiver.fix.at));
+                }
+            }
+        }
+        // The log walks about 30 m east from CENTER.
+        let fences = [Geofence::new("POST".into(), FenceShape::Circle { center: offset(10.0, 0.0), radius_m: 3.0 })];
+        let events = replay(&fences, &fixes);
+        assert_eq!(
+            events,
+            vec![("POST".to_string(), GeofenceEventKind::Enter), ("POST".to_string(), GeofenceEventKind::Exit)]
+        );
+    }
+
+    #[test]
+    fn entering_completes_the_linked_objective_once() {
+        let mut quest_log = QuestLog::default();
+        quest_log.add(Quest::new("SUPPLY RUN".into(), String::new(), vec!["Reach the checkpoint".into(), "Come home".into()]));
+        let quest = quest_log.quests[0].id;
+        let mut fence = Geofence::new("CHECKPOINT".into(), FenceShape::Circle { center: CENTER, radius_m: 40.0 });
+        assert_eq!(complete_linked_objective(&fence, &mut quest_log), None);
+
+        fence.objective = Some(ObjectiveLink { quest, objective: 0 });
+        assert_eq!(complete_linked_objective(&fence, &mut quest_log), Some(("Reach the checkpoint".to_string(), false)));
+        assert_eq!(complete_linked_objective(&fence, &mut quest_log), None);
+
+        fence.objective = Some(ObjectiveLink { quest, objective: 1 });
+        assert_eq!(complete_linked_objective(&fence, &mut quest_log), Some(("Come home".to_string(), true)));
+        assert_eq!(quest_log.quests[0].state, QuestState::Completed);
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Error;
+
+const HOLOTAPES_PATH: &str = "./data/holotapes.json";
+const IMPORT_DIR: &str = "./data";
+const IMPORTED_PATH: &str = "./data/holotape_imports.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Holotape {
+    pub id: usize,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub position: Option<[f64; 2]>,
+    // File name the tape was imported from, so a rescan doesn't duplicate it.
+    pub source: Option<String>,
+}
+
+impl Holotape {
+    pub fn new(title: String, body: String, position: Option<[f64; 2]>) -> Self {
+        Holotape {
+            id: thread_rng().gen_range(0, 9999999),
+            title,
+            body,
+            created_at: Utc::now(),
+            position,
+            source: None,
+        }
+    }
+}
+
+// Splits a text or Markdown file into a title and body. The first non-empty
+// line becomes the title with any Markdown heading marks dropped.
+pub fn parse_holotape_file(file_name: &str, content: &str) -> (String, String) {
+    let mut lines = content.lines().skip_while(|line| line.trim().is_empty());
+    let title = match lines.next() {
+        Some(first) => first.trim().trim_start_matches('#').trim().to_string(),
+        None => String::new(),
+    };
+    let title = if title.is_empty() {
+        Path::new(file_name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| file_name.to_string())
+    } else {
+        title
+    };
+    let body = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+    (title, body)
+}
+
+// Picks up any .txt or .md files dropped into the data directory that haven't
+// been imported yet. Returns how many new tapes were added.
+pub fn import_holotapes(holotapes: &mut Vec<Holotape>) -> io::Result<usize> {
+    let mut imported = match fs::read_to_string(IMPORTED_PATH) {
+        Ok(content) => serde_json::from_str(&content)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeSet::new(),
+        Err(e) => return Err(e),
+    };
+    let before = imported.clone();
+    let count = import_holotapes_from(Path::new(IMPORT_DIR), holotapes, &mut imported)?;
+    if imported != before {
+        fs::write(IMPORTED_PATH, serde_json::to_vec(&imported)?)?;
+    }
+    Ok(count)
+}
+
+// `imported` remembers every file name ever taken in, so deleting an
+// imported tape doesn't bring it back on the next scan. Tapes saved before
+// that list existed still carry their file name, so those count too.
+fn import_holotapes_from(dir: &Path, holotapes: &mut Vec<Holotape>, imported: &mut BTreeSet<String>) -> io::Result<usize> {
+    imported.extend(holotapes.iter().filter_map(|h| h.source.clone()));
+    let mut count = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_text = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("txt") || ext.eq_ignore_ascii_case("md"));
+        let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        if !is_text || imported.contains(&file_name) {
+            continue;
+        }
+
+        // Notes saved in some other encoding still import, with the odd
+        // character replaced.
+        let content = fs::read(&path)?;
+        let (title, body) = parse_holotape_file(&file_name, &String::from_utf8_lossy(&content));
+        let created_at = fs::metadata(&path)?
+            .modified()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        let mut holotape = Holotape::new(title, body, None);
+        holotape.created_at = created_at;
+        holotape.source = Some(file_name.clone());
+        holotapes.push(holotape);
+        imported.insert(file_name);
+        count += 1;
+    }
+    holotapes.sort_by_key(|h| std::cmp::Reverse(h.created_at));
+    Ok(count)
+}
+
+pub fn read_holotapes() -> Result<Vec<Holotape>, Error> {
+    match fs::read_to_string(HOLOTAPES_PATH) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_holotapes(holotapes: &[Holotape]) -> Result<(), Error> {
+    fs::write(HOLOTAPES_PATH, &serde_json::to_vec(holotapes)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+
+    #[test]
+    fn imports_text_that_is_not_utf8() {
+        let dir = scratch_dir("holotapes-latin1");
+        // "Café" in ISO-8859-1.
+        fs::write(dir.join("menu.txt"), b"Caf\xe9\nSoup of the day").unwrap();
+        fs::write(dir.join("plain.md"), "# Plain\nNothing odd").unwrap();
+
+        let mut tapes = Vec::new();
+        let mut imported = BTreeSet::new();
+        assert_eq!(import_holotapes_from(&dir, &mut tapes, &mut imported).unwrap(), 2);
+        assert!(tapes.iter().any(|t| t.title == "Caf\u{FFFD}" && t.body == "Soup of the day"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn deleted_tapes_stay_deleted() {
+        let dir = scratch_dir("holotapes-deleted");
+        fs::write(dir.join("log.txt"), "Day one\nStill alive").unwrap();
+
+        let mut tapes = Vec::new();
+        let mut imported = BTreeSet::new();
+        assert_eq!(import_holotapes_from(&dir, &mut tapes, &mut imported).unwrap(), 1);
+        assert_eq!(import_holotapes_from(&dir, &mut tapes, &mut imported).unwrap(), 0);
+
+        tapes.clear();
+        assert_eq!(import_holotapes_from(&dir, &mut tapes, &mut imported).unwrap(), 0);
+        assert!(tapes.is_empty());
+
+        // A tape imported before the list existed still counts as seen.
+        let mut old = Holotape::new("Day one".into(), String::new(), None);
+        old.source = Some("log.txt".into());
+        let mut tapes = vec![old];
+        let mut imported = BTreeSet::new();
+        assert_eq!(import_holotapes_from(&dir, &mut tapes, &mut imported).unwrap(), 0);
+        assert!(imported.contains("log.txt"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+use chrono::prelude::*;
+use chrono::Duration;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::pulse::PulseReading;
+
+const HR_LOG_PATH: &str = "./data/hr.log";
+const HR_CSV_PATH: &str = "./data/hr_export.csv";
+
+// Each record is an i64 unix timestamp, a u16 BPM and a u8 SpO2 (0 = unknown).
+const RECORD_LEN: usize = 11;
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+const MAX_ROTATIONS: usize = 7;
+// Eight days covers the daily summaries and THIS WEEK; older samples stay on
+// disk for the CSV export and only count towards the all-time extremes.
+const MEMORY_DAYS: i64 = 8;
+const CHART_HOURS: i64 = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HrPeriod {
+    Hour,
+    Day,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HrSummary {
+    pub min: u16,
+    pub avg: u16,
+    pub max: u16,
+    pub count: usize,
+}
+
+// Samples kept in memory, plus what the trends screen shows, worked out when
+// a sample arrives rather than on every frame.
+pub struct HrHistory {
+    pub readings: Vec<PulseReading>,
+    pub low: Option<u16>,
+    pub high: Option<u16>,
+    // (hours before now, BPM) over the last day.
+    pub chart: Vec<(f64, f64)>,
+    pub resting: Option<u16>,
+    // Newest first.
+    pub hourly: Vec<(NaiveDateTime, HrSummary)>,
+    pub daily: Vec<(NaiveDateTime, HrSummary)>,
+}
+
+impl HrHistory {
+    pub fn new(readings: Vec<PulseReading>, now: DateTime<Utc>) -> Self {
+        let mut history = HrHistory {
+            low: readings.iter().map(|r| r.bpm).min(),
+            high: readings.iter().map(|r| r.bpm).max(),
+            readings,
+            chart: Vec::new(),
+            resting: None,
+            hourly: Vec::new(),
+            daily: Vec::new(),
+        };
+        history.refresh(now);
+        history
+    }
+
+    pub fn push(&mut self, reading: PulseReading, now: DateTime<Utc>) {
+        self.low = Some(self.low.map_or(reading.bpm, |low| low.min(reading.bpm)));
+        self.high = Some(self.high.map_or(reading.bpm, |high| high.max(reading.bpm)));
+        self.readings.push(reading);
+        self.refresh(now);
+    }
+
+    // Also called once a minute so the chart keeps sliding with no new samples.
+    pub fn refresh(&mut self, now: DateTime<Utc>) {
+        let since = now - Duration::days(MEMORY_DAYS);
+        self.readings.retain(|r| r.at >= since);
+        self.chart = self
+            .readings
+            .iter()
+            .map(|r| (-((now - r.at).num_seconds() as f64) / 3600.0, r.bpm as f64))
+            .filter(|p| p.0 >= -(CHART_HOURS as f64))
+            .collect();
+        self.resting = resting_hr(&self.readings, now);
+        self.hourly = summarize_by(&self.readings, HrPeriod::Hour).into_iter().rev().take(6).collect();
+        self.daily = summarize_by(&self.readings, HrPeriod::Day).into_iter().rev().take(7).collect();
+    }
+}
+
+fn rotated_path(log: &str, n: usize) -> String {
+    format!("{}.{}", log, n)
+}
+
+fn rotate_if_needed(log: &str) -> io::Result<()> {
+    let len = match fs::metadata(log) {
+        Ok(meta) => meta.len(),
+        Err(_) => return Ok(()),
+    };
+    if len < MAX_LOG_BYTES {
+        return Ok(());
+    }
+
+    let oldest = rotated_path(log, MAX_ROTATIONS);
+    if Path::new(&oldest).exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for n in (1..MAX_ROTATIONS).rev() {
+        let from = rotated_path(log, n);
+        if Path::new(&from).exists() {
+            fs::rename(&from, rotated_path(log, n + 1))?;
+        }
+    }
+    fs::rename(log, rotated_path(log, 1))
+}
+
+pub fn append_hr_reading(reading: &PulseReading) -> io::Result<()> {
+    append_hr_reading_to(HR_LOG_PATH, reading)
+}
+
+fn append_hr_reading_to(log: &str, reading: &PulseReading) -> io::Result<()> {
+    rotate_if_needed(log)?;
+
+    let mut record = [0u8; RECORD_LEN];
+    record[..8].copy_from_slice(&reading.at.timestamp().to_le_bytes());
+    record[8..10].copy_from_slice(&reading.bpm.to_le_bytes());
+    record[10] = reading.spo2.unwrap_or(0);
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log)?
+        .write_all(&record)
+}
+
+fn parse_records(bytes: &[u8], out: &mut Vec<PulseReading>) {
+    for record in bytes.chunks_exact(RECORD_LEN) {
+        let mut ts = [0u8; 8];
+        ts.copy_from_slice(&record[..8]);
+        let at = match Utc.timestamp_opt(i64::from_le_bytes(ts), 0).single() {
+            Some(at) => at,
+            None => continue,
+        };
+        let bpm = u16::from_le_bytes([record[8], record[9]]);
+        let spo2 = if record[10] == 0 { None } else { Some(record[10]) };
+        out.push(PulseReading { bpm, spo2, at });
+    }
+}
+
+pub fn read_hr_log() -> io::Result<Vec<PulseReading>> {
+    read_hr_log_from(HR_LOG_PATH)
+}
+
+fn read_hr_log_from(log: &str) -> io::Result<Vec<PulseReading>> {
+    let mut readings = Vec::new();
+    let mut paths: Vec<String> = (1..=MAX_ROTATIONS).rev().map(|n| rotated_path(log, n)).collect();
+    paths.push(log.to_string());
+
+    for path in paths {
+        match fs::read(&path) {
+            Ok(bytes) => parse_records(&bytes, &mut readings),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(readings)
+}
+
+pub fn export_hr_csv(readings: &[PulseReading]) -> io::Result<(usize, &'static str)> {
+    let mut csv = String::from("timestamp,bpm,spo2\n");
+    for reading in readings {
+        let spo2 = reading.spo2.map(|s| s.to_string()).unwrap_or_default();
+        csv.push_str(&format!("{},{},{}\n", reading.at.to_rfc3339(), reading.bpm, spo2));
+    }
+    fs::write(HR_CSV_PATH, csv)?;
+    Ok((readings.len(), HR_CSV_PATH))
+}
+
+pub fn summarize(readings: &[PulseReading]) -> Option<HrSummary> {
+    let min = readings.iter().map(|r| r.bpm).min()?;
+    let max = readings.iter().map(|r| r.bpm).max()?;
+    let total: u64 = readings.iter().map(|r| r.bpm as u64).sum();
+    Some(HrSummary {
+        min,
+        avg: (total / readings.len() as u64) as u16,
+        max,
+        count: readings.len(),
+    })
+}
+
+pub fn summarize_by(readings: &[PulseReading], period: HrPeriod) -> Vec<(NaiveDateTime, HrSummary)> {
+    let mut buckets: BTreeMap<NaiveDateTime, Vec<PulseReading>> = BTreeMap::new();
+    for reading in readings {
+        let local = reading.at.with_timezone(&Local).naive_local();
+        let start = match period {
+            HrPeriod::Hour => local.date().and_hms_opt(local.hour(), 0, 0),
+            HrPeriod::Day => local.date().and_hms_opt(0, 0, 0),
+        };
+        if let Some(start) = start {
+            buckets.entry(start).or_default().push(*reading);
+        }
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(start, bucket)| summarize(&bucket).map(|s| (start, s)))
+        .collect()
+}
+
+// Resting HR is taken as the mean of the lowest tenth of the last day's readings.
+pub fn resting_hr(readings: &[PulseReading], now: DateTime<Utc>) -> Option<u16> {
+    let since = now - Duration::hours(24);
+    let mut recent: Vec<u16> = readings
+        .iter()
+        .filter(|r| r.at >= since)
+        .map(|r| r.bpm)
+        .collect();
+    if recent.is_empty() {
+        return None;
+    }
+
+    recent.sort_unstable();
+    let lowest = &recent[..recent.len().div_ceil(10)];
+    Some((lowest.iter().map(|&b| b as u32).sum::<u32>() / lowest.len() as u32) as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+
+    fn scratch_log(name: &str) -> String {
+        scratch_dir(&format!("hr-{}", name)).join("hr.log").to_string_lossy().into_owned()
+    }
+
+    fn local(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Local.with_ymd_and_hms(2026, 10, day, hour, minute, 0).unwrap().with_timezone(&Utc)
+    }
+
+    fn reading(bpm: u16, at: DateTime<Utc>) -> PulseReading {
+        PulseReading { bpm, spo2: None, at }
+    }
+
+    #[test]
+    fn full_logs_rotate_and_read_back_in_order() {
+        let log = scratch_log("rotate");
+        let full: Vec<u8> = (0..MAX_LOG_BYTES as usize / RECORD_LEN + 1)
+            .flat_map(|_| {
+                let mut record = [0u8; RECORD_LEN];
+                record[..8].copy_from_slice(&local(1, 8, 0).timestamp().to_le_bytes());
+                record[8..10].copy_from_slice(&60u16.to_le_bytes());
+                record
+            })
+            .collect();
+        fs::write(&log, &full).unwrap();
+        for n in 1..=MAX_ROTATIONS {
+            fs::write(rotated_path(&log, n), format!("rotation {}", n)).unwrap();
+        }
+
+        let latest = PulseReading { bpm: 72, spo2: Some(98), at: local(2, 9, 30) };
+        append_hr_reading_to(&log, &latest).unwrap();
+
+        // The oldest rotation is gone and everything else moved up one.
+        assert_eq!(fs::read_to_string(rotated_path(&log, MAX_ROTATIONS)).unwrap(), format!("rotation {}", MAX_ROTATIONS - 1));
+        assert_eq!(fs::read(rotated_path(&log, 1)).unwrap(), full);
+        assert_eq!(fs::metadata(&log).unwrap().len(), RECORD_LEN as u64);
+
+        for n in 2..=MAX_ROTATIONS {
+            fs::remove_file(rotated_path(&log, n)).unwrap();
+        }
+        let readings = read_hr_log_from(&log).unwrap();
+        assert_eq!(readings.len(), full.len() / RECORD_LEN + 1);
+        assert_eq!(readings[0], reading(60, local(1, 8, 0)));
+        assert_eq!(readings.last(), Some(&latest));
+    }
+
+    #[test]
+    fn summaries_bucket_by_local_hour_and_day() {
+        let readings = [
+            reading(60, local(5, 9, 5)),
+            reading(80, local(5, 9, 55)),
+            reading(100, local(5, 10, 0)),
+            reading(50, local(6, 0, 30)),
+        ];
+
+        let hourly = summarize_by(&readings, HrPeriod::Hour);
+        assert_eq!(hourly.len(), 3);
+        assert_eq!(hourly[0].0, local(5, 9, 0).with_timezone(&Local).naive_local());
+        assert_eq!(hourly[0].1, HrSummary { min: 60, avg: 70, max: 80, count: 2 });
+
+        let daily = summarize_by(&readings, HrPeriod::Day);
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].1, HrSummary { min: 60, avg: 80, max: 100, count: 3 });
+        assert_eq!(daily[1].1, HrSummary { min: 50, avg: 50, max: 50, count: 1 });
+        assert_eq!(summarize(&[]), None);
+    }
+
+    #[test]
+    fn resting_rate_averages_the_lowest_tenth_of_the_last_day() {
+        let now = local(10, 12, 0);
+        // Twenty samples over the last day, and a very low one from before it.
+        let mut readings: Vec<PulseReading> = (0..20)
+            .map(|i| reading(80 - i, now - Duration::minutes(i as i64 * 60)))
+            .collect();
+        readings.push(reading(40, now - Duration::hours(25)));
+
+        assert_eq!(resting_hr(&readings, now), Some(61));
+        assert_eq!(resting_hr(&readings[20..], now), None);
+    }
+
+    #[test]
+    fn history_keeps_a_week_and_the_all_time_extremes() {
+        let now = local(20, 12, 0);
+        let mut history = HrHistory::new(
+            vec![reading(190, now - Duration::days(30)), reading(70, now - Duration::hours(30)), reading(65, now - Duration::hours(2))],
+            now,
+        );
+        assert_eq!(history.readings.len(), 2);
+        assert_eq!((history.low, history.high), (Some(65), Some(190)));
+        assert_eq!(history.chart, vec![(-2.0, 65.0)]);
+        assert_eq!(history.daily.len(), 2);
+
+        history.push(reading(45, now), now);
+        assert_eq!(history.low, Some(45));
+        assert_eq!(history.chart.last(), Some(&(0.0, 45.0)));
+        assert_eq!(history.resting, Some(45));
+
+        history.refresh(now + Duration::days(MEMORY_DAYS) + Duration::minutes(1));
+        assert!(history.readings.is_empty() && history.chart.is_empty());
+        assert_eq!((history.low, history.high), (Some(45), Some(190)));
+    }
+}
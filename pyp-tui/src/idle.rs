@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+// While idle the clock only needs to move once a second.
+pub const IDLE_TICK_MS: u64 = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdleAction {
+    Clock,
+    Blank,
+}
+
+impl IdleAction {
+    pub const ALL: [IdleAction; 2] = [IdleAction::Clock, IdleAction::Blank];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdleAction::Clock => "Clock",
+            IdleAction::Blank => "Blank",
+        }
+    }
+}
+
+pub trait DisplayPower {
+    fn set_power(&mut self, on: bool) -> io::Result<()>;
+}
+
+// DSI/SPI panels expose a backlight device; bl_power takes the fbdev blank
+// levels where 0 is on and 4 is powered down.
+pub struct BacklightPower {
+    bl_power: PathBuf,
+}
+
+impl DisplayPower for BacklightPower {
+    fn set_power(&mut self, on: bool) -> io::Result<()> {
+        fs::write(&self.bl_power, if on { "0" } else { "4" })
+    }
+}
+
+// HDMI on the Pi has no backlight node, so DPMS goes through the firmware.
+pub struct HdmiPower;
+
+impl DisplayPower for HdmiPower {
+    fn set_power(&mut self, on: bool) -> io::Result<()> {
+        let status = Command::new("vcgencmd")
+            .args(["display_power", if on { "1" } else { "0" }])
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::Other, "vcgencmd display_power failed"))
+        }
+    }
+}
+
+pub fn detect_display_power() -> Box<dyn DisplayPower> {
+    let backlight = fs::read_dir(BACKLIGHT_DIR)
+        .ok()
+        .and_then(|mut entries| entries.next())
+        .and_then(|entry| entry.ok())
+        .map(|entry| entry.path().join("bl_power"));
+
+    match backlight {
+        Some(bl_power) if bl_power.exists() => Box::new(BacklightPower { bl_power }),
+        _ => Box::new(HdmiPower),
+    }
+}
+
+pub struct IdleTimer {
+    last_input: Instant,
+    idle: bool,
+}
+
+impl Default for IdleTimer {
+    fn default() -> Self {
+        IdleTimer {
+            last_input: Instant::now(),
+            idle: false,
+        }
+    }
+}
+
+impl IdleTimer {
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    // Returns true when this call moves the timer into the idle state. A zero
+    // timeout never goes idle.
+    pub fn check(&mut self, timeout_secs: u32) -> bool {
+        self.check_at(timeout_secs, Instant::now())
+    }
+
+    fn check_at(&mut self, timeout_secs: u32, now: Instant) -> bool {
+        if self.idle || timeout_secs == 0 {
+            return false;
+        }
+        if now.saturating_duration_since(self.last_input) >= Duration::from_secs(timeout_secs as u64) {
+            self.idle = true;
+            return true;
+        }
+        false
+    }
+
+    // Records activity and returns whether the device was asleep, in which
+    // case the caller should swallow the event that woke it.
+    pub fn wake(&mut self) -> bool {
+        self.last_input = Instant::now();
+        std::mem::replace(&mut self.idle, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goes_idle_once_after_the_timeout() {
+        let mut timer = IdleTimer::default();
+        let start = timer.last_input;
+        assert!(!timer.check_at(60, start + Duration::from_secs(59)));
+        assert!(!timer.is_idle());
+        assert!(timer.check_at(60, start + Duration::from_secs(60)));
+        assert!(timer.is_idle());
+        // Only the call that crosses the timeout reports it.
+        assert!(!timer.check_at(60, start + Duration::from_secs(120)));
+        assert!(timer.is_idle());
+    }
+
+    #[test]
+    fn a_zero_timeout_never_goes_idle() {
+        let mut timer = IdleTimer::default();
+        let start = timer.last_input;
+        assert!(!timer.check_at(0, start + Duration::from_secs(86_400)));
+        assert!(!timer.is_idle());
+    }
+
+    #[test]
+    fn waking_reports_whether_it_was_asleep_and_restarts_the_timeout() {
+        let mut timer = IdleTimer::default();
+        assert!(!timer.wake());
+
+        let start = timer.last_input;
+        assert!(timer.check_at(60, start + Duration::from_secs(60)));
+        assert!(timer.wake());
+        assert!(!timer.is_idle());
+        assert!(!timer.wake());
+
+        let woke = timer.last_input;
+        assert!(woke >= start);
+        assert!(!timer.check_at(60, woke + Duration::from_secs(59)));
+        assert!(timer.check_at(60, woke + Duration::from_secs(60)));
+    }
+}
@@ -7,7 +7,8 @@ use crossterm::{
     event::{self, Event, KeyCode},
 };
 use std::io;
-use std::time::{Duration, Instant};
+
+use crate::theme::Theme;
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -38,19 +39,21 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 pub fn show_virtual_keyboard(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     kb_title: &str,
+    theme: &Theme,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let mut input = String::new();
-    let keyboard_layout = vec![
+    let keyboard_layout = [
         vec!['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J'],
         vec!['K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T'],
         vec!['U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3'],
         vec!['4', '5', '6', '7', '8', '9', ' ', '←', '<', ' '], 
+        vec!['.', ',', '-'],
     ];
 
     let mut cursor_pos = (0, 0);
 
     loop {
-        terminal.clear();
+        terminal.clear()?;
         terminal.draw(|f| {
             let area = centered_rect(70, 50, f.area());
             f.render_widget(Clear, area);
@@ -73,7 +76,7 @@ pub fn show_virtual_keyboard(
                                     Cell::from(Span::styled(
                                         format!("[{}]", content),
                                         Style::default()
-                                            .fg(Color::Yellow)
+                                            .fg(theme.highlight)
                                             .add_modifier(Modifier::BOLD),
                                     ))
                                 } else {
@@ -89,7 +92,8 @@ pub fn show_virtual_keyboard(
             let widths: Vec<Constraint> = vec![Constraint::Length(6); max_cols];
 
             let table = Table::new(rows, widths)
-                .block(Block::default().title("Keyboard").borders(Borders::ALL));
+                .style(Style::default().fg(theme.fg))
+                .block(Block::default().title("Keyboard").borders(Borders::ALL).border_style(Style::default().fg(theme.border)));
 
             f.render_widget(table, area);
 
@@ -101,7 +105,8 @@ pub fn show_virtual_keyboard(
             };
 
             let preview = Paragraph::new(format!("Input: {}", input))
-                .block(Block::default().borders(Borders::ALL).title(kb_title));
+                .style(Style::default().fg(theme.fg))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(theme.border)).title(kb_title));
             f.render_widget(preview, input_area);
         })?;
 
@@ -147,7 +152,7 @@ pub fn show_virtual_keyboard(
                     }
                 }
                 KeyCode::Char(c) => {
-                    if c.is_ascii_alphanumeric() || c == ' ' {
+                    if c.is_ascii_alphanumeric() || matches!(c, ' ' | '.' | ',' | '-') {
                         input.push(c);
                     }
                 }
@@ -0,0 +1,2 @@
+// The map file format, shared by the app and pyp-mappack.
+pub mod mapdata;
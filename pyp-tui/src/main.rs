@@ -1,44 +1,94 @@
-use chrono::prelude::*;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode},
+    event::{self, EnableMouseCapture, Event as CEvent, KeyCode},
 	execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen},
 };
-use rand::{distributions::Alphanumeric, prelude::*};
 
 use std::fs;
 use std::io;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
-use std::error::Error as StdError;
 use thiserror::Error;
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Line, Text},
-    widgets::{Block, BorderType, Borders, ListState, Paragraph, Tabs},
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Span, Line},
+    widgets::{Block, Borders, Clear, ListState, Paragraph, Tabs},
     Terminal,
     Frame,
 };
 
-use gpsd_client::*;
-
 extern crate linux_embedded_hal as hal;
 extern crate max3010x;
 extern crate ratatui;
 
-use max3010x::{Max3010x, Led, SampleAveraging};
-
 const DB_PATH: &str = "./data/db.json";
 
 mod render_tabs;
 mod menus;
 mod kb;
+mod pulse;
+mod hr_log;
+mod alarms;
+mod battery;
+mod status_bar;
+mod character;
+mod settings;
+mod coords;
+mod theme;
+mod crt;
+mod idle;
+mod quests;
+mod workshops;
+mod stats;
+mod holotapes;
+mod position;
+mod nmea;
+mod map_view;
+mod waypoints;
+mod tracks;
+mod places;
+mod astro;
+mod geofences;
+mod toasts;
+mod files;
+#[cfg(test)]
+mod testing;
 
-use render_tabs::{render_map, render_stat, render_data, render_inv, read_db, get_map_data, Item, add_item_to_db, show_quantity_selector,};
-use menus::{MenuItem, StatSubMenu, InvSubMenu, DataSubMenu};
+use render_tabs::{render_map, render_stat, render_inv, render_hr_summary, render_hr_chart, render_settings, show_setting_editor, render_alarm_alert, render_shutdown_warning, render_character_figure, render_character_fields, render_quest_list, render_quest_detail, render_workshop_list, render_workshop_detail, render_activity_stats, render_holotape_list, render_holotape_detail, render_waypoint_list, render_waypoint_detail, render_nearby_list, render_nearby_detail, render_track_list, render_track_detail, render_geofence_list, render_geofence_detail, GEOFENCE_CREATE_ROWS, render_coords, render_sky, render_compass_readout, render_gps_status, render_snr_bars, show_holotape_reader, write_db, show_choice_selector, read_db, get_map_data, Item, add_item_to_db, show_quantity_selector, show_number_selector, show_stepped_selector,};
+use pulse::{spawn_pulse_monitor, PulseReading};
+use hr_log::{append_hr_reading, read_hr_log, export_hr_csv, HrHistory};
+use battery::{spawn_battery_monitor, power_off, BatteryLevel, BatteryStatus, SHUTDOWN_COUNTDOWN};
+use character::{read_character, write_character, award_and_save, Character, Special, XpSource};
+use kb::show_virtual_keyboard;
+use status_bar::{render_status_bar, render_idle_clock, lookup_timezone, GpsStatus, StatusBar};
+use alarms::{AlarmEvent, AlarmLogKind, AlarmMonitor, log_alarm_event};
+use holotapes::{read_holotapes, write_holotapes, import_holotapes, Holotape};
+use stats::{read_activity_log, write_activity_log, summarize_stats, ActivityLog, StatsPeriod};
+use settings::{read_settings, write_settings, SettingField, Settings};
+use theme::Theme;
+use crt::{play_boot_sequence, CrtEffects};
+use idle::{detect_display_power, DisplayPower, IdleAction, IdleTimer, IDLE_TICK_MS};
+use quests::{read_quest_log, write_quest_log, Objective, Quest, QuestAction, QuestLog, QuestState};
+use astro::{local_day, DayTimes};
+use coords::{distance_m, parse_coordinates, CoordinateFormat};
+use workshops::{read_workshops, write_workshops, transfer_item, Workshop, WorkshopAction};
+use position::{spawn_position_service, PositionFix, PositionSource, SkyView};
+use pyp_tui::mapdata::read_map_data;
+use map_view::{draw_compass, draw_sky_plot, draw_local_map, draw_world_map, MapOverlays, MapView, WorldView};
+use places::{places_in, places_near, Place};
+use geofences::{read_geofences, write_geofences, read_geofence_log, complete_linked_objective, FenceShape, Geofence, GeofenceAction, GeofenceEvent, GeofenceEventKind, GeofenceMonitor, GeofenceWatch, ObjectiveLink};
+use files::write_atomic;
+use toasts::{render_toast, toast_area, Toasts};
+use reverse_geocoder::ReverseGeocoder;
+use waypoints::{read_waypoints, write_waypoints, NavTarget, Waypoint, WaypointAction, WaypointBook, WaypointIcon};
+use tracks::{read_tracks, delete_track, export_gpx, import_gpx, Breadcrumbs, Track, TrackAction, TrackPoint, TrackRecorder};
+use menus::{MenuItem, StatSubMenu, InvSubMenu, DataSubMenu, MapSubMenu};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -51,66 +101,96 @@ pub enum Error {
 enum Event<I> {
     Input(I),
     Tick,
+    Pulse(PulseReading),
+    Battery(BatteryStatus),
+    Position(Option<PositionFix>),
+    Sky(SkyView),
+    Geofence(GeofenceEvent),
 }
-#[derive(Debug, PartialEq)]
-pub struct Coordinates {
-    pub latitude: f64,
-    pub longitude: f64,
-}
-pub fn get_current_coordinates_array() -> Result<[f64; 2], Box<dyn StdError>> {
-    let mut gps = GPS::connect()
-    	.map_err(|e| Box::<dyn StdError>::from(format!("GPS connect error: {:?}", e)))?;
-	let data: GPSData = gps.current_data()
-    	.map_err(|e| Box::<dyn StdError>::from(format!("GPS data error: {:?}", e)))?;
-	if data.lat.is_finite() && data.lon.is_finite() {
-    	Ok([data.lat, data.lon])
-	} else {
-		Err(Box::new(io::Error::new(
-			io::ErrorKind::NotFound,
-			"Latitude or longitude invalid in GPS data",
-		)))
-	}
-}
+
+// Reverse geocoding loads the whole places table, so the MAP text is only
+// rebuilt this often while moving.
+const MAP_REFRESH: Duration = Duration::from_secs(60);
+// Smaller steps are mostly receiver jitter while standing still.
+const MIN_DISTANCE_STEP_M: f64 = 10.0;
+// The WORLD view asks the geocoder for the nearest place on a grid this many
+// steps across, which is about as many labels as fit on screen.
+const WORLD_LABEL_STEPS: usize = 6;
+// The NEARBY list keeps its order until you've walked this far, so the
+// selection doesn't jump around underneath you.
+const NEARBY_REFRESH_M: f64 = 250.0;
+const NEARBY_PLACES: usize = 12;
+// Rise and set times move by a few seconds per kilometre, so the SUN & MOON
+// panel keeps its times until the day changes or you've gone this far.
+const SKY_REFRESH_M: f64 = 1000.0;
+// Crossings kept for the FENCES screen.
+const GEOFENCE_HISTORY: usize = 50;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-	
-	let coords: [f64; 2] = match get_current_coordinates_array() {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Error getting coordinates: {}", e);
-            [0.0, 0.0]
-        }
-    };
+	let mut settings = read_settings().unwrap_or_default();
 
 	let mut map_data: Option<String> = None;
-	let mut start_time = Instant::now();
-    let one_minute = Duration::from_secs(60);
+	let mut map_refreshed: Option<Instant> = None;
+	let mut position: Option<[f64; 2]> = None;
+	let mut local_map = read_map_data().ok();
+	let mut map_view = MapView::default();
+	let mut world_view = WorldView::default();
+	let mut world_places: Vec<Place> = Vec::new();
+	// The centre, zoom and screen area the labels were looked up for.
+	let mut world_places_from: Option<([f64; 2], WorldView, ratatui::layout::Rect)> = None;
+	// Loading the places table takes a moment, so it waits until WORLD is opened.
+	let mut geocoder: Option<ReverseGeocoder> = None;
+	let mut last_fix: Option<PositionFix> = None;
+	let mut sky = SkyView::default();
+	let mut distance_anchor: Option<[f64; 2]> = None;
 
-	if map_data.is_none() || start_time.elapsed() >= one_minute {
-		start_time = Instant::now();
-        map_data = Some(get_map_data(coords));
-		println!("getting map data: {:?}", map_data)
-    }
-
-	let stat_submenu_titles = vec!["GENERAL", "STATUS", "SETTINGS"];
-	let inv_submenu_titles = vec!["WEAPONS", "APPAREL", "AID", "MISC", "JUNK", "MODS", "AMMO"];
-	let data_submenu_titles = vec!["QUESTS", "WORKSHOPS", "STATS"];
+	let stat_submenu_titles = ["GENERAL", "STATUS", "SETTINGS"];
+	let inv_submenu_titles = ["WEAPONS", "APPAREL", "AID", "MISC", "JUNK", "MODS", "AMMO"];
+	let data_submenu_titles = ["QUESTS", "WORKSHOPS", "STATS", "NOTES", "FENCES"];
+	let map_submenu_titles = ["LOCAL", "WORLD", "COMPASS", "COORDS", "GPS", "NEARBY", "WAYPOINTS", "TRACKS"];
 
 	let mut active_stat_submenu = StatSubMenu::General;
 	let mut active_inv_submenu = InvSubMenu::Weapons;
 	let mut active_data_submenu = DataSubMenu::Quests;
+	let mut active_map_submenu = MapSubMenu::Local;
 
 
     enable_raw_mode().expect("can run in raw mode");
 	let mut stdout = io::stdout();
 
 	execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-	
+
+    let stdout = io::stdout();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+	// Low-CPU mode is just these two switched off in settings.
+	let mut theme = Theme::from_settings(&settings);
+	let mut crt = CrtEffects::default();
+	if settings.boot_sequence {
+		play_boot_sequence(&mut terminal, &theme, settings.crt_effects)?;
+	}
 
     let (tx, rx) = mpsc::channel();
-    let tick_rate = Duration::from_millis(200);
+	let pulse_enabled = Arc::new(AtomicBool::new(settings.pulse_sensor));
+	let battery_enabled = Arc::new(AtomicBool::new(settings.battery_monitor));
+    spawn_pulse_monitor(tx.clone(), pulse_enabled.clone());
+	let simulate_battery = std::env::args().any(|arg| arg == "--sim-battery");
+	spawn_battery_monitor(tx.clone(), simulate_battery, battery_enabled.clone());
+	let gps_enabled = Arc::new(AtomicBool::new(settings.gps));
+	let gps_source = Arc::new(Mutex::new(PositionSource::from_settings(&settings)));
+	let geofence_watch = Arc::new(Mutex::new(GeofenceWatch {
+		fences: read_geofences().unwrap_or_default(),
+		monitor: GeofenceMonitor::default(),
+	}));
+	spawn_position_service(tx.clone(), gps_enabled.clone(), gps_source.clone(), geofence_watch.clone());
+
+	let tick_ms = Arc::new(AtomicU64::new(settings.tick_rate_ms as u64));
+	let input_tick_ms = tick_ms.clone();
     thread::spawn(move || {
         let mut last_tick = Instant::now();
         loop {
+			let tick_rate = Duration::from_millis(input_tick_ms.load(Ordering::Relaxed));
             let timeout = tick_rate
                 .checked_sub(last_tick.elapsed())
                 .unwrap_or_else(|| Duration::from_secs(0));
@@ -121,41 +201,146 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
-            if last_tick.elapsed() >= tick_rate {
-                if let Ok(_) = tx.send(Event::Tick) {
-                    last_tick = Instant::now();
-                }
+            if last_tick.elapsed() >= tick_rate && tx.send(Event::Tick).is_ok() {
+                last_tick = Instant::now();
             }
         }
     });
 
-    let stdout = io::stdout();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    let menu_titles = vec!["STAT", "INV", "DATA", "MAP", "RADIO"];
+    let menu_titles = ["STAT", "INV", "DATA", "MAP", "RADIO"];
     let mut active_menu_item = MenuItem::Stat;
-    let mut inv_list_state = &mut ListState::default();
+    let inv_list_state = &mut ListState::default();
     inv_list_state.select(Some(0));
 
-	/* let dev = hal::I2cdev::new("/dev/i2c-1").unwrap();
+	let mut hr_history = HrHistory::new(read_hr_log().unwrap_or_default(), chrono::Utc::now());
+	let mut hr_export_note: Option<String> = None;
+
+	let mut alarm_monitor = AlarmMonitor::default();
+	let mut pending_alarms: VecDeque<AlarmEvent> = VecDeque::new();
+	let mut alarm_flash = false;
+	let settings_list_state = &mut ListState::default();
+	settings_list_state.select(Some(0));
 
-	let mut sensor = Max3010x::new_max30102(dev);
-	let mut sensor = sensor.into_heart_rate().unwrap();
-	sensor.set_sample_averaging(SampleAveraging::Sa4).unwrap();
-	sensor.set_pulse_amplitude(Led::All, 15).unwrap();
-	sensor.enable_fifo_rollover().unwrap();
+	let mut character = read_character(&settings.active_profile).unwrap_or_default();
+	let mut character_dirty = false;
+	let character_list_state = &mut ListState::default();
+	character_list_state.select(Some(0));
 
-	let mut data = [0; 3];
-	let samples_read = sensor.read_fifo(&mut data).unwrap();
+	let mut quest_log = read_quest_log().unwrap_or_default();
+	let quest_list_state = &mut ListState::default();
+	quest_list_state.select(Some(0));
 
-  	let dev = sensor.destroy(); */
+	let mut workshops = read_workshops().unwrap_or_default();
+	let workshop_list_state = &mut ListState::default();
+	workshop_list_state.select(Some(0));
+
+	let mut holotapes = read_holotapes().unwrap_or_default();
+	if import_holotapes(&mut holotapes).unwrap_or(0) > 0 {
+		write_holotapes(&holotapes)?;
+	}
+	let holotape_list_state = &mut ListState::default();
+	holotape_list_state.select(Some(0));
+
+	let mut waypoint_book = read_waypoints().unwrap_or_default();
+	let waypoint_list_state = &mut ListState::default();
+	waypoint_list_state.select(Some(0));
+
+	let mut nearby: Vec<Place> = Vec::new();
+	// Where the list was built and how wide it looked.
+	let mut nearby_from: Option<([f64; 2], u32)> = None;
+	let nearby_list_state = &mut ListState::default();
+	nearby_list_state.select(Some(0));
+	let mut sky_times: Option<DayTimes> = None;
+
+	let mut tracks = read_tracks().unwrap_or_default();
+	let track_list_state = &mut ListState::default();
+	track_list_state.select(Some(0));
+	let mut recorder: Option<TrackRecorder> = None;
+	let mut breadcrumbs: Option<Breadcrumbs> = None;
+	let mut track_note: Option<String> = None;
+
+	// The position service checks fixes against its own copy, updated on edits.
+	let mut geofences = geofence_watch.lock().unwrap().fences.clone();
+	let geofence_list_state = &mut ListState::default();
+	geofence_list_state.select(Some(0));
+	let mut geofence_history = read_geofence_log(GEOFENCE_HISTORY).unwrap_or_default();
+	let mut toasts = Toasts::default();
+
+	let mut activity_log = read_activity_log().unwrap_or_default();
+	// Read again whenever the inventory is changed, not on every frame.
+	let mut inventory = read_db().unwrap_or_default();
+	let mut stats_period = StatsPeriod::Today;
+	let mut uptime_mark = Instant::now();
+
+	let mut status_bar = StatusBar::new(None, None, settings.time_format);
+	status_bar.quest = quest_log.current_quest().map(Quest::summary);
+	let mut shutdown_requested = false;
+	let mut shutdown_at: Option<Instant> = None;
+	let mut idle_timer = IdleTimer::default();
+	let mut display_power = detect_display_power();
 
     loop {
-		terminal.clear();
+		// A followed track takes over the arrow from any saved target. Range
+		// and bearing move with every fix, so this is rebuilt each pass.
+		let nav_target = breadcrumbs.as_ref().and_then(Breadcrumbs::target).or_else(|| waypoint_book.target.clone());
+		status_bar.nav = nav_target.as_ref().map(|t| t.guidance(position, settings.units));
+		if let (MenuItem::Map, MapSubMenu::Nearby, Some(here)) = (active_menu_item, active_map_submenu, position) {
+			let fresh = nearby_from.is_some_and(|(from, radius)| {
+				radius == settings.nearby_radius_km && distance_m(from, here) < NEARBY_REFRESH_M
+			});
+			if !fresh {
+				let radius_m = settings.nearby_radius_km as f64 * 1000.0;
+				nearby = places_near(geocoder.get_or_insert_with(ReverseGeocoder::new), here, radius_m, NEARBY_PLACES);
+				nearby_from = Some((here, settings.nearby_radius_km));
+				nearby_list_state.select(Some(0));
+			}
+		}
+		if let (MenuItem::Map, MapSubMenu::Coords, Some(here)) = (active_menu_item, active_map_submenu, position) {
+			let day = match status_bar.timezone {
+				Some(tz) => local_day(status_bar.clock, &tz),
+				None => local_day(status_bar.clock, &chrono::Local),
+			};
+			let fresh = sky_times.is_some_and(|times| times.day == day && distance_m(times.coordinates, here) < SKY_REFRESH_M);
+			if !fresh {
+				sky_times = Some(DayTimes::new(day, here));
+			}
+		}
+		// Loading the place list takes a moment, so do it here rather than
+		// halfway through drawing a frame.
+		if let (MenuItem::Map, MapSubMenu::World) = (active_menu_item, active_map_submenu) {
+			geocoder.get_or_insert_with(ReverseGeocoder::new);
+		}
+		let toast = toasts.current().map(str::to_string);
+		terminal.clear()?;
         terminal.draw(|rect| {
             let size = rect.area();
+			rect.render_widget(Block::default().style(Style::default().fg(theme.fg)), size);
+
+			if idle_timer.is_idle() {
+				if settings.idle_action == IdleAction::Clock {
+					rect.render_widget(render_idle_clock(&status_bar, &theme, size.height), size);
+				}
+				return;
+			}
+
+			if let Some(at) = shutdown_at {
+				let remaining = at.saturating_duration_since(Instant::now());
+				rect.render_widget(render_shutdown_warning(status_bar.battery.as_ref(), remaining, alarm_flash, &theme), size);
+				if settings.crt_effects {
+					crt.apply(rect.buffer_mut());
+				}
+				return;
+			}
+
+			if let Some(alarm) = pending_alarms.front() {
+				rect.render_widget(render_alarm_alert(alarm, alarm_flash, &theme), size);
+				if settings.crt_effects {
+					crt.apply(rect.buffer_mut());
+				}
+				return;
+			}
 			
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
@@ -170,16 +355,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 )
                 .split(size);
 			
-            let copyright = Paragraph::new("COPYRIGHT 2075 ROBCO(R)")
-                .style(Style::default().fg(Color::LightCyan))
-                .alignment(Alignment::Center)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .style(Style::default().fg(Color::White))
-                        .title("COPYRIGHT")
-                        .border_type(BorderType::Plain),
-                );
+            let copyright = render_status_bar(&status_bar, &theme);
 
             let menu: Vec<Line> = menu_titles
                 .iter()
@@ -189,19 +365,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         Span::styled(
                             first,
                             Style::default()
-                                .fg(Color::Yellow)
+                                .fg(theme.highlight)
                                 .add_modifier(Modifier::UNDERLINED),
                         ),
-                        Span::styled(rest, Style::default().fg(Color::White)),
+                        Span::styled(rest, Style::default().fg(theme.fg)),
                     ])
                 })
                 .collect();
 			
             let tabs = Tabs::new(menu)
                 .select(Some(active_menu_item.into()))
-                .block(Block::default().title("STAT").borders(Borders::ALL))
-                .style(Style::default().fg(Color::White))
-                .highlight_style(Style::default().fg(Color::Yellow))
+                .block(Block::default().title("STAT").borders(Borders::ALL).border_style(Style::default().fg(theme.border)))
+                .style(Style::default().fg(theme.fg))
+                .highlight_style(Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD))
                 .divider(Span::raw("|"));
 
 			let (submenu_spans, active_index) = match active_menu_item {
@@ -211,8 +387,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 						.map(|t| {
 							let (first, rest) = t.split_at(1);
 							Line::from(vec![
-								Span::styled(first, Style::default().fg(Color::Green).add_modifier(Modifier::UNDERLINED)),
-								Span::styled(rest, Style::default().fg(Color::White)),
+								Span::styled(first, Style::default().fg(theme.highlight).add_modifier(Modifier::UNDERLINED)),
+								Span::styled(rest, Style::default().fg(theme.fg)),
 							])
 						})
 						.collect::<Vec<Line>>(),
@@ -224,8 +400,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 						.map(|t| {
 							let (first, rest) = t.split_at(1);
 							Line::from(vec![
-								Span::styled(first, Style::default().fg(Color::Green).add_modifier(Modifier::UNDERLINED)),
-								Span::styled(rest, Style::default().fg(Color::White)),
+								Span::styled(first, Style::default().fg(theme.highlight).add_modifier(Modifier::UNDERLINED)),
+								Span::styled(rest, Style::default().fg(theme.fg)),
 							])
 						})
 						.collect::<Vec<Line>>(),
@@ -237,13 +413,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 						.map(|t| {
 							let (first, rest) = t.split_at(1);
 							Line::from(vec![
-								Span::styled(first, Style::default().fg(Color::Green).add_modifier(Modifier::UNDERLINED)),
-								Span::styled(rest, Style::default().fg(Color::White)),
+								Span::styled(first, Style::default().fg(theme.highlight).add_modifier(Modifier::UNDERLINED)),
+								Span::styled(rest, Style::default().fg(theme.fg)),
 							])
 						})
 						.collect::<Vec<Line>>(),
 					active_data_submenu.into(),
 				),
+				MenuItem::Map => (
+					map_submenu_titles
+						.iter()
+						.map(|t| {
+							let (first, rest) = t.split_at(1);
+							Line::from(vec![
+								Span::styled(first, Style::default().fg(theme.highlight).add_modifier(Modifier::UNDERLINED)),
+								Span::styled(rest, Style::default().fg(theme.fg)),
+							])
+						})
+						.collect::<Vec<Line>>(),
+					active_map_submenu.into(),
+				),
 				_ => (vec![], 0),
 			};
 
@@ -253,9 +442,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 			if show_secondary_menu {
 				let secondary_tabs = Tabs::new(submenu_spans)
 					.select(active_index)
-					.block(Block::default().title("SUBMENU").borders(Borders::ALL))
-					.style(Style::default().fg(Color::White))
-					.highlight_style(Style::default().fg(Color::Green))
+					.block(Block::default().title("SUBMENU").borders(Borders::ALL).border_style(Style::default().fg(theme.border)))
+					.style(Style::default().fg(theme.fg))
+					.highlight_style(Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD))
 					.divider(Span::raw("|"));
 
 				let adjusted_chunks = Layout::default()
@@ -277,23 +466,125 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 				match active_menu_item {
 					MenuItem::Stat => match active_stat_submenu {
-						StatSubMenu::General => rect.render_widget(render_stat_general(), adjusted_chunks[2]),
-						StatSubMenu::Status => rect.render_widget(render_stat_status(), adjusted_chunks[2]),
-						StatSubMenu::Settings => rect.render_widget(render_stat_settings(), adjusted_chunks[2]),
+						StatSubMenu::General => draw_character_sheet(rect, adjusted_chunks[2], &character, status_bar.battery.and_then(|b| b.percent), character_list_state, &theme),
+						StatSubMenu::Status => rect.render_widget(render_stat_status(&theme), adjusted_chunks[2]),
+						StatSubMenu::Settings => rect.render_stateful_widget(render_settings(&settings, &theme), adjusted_chunks[2], settings_list_state),
 					},
 					MenuItem::Inv => match active_inv_submenu {
-						InvSubMenu::Weapons => draw_filtered_inventory(rect, adjusted_chunks[2], &mut inv_list_state, "Weapons"),
-						InvSubMenu::Apparel => draw_filtered_inventory(rect, adjusted_chunks[2], &mut inv_list_state, "Apparel"),
-						InvSubMenu::Aid => draw_filtered_inventory(rect, adjusted_chunks[2], &mut inv_list_state, "Aid"),
-						InvSubMenu::Misc => draw_filtered_inventory(rect, adjusted_chunks[2], &mut inv_list_state, "Misc"),
-						InvSubMenu::Junk => draw_filtered_inventory(rect, adjusted_chunks[2], &mut inv_list_state, "Junk"),
-						InvSubMenu::Mods => draw_filtered_inventory(rect, adjusted_chunks[2], &mut inv_list_state, "Mods"),
-						InvSubMenu::Ammo => draw_filtered_inventory(rect, adjusted_chunks[2], &mut inv_list_state, "Ammo"),
+						InvSubMenu::Weapons => draw_filtered_inventory(rect, adjusted_chunks[2], inv_list_state, "Weapons", &theme),
+						InvSubMenu::Apparel => draw_filtered_inventory(rect, adjusted_chunks[2], inv_list_state, "Apparel", &theme),
+						InvSubMenu::Aid => draw_filtered_inventory(rect, adjusted_chunks[2], inv_list_state, "Aid", &theme),
+						InvSubMenu::Misc => draw_filtered_inventory(rect, adjusted_chunks[2], inv_list_state, "Misc", &theme),
+						InvSubMenu::Junk => draw_filtered_inventory(rect, adjusted_chunks[2], inv_list_state, "Junk", &theme),
+						InvSubMenu::Mods => draw_filtered_inventory(rect, adjusted_chunks[2], inv_list_state, "Mods", &theme),
+						InvSubMenu::Ammo => draw_filtered_inventory(rect, adjusted_chunks[2], inv_list_state, "Ammo", &theme),
 					},
 					MenuItem::Data => match active_data_submenu {
-						DataSubMenu::Quests => rect.render_widget(render_data_quests(), adjusted_chunks[2]),
-						DataSubMenu::Workshops => rect.render_widget(render_data_workshops(), adjusted_chunks[2]),
-						DataSubMenu::Stats => rect.render_widget(render_data_stats(), adjusted_chunks[2]),
+						DataSubMenu::Quests => draw_quest_log(rect, adjusted_chunks[2], &quest_log, quest_list_state, settings.coordinate_format, &theme),
+						DataSubMenu::Workshops => draw_workshops(rect, adjusted_chunks[2], &workshops, workshop_list_state, position, &settings, &theme),
+						DataSubMenu::Stats => draw_stats(rect, adjusted_chunks[2], &activity_log, &inventory, &quest_log, &hr_history, stats_period, hr_export_note.as_deref(), &settings, &theme),
+						DataSubMenu::Notes => draw_holotapes(rect, adjusted_chunks[2], &holotapes, holotape_list_state, settings.coordinate_format, &theme),
+						DataSubMenu::Fences => draw_geofences(
+							rect,
+							adjusted_chunks[2],
+							&geofences,
+							geofence_list_state,
+							&geofence_watch.lock().unwrap().monitor,
+							&geofence_history,
+							&quest_log,
+							&waypoint_book,
+							position,
+							&settings,
+							&theme,
+						),
+					},
+					MenuItem::Map => match active_map_submenu {
+						MapSubMenu::Local => {
+							let overlays = MapOverlays {
+								tracks: shown_tracks(&tracks, recorder.as_ref(), breadcrumbs.as_ref()),
+								waypoints: &waypoint_book.waypoints,
+								target: nav_target.as_ref(),
+							};
+							draw_local_map(rect, adjusted_chunks[2], local_map.as_mut(), &map_view, position, &overlays, settings.units, &theme);
+						}
+						MapSubMenu::World => {
+							let area = adjusted_chunks[2];
+							let center = position.or_else(|| local_map.as_ref().map(|m| m.center()));
+							// Sampling the grid is a few dozen geocoder searches, so it's
+							// only redone once the view has moved by a grid step.
+							if let (Some(center), Some(geocoder)) = (center, geocoder.as_ref()) {
+								let grid_step_m = world_view.half_width_m() * 2.0 / WORLD_LABEL_STEPS as f64;
+								let fresh = world_places_from.is_some_and(|(from, view, drawn)| {
+									view == world_view && drawn == area && distance_m(from, center) < grid_step_m
+								});
+								if !fresh {
+									let (south_west, north_east) = world_view.bounds(center, area);
+									world_places = places_in(geocoder, south_west, north_east, WORLD_LABEL_STEPS);
+									world_places_from = Some((center, world_view, area));
+								}
+							}
+							draw_world_map(rect, area, &world_view, center, &world_places, nav_target.as_ref(), settings.units, &theme);
+						}
+						MapSubMenu::Compass => {
+							let compass_chunks = Layout::default()
+								.direction(Direction::Horizontal)
+								.constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+								.split(adjusted_chunks[2]);
+							let heading = last_fix.as_ref().and_then(PositionFix::course);
+							draw_compass(rect, compass_chunks[0], heading, position, nav_target.as_ref(), &theme);
+							rect.render_widget(render_compass_readout(last_fix.as_ref(), nav_target.as_ref(), settings.units, &theme), compass_chunks[1]);
+						}
+						MapSubMenu::Coords => {
+							let coords_chunks = Layout::default()
+								.direction(Direction::Horizontal)
+								.constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+								.split(adjusted_chunks[2]);
+							let info_chunks = Layout::default()
+								.direction(Direction::Vertical)
+								.constraints([Constraint::Length(7), Constraint::Min(0)].as_ref())
+								.split(coords_chunks[1]);
+							rect.render_widget(render_coords(last_fix.as_ref(), settings.units, &theme), coords_chunks[0]);
+							rect.render_widget(render_map(map_data.clone(), &theme), info_chunks[0]);
+							rect.render_widget(
+								render_sky(position, sky_times.as_ref(), status_bar.clock, status_bar.timezone, settings.time_format, &theme),
+								info_chunks[1],
+							);
+						}
+						MapSubMenu::Gps => {
+							let gps_chunks = Layout::default()
+								.direction(Direction::Horizontal)
+								.constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+								.split(adjusted_chunks[2]);
+							let status_chunks = Layout::default()
+								.direction(Direction::Vertical)
+								.constraints([Constraint::Length(11), Constraint::Min(0)].as_ref())
+								.split(gps_chunks[0]);
+							rect.render_widget(render_gps_status(last_fix.as_ref(), &sky, settings.gps, &theme), status_chunks[0]);
+							rect.render_widget(render_snr_bars(&sky, &theme), status_chunks[1]);
+							draw_sky_plot(rect, gps_chunks[1], &sky, &theme);
+						}
+						MapSubMenu::Nearby => draw_nearby(
+							rect,
+							adjusted_chunks[2],
+							&nearby,
+							nearby_list_state,
+							position,
+							nav_target.as_ref(),
+							&settings,
+							&theme,
+						),
+						MapSubMenu::Waypoints => draw_waypoints(rect, adjusted_chunks[2], &waypoint_book, waypoint_list_state, position, &settings, &theme),
+						MapSubMenu::Tracks => draw_tracks(
+							rect,
+							adjusted_chunks[2],
+							&tracks,
+							track_list_state,
+							recorder.as_ref().map(|r| &r.track),
+							breadcrumbs.as_ref().map(|b| b.path.as_path()),
+							track_note.as_deref(),
+							&settings,
+							&theme,
+						),
 					},
 					_ => {}
 				}
@@ -302,16 +593,41 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 				rect.render_widget(copyright, adjusted_chunks[3]);
 			} else {
 				rect.render_widget(tabs, chunks[0]);
-				match active_menu_item {
-					MenuItem::Map => rect.render_widget(render_map(map_data.clone()), chunks[1]),
-					MenuItem::Radio => rect.render_widget(render_stat(), chunks[1]),
-					_ => {}
+				if active_menu_item == MenuItem::Radio {
+					rect.render_widget(render_stat(&theme), chunks[1]);
 				}
 				rect.render_widget(copyright, chunks[2]);
 			}
+
+			if let Some(text) = toast.as_deref() {
+				let area = toast_area(size);
+				rect.render_widget(Clear, area);
+				rect.render_widget(render_toast(text, &theme), area);
+			}
+
+			if settings.crt_effects {
+				crt.apply(rect.buffer_mut());
+			}
         })?;
 
-        match rx.recv()? {
+        let event = rx.recv()?;
+		if let Event::Input(_) = event {
+			// The press that wakes the screen shouldn't also act on whatever is under it.
+			if idle_timer.wake() {
+				leave_idle(display_power.as_mut(), &settings, &tick_ms);
+				continue;
+			}
+		}
+
+        match event {
+            Event::Input(event) if !pending_alarms.is_empty() => {
+				if event.code == KeyCode::Enter {
+					if let Some(mut alarm) = pending_alarms.pop_front() {
+						alarm.acknowledged_at = Some(chrono::Utc::now());
+						let _ = log_alarm_event(&alarm, AlarmLogKind::Acknowledged);
+					}
+				}
+			}
             Event::Input(event) => match event.code {
                 KeyCode::Esc => {
                     disable_raw_mode()?;
@@ -338,6 +654,115 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 					};
 				}
 
+                KeyCode::Down if active_menu_item == MenuItem::Stat && matches!(active_stat_submenu, StatSubMenu::Settings) => {
+					let selected = settings_list_state.selected().unwrap_or(0);
+					settings_list_state.select(Some((selected + 1) % SettingField::all().len()));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Stat && matches!(active_stat_submenu, StatSubMenu::General) => {
+					let rows = character_rows(&character);
+					let selected = character_list_state.selected().unwrap_or(0);
+					character_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Stat && matches!(active_stat_submenu, StatSubMenu::General) => {
+					let rows = character_rows(&character);
+					let selected = character_list_state.selected().unwrap_or(0);
+					character_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Stat && matches!(active_stat_submenu, StatSubMenu::Settings) => {
+					let selected = settings_list_state.selected().unwrap_or(0);
+					let rows = SettingField::all().len();
+					settings_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Quests) => {
+					let rows = quest_log.quests.len() + 1;
+					let selected = quest_list_state.selected().unwrap_or(0);
+					quest_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Quests) => {
+					let rows = quest_log.quests.len() + 1;
+					let selected = quest_list_state.selected().unwrap_or(0);
+					quest_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Workshops) => {
+					let rows = workshops.len() + 1;
+					let selected = workshop_list_state.selected().unwrap_or(0);
+					workshop_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Workshops) => {
+					let rows = workshops.len() + 1;
+					let selected = workshop_list_state.selected().unwrap_or(0);
+					workshop_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Notes) => {
+					let rows = holotapes.len() + 2;
+					let selected = holotape_list_state.selected().unwrap_or(0);
+					holotape_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Notes) => {
+					let rows = holotapes.len() + 2;
+					let selected = holotape_list_state.selected().unwrap_or(0);
+					holotape_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Fences) => {
+					let rows = geofences.len() + GEOFENCE_CREATE_ROWS.len();
+					let selected = geofence_list_state.selected().unwrap_or(0);
+					geofence_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Fences) => {
+					let rows = geofences.len() + GEOFENCE_CREATE_ROWS.len();
+					let selected = geofence_list_state.selected().unwrap_or(0);
+					geofence_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Stats) => {
+					stats_period = stats_period.next();
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Stats) => {
+					stats_period = stats_period.previous();
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Local) => map_view.zoom_out(),
+                KeyCode::Up if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Local) => map_view.zoom_in(),
+                KeyCode::Down if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::World) => world_view.zoom_out(),
+                KeyCode::Up if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::World) => world_view.zoom_in(),
+				KeyCode::Char(key @ ('w' | 'a' | 's' | 'd')) if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Local) => {
+					let (east, north) = match key {
+						'w' => (0.0, 1.0),
+						's' => (0.0, -1.0),
+						'a' => (-1.0, 0.0),
+						_ => (1.0, 0.0),
+					};
+					let fallback = local_map.as_ref().map(|m| m.center());
+					map_view.pan(map_view.center(position, fallback), east, north);
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Nearby) => {
+					let rows = nearby.len().max(1);
+					let selected = nearby_list_state.selected().unwrap_or(0);
+					nearby_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Nearby) => {
+					let rows = nearby.len().max(1);
+					let selected = nearby_list_state.selected().unwrap_or(0);
+					nearby_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Waypoints) => {
+					let rows = waypoint_book.waypoints.len() + 2;
+					let selected = waypoint_list_state.selected().unwrap_or(0);
+					waypoint_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Waypoints) => {
+					let rows = waypoint_book.waypoints.len() + 2;
+					let selected = waypoint_list_state.selected().unwrap_or(0);
+					waypoint_list_state.select(Some((selected + rows - 1) % rows));
+				}
+                KeyCode::Down if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Tracks) => {
+					let rows = tracks.len() + 2;
+					let selected = track_list_state.selected().unwrap_or(0);
+					track_list_state.select(Some((selected + 1) % rows));
+				}
+                KeyCode::Up if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Tracks) => {
+					let rows = tracks.len() + 2;
+					let selected = track_list_state.selected().unwrap_or(0);
+					track_list_state.select(Some((selected + rows - 1) % rows));
+				}
                 KeyCode::Down => {
                     if let Some(selected) = inv_list_state.selected() {
                         let amount_items = read_db().expect("can fetch item list").len();
@@ -368,15 +793,282 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 								.collect();
 							filtered_items.sort_by_key(|item| std::cmp::Reverse(item.created_at));
 							if selected == filtered_items.len() {
-								add_item_to_db().expect("can add new item");
+								add_item_to_db(&theme).expect("can add new item");
 								inv_list_state.select(Some(0));
 							} else {
 								let selected_item = &filtered_items[selected];
-								let new_item_quantity = show_quantity_selector(&mut terminal, selected_item.quantity)
+								let new_item_quantity = show_quantity_selector(&mut terminal, selected_item.quantity, &theme)
 									.map_err(|e| Error::ReadDBError(io::Error::new(io::ErrorKind::Other, e.to_string())))?;
-								update_selected_item_quantity((selected_item.id as usize).try_into().unwrap(), new_item_quantity as u32);
+								update_selected_item_quantity(selected_item.id, new_item_quantity)?;
+							}
+							inventory = read_db().unwrap_or_default();
+						}
+					} else if active_menu_item == MenuItem::Stat && matches!(active_stat_submenu, StatSubMenu::General) {
+						let selected = character_list_state.selected().unwrap_or(0);
+						let perk_start = 1 + Special::NAMES.len();
+						if selected == 0 {
+							let name = show_virtual_keyboard(&mut terminal, "Name", &theme)?;
+							if !name.trim().is_empty() {
+								character.name = name.trim().to_string();
+							}
+						} else if selected < perk_start {
+							let index = selected - 1;
+							let value = show_stepped_selector(&mut terminal, Special::NAMES[index], character.special.get(index) as u32, 1, 10, 1, &theme)?;
+							character.special.set(index, value as u8);
+						} else if selected < perk_start + character.perks.len() {
+							let perk = show_virtual_keyboard(&mut terminal, "Rename Perk (empty removes)", &theme)?;
+							if perk.trim().is_empty() {
+								character.perks.remove(selected - perk_start);
+								character_list_state.select(Some(selected - 1));
+							} else {
+								character.perks[selected - perk_start] = perk.trim().to_string();
+							}
+						} else {
+							let perk = show_virtual_keyboard(&mut terminal, "New Perk", &theme)?;
+							if !perk.trim().is_empty() {
+								character.perks.push(perk.trim().to_string());
+							}
+						}
+						write_character(&settings.active_profile, &character)?;
+					} else if active_menu_item == MenuItem::Stat && matches!(active_stat_submenu, StatSubMenu::Settings) {
+						let field = SettingField::all()[settings_list_state.selected().unwrap_or(0)];
+						let value = match show_setting_editor(&mut terminal, field.label(), settings.get(field), &theme) {
+							Ok(value) => value,
+							Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+							Err(e) => return Err(e.into()),
+						};
+						// Distance walked so far belongs to the profile being left.
+						if field == SettingField::ActiveProfile && std::mem::take(&mut character_dirty) {
+							write_character(&settings.active_profile, &character)?;
+						}
+						settings.set(field, value);
+						write_settings(&settings)?;
+
+						tick_ms.store(settings.tick_rate_ms as u64, Ordering::Relaxed);
+						pulse_enabled.store(settings.pulse_sensor, Ordering::Relaxed);
+						battery_enabled.store(settings.battery_monitor, Ordering::Relaxed);
+						if !settings.battery_monitor {
+							status_bar.battery = None;
+							shutdown_at = None;
+						}
+						gps_enabled.store(settings.gps, Ordering::Relaxed);
+						*gps_source.lock().unwrap() = PositionSource::from_settings(&settings);
+						if !settings.gps {
+							status_bar.gps = None;
+							position = None;
+							last_fix = None;
+							sky = SkyView::default();
+							distance_anchor = None;
+						}
+						status_bar.time_format = settings.time_format;
+						theme = Theme::from_settings(&settings);
+						match field {
+							SettingField::CoordinateFormat => {
+								map_data = position.map(|p| {
+									get_map_data(geocoder.get_or_insert_with(ReverseGeocoder::new), p, status_bar.timezone, settings.coordinate_format)
+								});
+							}
+							SettingField::ActiveProfile => {
+								character = read_character(&settings.active_profile).unwrap_or_default();
+								character_list_state.select(Some(0));
+							}
+							_ => {}
+						}
+					} else if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Quests) {
+						let selected = quest_list_state.selected().unwrap_or(0);
+						let completed = if selected == quest_log.quests.len() {
+							match create_quest(&mut terminal, &theme) {
+								Ok(Some(quest)) => {
+									quest_log.add(quest);
+									quest_list_state.select(Some(0));
+								}
+								Ok(None) => {}
+								Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+								Err(e) => return Err(e.into()),
+							}
+							false
+						} else {
+							match run_quest_action(&mut terminal, &mut quest_log, selected, position, &theme) {
+								Ok(completed) => completed,
+								Err(e) if e.kind() == io::ErrorKind::Interrupted => false,
+								Err(e) => return Err(e.into()),
+							}
+						};
+						if quest_list_state.selected().unwrap_or(0) > quest_log.quests.len() {
+							quest_list_state.select(Some(quest_log.quests.len()));
+						}
+						write_quest_log(&quest_log)?;
+						if completed {
+							award_and_save(&settings.active_profile, &mut character, XpSource::QuestCompleted)?;
+						}
+						status_bar.quest = quest_log.current_quest().map(Quest::summary);
+					} else if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Workshops) {
+						let selected = workshop_list_state.selected().unwrap_or(0);
+						let result = if selected == workshops.len() {
+							create_workshop(&mut terminal, position, &theme).map(|workshop| {
+								if let Some(workshop) = workshop {
+									workshops.push(workshop);
+								}
+							})
+						} else {
+							run_workshop_action(&mut terminal, &mut workshops, selected, position, &theme)
+						};
+						match result {
+							Ok(()) => {}
+							Err(Error::ReadDBError(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+							Err(e) => return Err(e.into()),
+						}
+						if selected > workshops.len() {
+							workshop_list_state.select(Some(workshops.len()));
+						}
+						write_workshops(&workshops)?;
+						inventory = read_db().unwrap_or_default();
+					} else if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Notes) {
+						let selected = holotape_list_state.selected().unwrap_or(0);
+						let result = if selected == holotapes.len() {
+							record_holotape(&mut terminal, position, &theme).map(|tape| {
+								if let Some(tape) = tape {
+									holotapes.insert(0, tape);
+									holotape_list_state.select(Some(0));
+								}
+							})
+						} else if selected == holotapes.len() + 1 {
+							import_holotapes(&mut holotapes).map(|_| ())
+						} else {
+							match show_choice_selector(&mut terminal, &holotapes[selected].title, &["Play", "Delete"], 0, &theme) {
+								Ok(0) => show_holotape_reader(&mut terminal, &holotapes[selected], settings.coordinate_format, &theme),
+								Ok(_) => {
+									holotapes.remove(selected);
+									Ok(())
+								}
+								Err(e) => Err(e),
+							}
+						};
+						match result {
+							Ok(()) => {}
+							Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+							Err(e) => return Err(e.into()),
+						}
+						write_holotapes(&holotapes)?;
+					} else if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Local) {
+						map_view.recenter();
+					} else if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Nearby) {
+						let selected = nearby_list_state.selected().unwrap_or(0);
+						if let Some(place) = nearby.get(selected) {
+							let target_before = waypoint_book.target.clone();
+							match run_nearby_action(&mut terminal, &mut waypoint_book, place, &theme) {
+								Ok(()) => {}
+								Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+								Err(e) => return Err(e.into()),
+							}
+							if waypoint_book.target.is_some() && waypoint_book.target != target_before {
+								breadcrumbs = None;
+							}
+							write_waypoints(&waypoint_book)?;
+						}
+					} else if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Waypoints) {
+						let selected = waypoint_list_state.selected().unwrap_or(0);
+						let target_before = waypoint_book.target.clone();
+						let result = if selected == waypoint_book.waypoints.len() && position.is_none() {
+							// Typing coordinates in is the next row; don't drop into it unasked.
+							toasts.push("NO GPS FIX TO MARK".to_string());
+							Ok(())
+						} else if selected >= waypoint_book.waypoints.len() {
+							let here = if selected == waypoint_book.waypoints.len() { position } else { None };
+							create_waypoint(&mut terminal, here, settings.coordinate_format, &theme).map(|waypoint| {
+								if let Some(waypoint) = waypoint {
+									waypoint_book.add(waypoint);
+									waypoint_list_state.select(Some(0));
+								}
+							})
+						} else {
+							run_waypoint_action(&mut terminal, &mut waypoint_book, selected, &theme)
+						};
+						match result {
+							Ok(()) => {}
+							Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+							Err(e) => return Err(e.into()),
+						}
+						if waypoint_list_state.selected().unwrap_or(0) > waypoint_book.waypoints.len() + 1 {
+							waypoint_list_state.select(Some(waypoint_book.waypoints.len() + 1));
+						}
+						if waypoint_book.target.is_some() && waypoint_book.target != target_before {
+							breadcrumbs = None;
+						}
+						write_waypoints(&waypoint_book)?;
+					} else if active_menu_item == MenuItem::Map && matches!(active_map_submenu, MapSubMenu::Tracks) {
+						let selected = track_list_state.selected().unwrap_or(0);
+						let following_before = breadcrumbs.clone();
+						let result = if selected == 0 {
+							match recorder.take() {
+								Some(active) => active.stop().map(|track| {
+									if let Some(track) = track {
+										tracks.insert(0, track);
+									}
+								}),
+								None => TrackRecorder::start().map(|started| recorder = Some(started)),
+							}
+						} else if selected == tracks.len() + 1 {
+							import_gpx(&mut tracks).map(|(imported, skipped)| {
+								track_note = Some(match skipped {
+									0 => format!("Imported {} tracks", imported),
+									_ => format!("Imported {} tracks, {} files were not valid GPX", imported, skipped),
+								});
+							})
+						} else {
+							run_track_action(&mut terminal, &mut tracks, selected - 1, &mut breadcrumbs, position, &theme)
+								.map(|note| track_note = note)
+						};
+						match result {
+							Ok(()) => {}
+							Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+							Err(e) => return Err(e.into()),
+						}
+						if track_list_state.selected().unwrap_or(0) > tracks.len() + 1 {
+							track_list_state.select(Some(tracks.len() + 1));
+						}
+						// Only one thing gets the arrow at a time.
+						if breadcrumbs.is_some() && breadcrumbs != following_before && waypoint_book.target.is_some() {
+							waypoint_book.target = None;
+							write_waypoints(&waypoint_book)?;
+						}
+					} else if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Fences) {
+						let selected = geofence_list_state.selected().unwrap_or(0);
+						let created = match selected.checked_sub(geofences.len()) {
+							None => run_geofence_action(&mut terminal, &mut geofences, selected, &quest_log, &theme).map(|()| None),
+							Some(0) => {
+								let anchors: Vec<(String, [f64; 2])> = waypoint_book.waypoints.iter().map(|w| (w.name.clone(), w.position)).collect();
+								create_circle_fence(&mut terminal, "Around Waypoint", &anchors, &theme)
 							}
+							Some(1) => {
+								let anchors: Vec<(String, [f64; 2])> = workshops
+									.iter()
+									.filter_map(|w| w.position.map(|position| (w.name.clone(), position)))
+									.collect();
+								create_circle_fence(&mut terminal, "Around Workshop", &anchors, &theme)
+							}
+							Some(_) => create_polygon_fence(&mut terminal, &waypoint_book, &theme),
+						};
+						match created {
+							Ok(Some(fence)) => {
+								geofences.push(fence);
+								geofence_list_state.select(Some(geofences.len() - 1));
+							}
+							Ok(None) => {}
+							Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+							Err(e) => return Err(e.into()),
+						}
+						if geofence_list_state.selected().unwrap_or(0) >= geofences.len() + GEOFENCE_CREATE_ROWS.len() {
+							geofence_list_state.select(Some(geofences.len()));
 						}
+						write_geofences(&geofences)?;
+						geofence_watch.lock().unwrap().fences = geofences.clone();
+					} else if active_menu_item == MenuItem::Data && matches!(active_data_submenu, DataSubMenu::Stats) {
+						// Only the last week is kept in memory, so export from the log itself.
+						hr_export_note = Some(match read_hr_log().and_then(|log| export_hr_csv(&log)) {
+							Ok((count, path)) => format!("Exported {} samples to {}", count, path),
+							Err(e) => format!("Export failed: {}", e),
+						});
 					}
 				}
 
@@ -403,9 +1095,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 						}
 						MenuItem::Data => {
 							active_data_submenu = match active_data_submenu {
-								DataSubMenu::Quests => DataSubMenu::Stats,
+								DataSubMenu::Quests => DataSubMenu::Fences,
 								DataSubMenu::Workshops => DataSubMenu::Quests,
 								DataSubMenu::Stats => DataSubMenu::Workshops,
+								DataSubMenu::Notes => DataSubMenu::Stats,
+								DataSubMenu::Fences => DataSubMenu::Notes,
+							};
+						}
+						MenuItem::Map => {
+							active_map_submenu = match active_map_submenu {
+								MapSubMenu::Local => MapSubMenu::Tracks,
+								MapSubMenu::World => MapSubMenu::Local,
+								MapSubMenu::Compass => MapSubMenu::World,
+								MapSubMenu::Coords => MapSubMenu::Compass,
+								MapSubMenu::Gps => MapSubMenu::Coords,
+								MapSubMenu::Nearby => MapSubMenu::Gps,
+								MapSubMenu::Waypoints => MapSubMenu::Nearby,
+								MapSubMenu::Tracks => MapSubMenu::Waypoints,
 							};
 						}
 						_ => {}
@@ -435,7 +1141,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 							active_data_submenu = match active_data_submenu {
 								DataSubMenu::Quests => DataSubMenu::Workshops,
 								DataSubMenu::Workshops => DataSubMenu::Stats,
-								DataSubMenu::Stats => DataSubMenu::Quests,
+								DataSubMenu::Stats => DataSubMenu::Notes,
+								DataSubMenu::Notes => DataSubMenu::Fences,
+								DataSubMenu::Fences => DataSubMenu::Quests,
+							};
+						}
+						MenuItem::Map => {
+							active_map_submenu = match active_map_submenu {
+								MapSubMenu::Local => MapSubMenu::World,
+								MapSubMenu::World => MapSubMenu::Compass,
+								MapSubMenu::Compass => MapSubMenu::Coords,
+								MapSubMenu::Coords => MapSubMenu::Gps,
+								MapSubMenu::Gps => MapSubMenu::Nearby,
+								MapSubMenu::Nearby => MapSubMenu::Waypoints,
+								MapSubMenu::Waypoints => MapSubMenu::Tracks,
+								MapSubMenu::Tracks => MapSubMenu::Local,
 							};
 						}
 						_ => {}
@@ -444,29 +1164,446 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                 _ => {}
             },
-            Event::Tick => {}
+            Event::Tick => {
+				if shutdown_at.is_some_and(|at| Instant::now() >= at) {
+					disable_raw_mode()?;
+					terminal.show_cursor()?;
+					ratatui::restore();
+					shutdown_requested = true;
+					break;
+				}
+				alarm_flash = !alarm_flash;
+				status_bar.tick();
+				if settings.crt_effects {
+					crt.advance();
+				}
+				if uptime_mark.elapsed() >= Duration::from_secs(60) {
+					activity_log.record_uptime(uptime_mark.elapsed().as_secs());
+					uptime_mark = Instant::now();
+					let _ = write_activity_log(&activity_log);
+					if std::mem::take(&mut character_dirty) {
+						let _ = write_character(&settings.active_profile, &character);
+					}
+					hr_history.refresh(chrono::Utc::now());
+				}
+				if idle_timer.check(settings.idle_timeout_s) {
+					tick_ms.store(IDLE_TICK_MS, Ordering::Relaxed);
+					if settings.idle_action == IdleAction::Blank {
+						let _ = display_power.set_power(false);
+					}
+				}
+			}
+            Event::Battery(status) => {
+				status_bar.battery = Some(status);
+				// Plugging in a charger before the countdown runs out calls it off.
+				if status.level != BatteryLevel::Critical {
+					shutdown_at = None;
+				} else if shutdown_at.is_none() {
+					shutdown_at = Some(Instant::now() + SHUTDOWN_COUNTDOWN);
+					if idle_timer.wake() {
+						leave_idle(display_power.as_mut(), &settings, &tick_ms);
+					}
+				}
+			}
+            Event::Position(fix) => {
+				// Late reports can still be queued right after GPS is switched off.
+				if !settings.gps {
+					continue;
+				}
+				status_bar.gps = fix.map(|fix| GpsStatus {
+					fix: fix.mode,
+					satellites: fix.satellites_used,
+				});
+				last_fix = fix;
+				if fix.is_none() {
+					sky = SkyView::default();
+				}
+				let coordinates = fix.and_then(|fix| fix.coordinates());
+				if coordinates.is_some() && position.is_none() {
+					activity_log.record_gps_fix();
+				}
+				position = coordinates;
+				let Some(here) = coordinates else {
+					continue;
+				};
+
+				if let (Some(active), Some(fix)) = (recorder.as_mut(), fix) {
+					// Same as the HR log: a missed point beats a crashed UI.
+					let _ = active.record(&fix, settings.track_interval_s);
+				}
+				if let Some(trail) = breadcrumbs.as_mut() {
+					trail.advance(here);
+					if trail.is_finished() {
+						breadcrumbs = None;
+					}
+				}
+
+				if map_refreshed.is_none_or(|at| at.elapsed() >= MAP_REFRESH) {
+					status_bar.timezone = lookup_timezone(here);
+					map_data = Some(get_map_data(geocoder.get_or_insert_with(ReverseGeocoder::new), here, status_bar.timezone, settings.coordinate_format));
+					map_refreshed = Some(Instant::now());
+				}
+
+				// The anchor stays put through poor or stationary fixes, so a
+				// receiver wandering on a table doesn't add up to a walk.
+				match distance_anchor {
+					_ if !fix.is_some_and(|fix| fix.counts_as_movement()) => {}
+					Some(anchor) => {
+						let step = distance_m(anchor, here);
+						if step >= MIN_DISTANCE_STEP_M {
+							activity_log.record_distance(step);
+							// Saved with the activity log, not on every step.
+							character.award_xp(XpSource::Distance(step));
+							character_dirty = true;
+							distance_anchor = Some(here);
+						}
+					}
+					None => distance_anchor = Some(here),
+				}
+			}
+            Event::Geofence(event) => {
+				// Already logged by the position service.
+				toasts.push(format!("{} {}", event.kind.as_str(), event.name));
+				let fence = geofences.iter().find(|f| f.id == event.fence);
+				if let (GeofenceEventKind::Enter, Some(fence)) = (event.kind, fence) {
+					if let Some((objective, completed)) = complete_linked_objective(fence, &mut quest_log) {
+						toasts.push(format!("OBJECTIVE COMPLETE: {}", objective));
+						write_quest_log(&quest_log)?;
+						if completed {
+							award_and_save(&settings.active_profile, &mut character, XpSource::QuestCompleted)?;
+						}
+						status_bar.quest = quest_log.current_quest().map(Quest::summary);
+					}
+				}
+				geofence_history.push(event);
+				if geofence_history.len() > GEOFENCE_HISTORY {
+					geofence_history.drain(..geofence_history.len() - GEOFENCE_HISTORY);
+				}
+				if idle_timer.wake() {
+					leave_idle(display_power.as_mut(), &settings, &tick_ms);
+				}
+			}
+            Event::Sky(view) => {
+				if settings.gps {
+					sky = view;
+				}
+			}
+            Event::Pulse(reading) => {
+				// Losing one sample to a full SD card shouldn't take the UI down with it.
+				let _ = append_hr_reading(&reading);
+				for alarm in alarm_monitor.check(&reading, &settings.alarms) {
+					let _ = log_alarm_event(&alarm, AlarmLogKind::Raised);
+					pending_alarms.push_back(alarm);
+					if idle_timer.wake() {
+						leave_idle(display_power.as_mut(), &settings, &tick_ms);
+					}
+				}
+				status_bar.last_pulse = Some(reading);
+				hr_history.push(reading, chrono::Utc::now());
+			}
         }
     }
 
+	activity_log.record_uptime(uptime_mark.elapsed().as_secs());
+	write_activity_log(&activity_log)?;
+	if character_dirty {
+		write_character(&settings.active_profile, &character)?;
+	}
+	if let Some(active) = recorder.take() {
+		active.stop()?;
+	}
+
+	if shutdown_requested {
+		eprintln!("Battery critical, shutting down");
+		if !simulate_battery {
+			power_off()?;
+		}
+	}
+
     Ok(())
 }
 
-fn draw_filtered_inventory<'a>(
-    rect: &mut Frame<'a>,
+fn leave_idle(display_power: &mut dyn DisplayPower, settings: &Settings, tick_ms: &AtomicU64) {
+	tick_ms.store(settings.tick_rate_ms as u64, Ordering::Relaxed);
+	if settings.idle_action == IdleAction::Blank {
+		let _ = display_power.set_power(true);
+	}
+}
+
+fn draw_filtered_inventory(
+    rect: &mut Frame,
     area: ratatui::layout::Rect,
     inv_list_state: &mut ListState,
     category: &str,
+    theme: &Theme,
 ) {
     let inv_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
         .split(area);
 
-    let (left, right) = render_inv(inv_list_state, category);
+    let (left, right) = render_inv(inv_list_state, category, theme);
     rect.render_stateful_widget(left, inv_chunks[0], inv_list_state);
     rect.render_widget(right, inv_chunks[1]);
 }
 
+fn character_rows(character: &Character) -> usize {
+	1 + Special::NAMES.len() + character.perks.len() + 1
+}
+
+fn draw_character_sheet(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    character: &Character,
+    battery_percent: Option<u8>,
+    character_list_state: &mut ListState,
+    theme: &Theme,
+) {
+    let sheet_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    rect.render_widget(render_character_figure(character, battery_percent, theme), sheet_chunks[0]);
+    rect.render_stateful_widget(render_character_fields(character, theme), sheet_chunks[1], character_list_state);
+}
+
+fn draw_quest_log(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    quest_log: &QuestLog,
+    quest_list_state: &mut ListState,
+    format: CoordinateFormat,
+    theme: &Theme,
+) {
+    let quest_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+        .split(area);
+
+    let quest = quest_list_state.selected().and_then(|i| quest_log.quests.get(i));
+    let is_current = quest.is_some_and(|q| quest_log.current == Some(q.id));
+    rect.render_stateful_widget(render_quest_list(quest_log, theme), quest_chunks[0], quest_list_state);
+    rect.render_widget(render_quest_detail(quest, is_current, format, theme), quest_chunks[1]);
+}
+
+fn draw_workshops(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    workshops: &[Workshop],
+    workshop_list_state: &mut ListState,
+    position: Option<[f64; 2]>,
+    settings: &Settings,
+    theme: &Theme,
+) {
+    let workshop_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    let workshop = workshop_list_state.selected().and_then(|i| workshops.get(i));
+    rect.render_stateful_widget(render_workshop_list(workshops, position, settings.units, theme), workshop_chunks[0], workshop_list_state);
+    rect.render_widget(
+        render_workshop_detail(workshop, position, settings.coordinate_format, settings.units, theme),
+        workshop_chunks[1],
+    );
+}
+
+fn draw_holotapes(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    holotapes: &[Holotape],
+    holotape_list_state: &mut ListState,
+    format: CoordinateFormat,
+    theme: &Theme,
+) {
+    let holotape_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+        .split(area);
+
+    let tape = holotape_list_state.selected().and_then(|i| holotapes.get(i));
+    rect.render_stateful_widget(render_holotape_list(holotapes, theme), holotape_chunks[0], holotape_list_state);
+    rect.render_widget(render_holotape_detail(tape, format, theme), holotape_chunks[1]);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_geofences(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    fences: &[Geofence],
+    geofence_list_state: &mut ListState,
+    monitor: &GeofenceMonitor,
+    history: &[GeofenceEvent],
+    quest_log: &QuestLog,
+    book: &WaypointBook,
+    position: Option<[f64; 2]>,
+    settings: &Settings,
+    theme: &Theme,
+) {
+    let fence_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+        .split(area);
+
+    let selected = geofence_list_state.selected().unwrap_or(0);
+    let fence = fences.get(selected);
+    let hint = match selected.checked_sub(fences.len()) {
+        Some(0) if book.waypoints.is_empty() => "Save a waypoint first",
+        Some(0) => "Pick a waypoint, then a radius",
+        Some(1) => "Pick a workshop with a location, then a radius",
+        Some(_) if book.waypoints.len() < 3 => "Needs at least three saved waypoints",
+        _ => "Pick waypoints as corners, in order, then name it",
+    };
+    let inside: Vec<Option<bool>> = fences.iter().map(|f| monitor.is_inside(f)).collect();
+    // Only the selected fence's crossings, or everything on the "+" rows.
+    let history: Vec<GeofenceEvent> = history
+        .iter()
+        .filter(|event| fence.is_none_or(|f| f.id == event.fence))
+        .cloned()
+        .collect();
+    rect.render_stateful_widget(render_geofence_list(fences, &inside, theme), fence_chunks[0], geofence_list_state);
+    rect.render_widget(
+        render_geofence_detail(
+            fence,
+            fence.and_then(|f| monitor.is_inside(f)),
+            hint,
+            &history,
+            quest_log,
+            position,
+            settings.units,
+            theme,
+        ),
+        fence_chunks[1],
+    );
+}
+
+fn draw_waypoints(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    book: &WaypointBook,
+    waypoint_list_state: &mut ListState,
+    position: Option<[f64; 2]>,
+    settings: &Settings,
+    theme: &Theme,
+) {
+    let waypoint_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    let selected = waypoint_list_state.selected().unwrap_or(0);
+    rect.render_stateful_widget(render_waypoint_list(book, position, settings.units, theme), waypoint_chunks[0], waypoint_list_state);
+    rect.render_widget(
+        render_waypoint_detail(book, selected, position, settings.coordinate_format, settings.units, theme),
+        waypoint_chunks[1],
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_nearby(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    places: &[Place],
+    nearby_list_state: &mut ListState,
+    position: Option<[f64; 2]>,
+    target: Option<&NavTarget>,
+    settings: &Settings,
+    theme: &Theme,
+) {
+    let nearby_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    let selected = nearby_list_state.selected().unwrap_or(0);
+    rect.render_stateful_widget(render_nearby_list(places, position, target, settings.units, theme), nearby_chunks[0], nearby_list_state);
+    rect.render_widget(
+        render_nearby_detail(places.get(selected), position, target, settings.nearby_radius_km, settings.coordinate_format, settings.units, theme),
+        nearby_chunks[1],
+    );
+}
+
+// Tracks to draw on the local map: anything switched on, the one being
+// followed and the one being recorded.
+fn shown_tracks<'a>(tracks: &'a [Track], recorder: Option<&'a TrackRecorder>, breadcrumbs: Option<&Breadcrumbs>) -> Vec<&'a [TrackPoint]> {
+    let mut shown: Vec<&[TrackPoint]> = tracks
+        .iter()
+        .filter(|t| t.shown || breadcrumbs.is_some_and(|b| b.path == t.path))
+        .map(|t| t.points.as_slice())
+        .collect();
+    shown.extend(recorder.map(|r| r.track.points.as_slice()));
+    shown
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_tracks(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    tracks: &[Track],
+    track_list_state: &mut ListState,
+    recording: Option<&Track>,
+    following: Option<&std::path::Path>,
+    note: Option<&str>,
+    settings: &Settings,
+    theme: &Theme,
+) {
+    let track_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    let selected = track_list_state.selected().unwrap_or(0);
+    let track = match selected {
+        0 => recording,
+        n => tracks.get(n - 1),
+    };
+    rect.render_stateful_widget(render_track_list(tracks, recording, following, settings.units, theme), track_chunks[0], track_list_state);
+    rect.render_widget(
+        render_track_detail(track, selected == 0, note, settings.track_interval_s, settings.units, theme),
+        track_chunks[1],
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_stats(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    activity_log: &ActivityLog,
+    items: &[Item],
+    quest_log: &QuestLog,
+    hr_history: &HrHistory,
+    period: StatsPeriod,
+    export_note: Option<&str>,
+    settings: &Settings,
+    theme: &Theme,
+) {
+    let stats_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    let summary = summarize_stats(activity_log, items, quest_log, hr_history, period);
+    rect.render_widget(render_activity_stats(&summary, period, settings.units, theme), stats_chunks[0]);
+    draw_hr_trends(rect, stats_chunks[1], hr_history, export_note, theme);
+}
+
+fn draw_hr_trends(
+    rect: &mut Frame,
+    area: ratatui::layout::Rect,
+    hr_history: &HrHistory,
+    export_note: Option<&str>,
+    theme: &Theme,
+) {
+    let hr_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+        .split(area);
+
+    rect.render_widget(render_hr_chart(&hr_history.chart, theme), hr_chunks[0]);
+    rect.render_widget(render_hr_summary(hr_history, export_note, theme), hr_chunks[1]);
+}
+
 fn update_selected_item_quantity(
     id: usize,
     new_quantity: u32,
@@ -486,7 +1623,7 @@ fn update_selected_item_quantity(
 
         if new_quantity > 0 {
             let updated_item = Item {
-                id: id.try_into().unwrap(),
+                id,
                 name,
                 details,
                 quantity: new_quantity,
@@ -497,28 +1634,437 @@ fn update_selected_item_quantity(
             parsed.push(updated_item);
         }
 
-        fs::write(DB_PATH, &serde_json::to_vec(&parsed)?)?;
+        write_atomic(DB_PATH, &serde_json::to_vec(&parsed)?)?;
     }
 
     Ok(())
 }
 
-fn render_stat_general<'a>() -> Paragraph<'a> {
-    Paragraph::new("General").block(Block::default().title("STAT"))
+fn render_stat_status<'a>(theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new("Status").block(Block::default().title("STAT").border_style(Style::default().fg(theme.border)))
 }
-fn render_stat_status<'a>() -> Paragraph<'a> {
-    Paragraph::new("Status").block(Block::default().title("STAT"))
+
+fn keyboard_entry(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, title: &str, theme: &Theme) -> io::Result<String> {
+    show_virtual_keyboard(terminal, title, theme)
+        .map(|text| text.trim().to_string())
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
 }
-fn render_stat_settings<'a>() -> Paragraph<'a> {
-    Paragraph::new("Settings").block(Block::default().title("STAT"))
+
+fn create_quest(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, theme: &Theme) -> io::Result<Option<Quest>> {
+    let title = keyboard_entry(terminal, "Quest Title (empty cancels)", theme)?;
+    if title.is_empty() {
+        return Ok(None);
+    }
+    let description = keyboard_entry(terminal, "Description", theme)?;
+    let mut objectives = Vec::new();
+    loop {
+        let objective = keyboard_entry(terminal, "Objective (empty to finish)", theme)?;
+        if objective.is_empty() {
+            break;
+        }
+        objectives.push(objective);
+    }
+    Ok(Some(Quest::new(title, description, objectives)))
 }
 
-fn render_data_quests<'a>() -> Paragraph<'a> {
-    Paragraph::new("Quests").block(Block::default().title("DATA"))
+// Returns true when the action completed the quest.
+fn run_quest_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    quest_log: &mut QuestLog,
+    index: usize,
+    position: Option<[f64; 2]>,
+    theme: &Theme,
+) -> io::Result<bool> {
+    let actions = QuestAction::available(quest_log.quests[index].state);
+    let labels: Vec<&str> = actions.iter().map(|a| a.as_str()).collect();
+    let action = actions[show_choice_selector(terminal, "Quest", &labels, 0, theme)?];
+
+    let quest = &mut quest_log.quests[index];
+    let completed = match action {
+        QuestAction::SetCurrent => {
+            quest_log.current = Some(quest.id);
+            false
+        }
+        QuestAction::ToggleObjective if !quest.objectives.is_empty() => {
+            let labels: Vec<String> = quest
+                .objectives
+                .iter()
+                .map(|o| format!("{} {}", if o.done { "[X]" } else { "[ ]" }, o.text))
+                .collect();
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+            let objective = show_choice_selector(terminal, "Toggle Objective", &labels, 0, theme)?;
+            quest.toggle_objective(objective)
+        }
+        QuestAction::ToggleObjective => false,
+        QuestAction::AddObjective => {
+            let text = keyboard_entry(terminal, "New Objective", theme)?;
+            if !text.is_empty() {
+                quest.objectives.push(Objective { text, done: false });
+            }
+            false
+        }
+        QuestAction::SetDueDate => {
+            let today = chrono::Local::now().date_naive();
+            let current = quest.due.map(|due| (due - today).num_days().max(0) as u32).unwrap_or(0);
+            let days = show_number_selector(terminal, "Due In Days (0 clears)", current, 90, theme)?;
+            quest.due = (days > 0).then(|| today + chrono::Duration::days(days as i64));
+            false
+        }
+        QuestAction::SetWaypoint => {
+            if position.is_some() {
+                quest.waypoint = position;
+            }
+            false
+        }
+        QuestAction::Complete => quest.set_state(QuestState::Completed),
+        QuestAction::Fail => quest.set_state(QuestState::Failed),
+        QuestAction::Reactivate => quest.set_state(QuestState::Active),
+        QuestAction::Delete => {
+            quest_log.remove(index);
+            false
+        }
+    };
+    Ok(completed)
+}
+fn record_holotape(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    position: Option<[f64; 2]>,
+    theme: &Theme,
+) -> io::Result<Option<Holotape>> {
+    let title = keyboard_entry(terminal, "Holotape Title (empty cancels)", theme)?;
+    if title.is_empty() {
+        return Ok(None);
+    }
+    let mut body = Vec::new();
+    loop {
+        let line = keyboard_entry(terminal, "Line (empty to finish)", theme)?;
+        if line.is_empty() {
+            break;
+        }
+        body.push(line);
+    }
+    let position = match position {
+        Some(_) if show_choice_selector(terminal, "Tag Current Position?", &["Yes", "No"], 0, theme)? == 0 => position,
+        _ => None,
+    };
+    Ok(Some(Holotape::new(title, body.join("\n"), position)))
+}
+
+fn create_workshop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    position: Option<[f64; 2]>,
+    theme: &Theme,
+) -> Result<Option<Workshop>, Error> {
+    let name = keyboard_entry(terminal, "Workshop Name (empty cancels)", theme)?;
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let notes = keyboard_entry(terminal, "Notes", theme)?;
+    Ok(Some(Workshop::new(name, notes, position)))
+}
+
+fn item_labels(items: &[Item]) -> Vec<String> {
+    items
+        .iter()
+        .map(|item| format!("{} x{} ({})", item.name, item.quantity, item.category))
+        .collect()
+}
+
+fn run_workshop_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    workshops: &mut Vec<Workshop>,
+    index: usize,
+    position: Option<[f64; 2]>,
+    theme: &Theme,
+) -> Result<(), Error> {
+    let db_error = |e: render_tabs::Error| Error::ReadDBError(io::Error::new(io::ErrorKind::Other, e.to_string()));
+    let labels: Vec<&str> = WorkshopAction::ALL.iter().map(|a| a.as_str()).collect();
+    let action = WorkshopAction::ALL[show_choice_selector(terminal, &workshops[index].name, &labels, 0, theme)?];
+    let workshop = &mut workshops[index];
+
+    match action {
+        WorkshopAction::Deposit | WorkshopAction::Withdraw => {
+            let mut inventory = read_db().map_err(db_error)?;
+            let (from, to) = if action == WorkshopAction::Deposit {
+                (&mut inventory, &mut workshop.stash)
+            } else {
+                (&mut workshop.stash, &mut inventory)
+            };
+            if from.is_empty() {
+                return Ok(());
+            }
+            let labels = item_labels(from);
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+            let item = show_choice_selector(terminal, action.as_str(), &labels, 0, theme)?;
+            let quantity = show_number_selector(terminal, "Quantity", from[item].quantity, from[item].quantity, theme)?;
+            transfer_item(from, item, quantity, to);
+            // Each file is replaced atomically but the pair is not: the side
+            // giving the item up is saved first, so a crash in between can
+            // lose the move but never duplicate the item.
+            if action == WorkshopAction::Deposit {
+                write_db(&inventory).map_err(db_error)?;
+                write_workshops(workshops)?;
+            } else {
+                write_workshops(workshops)?;
+                write_db(&inventory).map_err(db_error)?;
+            }
+        }
+        WorkshopAction::EditNotes => workshop.notes = keyboard_entry(terminal, "Notes", theme)?,
+        WorkshopAction::Rename => {
+            let name = keyboard_entry(terminal, "Workshop Name", theme)?;
+            if !name.is_empty() {
+                workshop.name = name;
+            }
+        }
+        WorkshopAction::UpdatePosition => {
+            if position.is_some() {
+                workshop.position = position;
+            }
+        }
+        WorkshopAction::Delete => {
+            // Whatever was stashed there goes back into the pack.
+            let mut inventory = read_db().map_err(db_error)?;
+            let mut removed = workshops.remove(index);
+            while !removed.stash.is_empty() {
+                let quantity = removed.stash[0].quantity;
+                transfer_item(&mut removed.stash, 0, quantity, &mut inventory);
+            }
+            write_workshops(workshops)?;
+            write_db(&inventory).map_err(db_error)?;
+        }
+    }
+    Ok(())
+}
+
+fn choose_waypoint_icon(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    current: WaypointIcon,
+    theme: &Theme,
+) -> io::Result<WaypointIcon> {
+    let labels: Vec<String> = WaypointIcon::ALL
+        .iter()
+        .map(|icon| format!("{} {}", icon.glyph(), icon.as_str()))
+        .collect();
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let initial = WaypointIcon::ALL.iter().position(|&icon| icon == current).unwrap_or(0);
+    Ok(WaypointIcon::ALL[show_choice_selector(terminal, "Icon", &labels, initial, theme)?])
+}
+
+// Marks `here` when given, otherwise asks for a typed position in whichever
+// format the user picks, starting from the one set for display.
+fn create_waypoint(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    here: Option<[f64; 2]>,
+    format: CoordinateFormat,
+    theme: &Theme,
+) -> io::Result<Option<Waypoint>> {
+    let position = match here {
+        Some(here) => here,
+        None => {
+            let labels: Vec<&str> = CoordinateFormat::ALL.iter().map(|f| f.as_str()).collect();
+            let initial = CoordinateFormat::ALL.iter().position(|&f| f == format).unwrap_or(0);
+            let format = CoordinateFormat::ALL[show_choice_selector(terminal, "Coordinate Format", &labels, initial, theme)?];
+            let mut title = format!("{}, e.g. {}", format.as_str(), format.example());
+            loop {
+                let text = keyboard_entry(terminal, &title, theme)?;
+                if text.is_empty() {
+                    return Ok(None);
+                }
+                match parse_coordinates(&text, format) {
+                    Some(position) => break position,
+                    None => title = format!("Not {}, try again (empty cancels)", format.as_str()),
+                }
+            }
+        }
+    };
+    let name = keyboard_entry(terminal, "Waypoint Name (empty cancels)", theme)?;
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let icon = choose_waypoint_icon(terminal, WaypointIcon::Other, theme)?;
+    Ok(Some(Waypoint::new(name, icon, position)))
 }
-fn render_data_workshops<'a>() -> Paragraph<'a> {
-    Paragraph::new("Workshops").block(Block::default().title("DATA"))
+
+fn run_nearby_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    book: &mut WaypointBook,
+    place: &Place,
+    theme: &Theme,
+) -> io::Result<()> {
+    let is_target = place.is_target(book.target.as_ref());
+    let navigate = if is_target { "Stop Navigating" } else { "Navigate Here" };
+    match show_choice_selector(terminal, &place.name, &[navigate, "Save as Waypoint"], 0, theme)? {
+        0 if is_target => book.target = None,
+        0 => book.target = Some(place.nav_target()),
+        _ => book.add(Waypoint::new(place.name.clone(), WaypointIcon::Settlement, place.position)),
+    }
+    Ok(())
+}
+
+fn run_waypoint_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    book: &mut WaypointBook,
+    index: usize,
+    theme: &Theme,
+) -> io::Result<()> {
+    let actions = WaypointAction::available(book.is_target(&book.waypoints[index]));
+    let labels: Vec<&str> = actions.iter().map(|a| a.as_str()).collect();
+    let action = actions[show_choice_selector(terminal, &book.waypoints[index].name, &labels, 0, theme)?];
+
+    match action {
+        WaypointAction::Navigate => book.target = Some(NavTarget::to_waypoint(&book.waypoints[index])),
+        WaypointAction::StopNavigating => book.target = None,
+        WaypointAction::Rename => {
+            let name = keyboard_entry(terminal, "Waypoint Name", theme)?;
+            if !name.is_empty() {
+                book.rename(index, name);
+            }
+        }
+        WaypointAction::ChangeIcon => {
+            book.waypoints[index].icon = choose_waypoint_icon(terminal, book.waypoints[index].icon, theme)?;
+        }
+        WaypointAction::Delete => book.remove(index),
+    }
+    Ok(())
 }
-fn render_data_stats<'a>() -> Paragraph<'a> {
-    Paragraph::new("Stats").block(Block::default().title("DATA"))
+
+// Returns a note for the track screen when the action has something to report.
+fn run_track_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    tracks: &mut Vec<Track>,
+    index: usize,
+    breadcrumbs: &mut Option<Breadcrumbs>,
+    position: Option<[f64; 2]>,
+    theme: &Theme,
+) -> io::Result<Option<String>> {
+    let following = breadcrumbs.as_ref().is_some_and(|b| b.path == tracks[index].path);
+    let actions = TrackAction::available(tracks[index].shown, following);
+    let labels: Vec<&str> = actions.iter().map(|a| a.as_str()).collect();
+    let action = actions[show_choice_selector(terminal, &tracks[index].name, &labels, 0, theme)?];
+
+    match action {
+        TrackAction::Show => tracks[index].shown = true,
+        TrackAction::Hide => tracks[index].shown = false,
+        TrackAction::Follow => *breadcrumbs = Some(Breadcrumbs::new(&tracks[index], position)),
+        TrackAction::StopFollowing => *breadcrumbs = None,
+        TrackAction::ExportGpx => {
+            let path = export_gpx(&tracks[index])?;
+            return Ok(Some(format!("Exported to {}", path.display())));
+        }
+        TrackAction::Delete => {
+            delete_track(&tracks[index])?;
+            tracks.remove(index);
+            if following {
+                *breadcrumbs = None;
+            }
+        }
+    }
+    Ok(None)
+}
+
+// A circle around one of `anchors`, named after it.
+fn create_circle_fence(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    title: &str,
+    anchors: &[(String, [f64; 2])],
+    theme: &Theme,
+) -> io::Result<Option<Geofence>> {
+    if anchors.is_empty() {
+        return Ok(None);
+    }
+    let labels: Vec<&str> = anchors.iter().map(|(name, _)| name.as_str()).collect();
+    let (name, center) = anchors[show_choice_selector(terminal, title, &labels, 0, theme)?].clone();
+    let radius = show_stepped_selector(terminal, "Radius (m)", 50, 10, 1000, 10, theme)?;
+    Ok(Some(Geofence::new(name, FenceShape::Circle { center, radius_m: radius as f64 })))
+}
+
+// Corners are saved waypoints, picked in the order they go around.
+fn create_polygon_fence(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    book: &WaypointBook,
+    theme: &Theme,
+) -> io::Result<Option<Geofence>> {
+    if book.waypoints.len() < 3 {
+        return Ok(None);
+    }
+    let mut corners: Vec<usize> = Vec::new();
+    loop {
+        let mut labels: Vec<String> = book
+            .waypoints
+            .iter()
+            .enumerate()
+            .map(|(i, w)| match corners.iter().position(|&c| c == i) {
+                Some(order) => format!("{}. {}", order + 1, w.name),
+                None => format!("   {}", w.name),
+            })
+            .collect();
+        labels.push(if corners.len() >= 3 { "Done".into() } else { "Done (needs 3 corners)".into() });
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let choice = show_choice_selector(terminal, &format!("Corner {}", corners.len() + 1), &labels, 0, theme)?;
+        if choice == book.waypoints.len() {
+            if corners.len() >= 3 {
+                break;
+            }
+        } else if let Some(at) = corners.iter().position(|&c| c == choice) {
+            corners.remove(at);
+        } else {
+            corners.push(choice);
+        }
+    }
+    let name = keyboard_entry(terminal, "Fence Name (empty cancels)", theme)?;
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let vertices = corners.iter().map(|&i| book.waypoints[i].position).collect();
+    Ok(Some(Geofence::new(name, FenceShape::Polygon { vertices })))
+}
+
+fn run_geofence_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    fences: &mut Vec<Geofence>,
+    index: usize,
+    quest_log: &QuestLog,
+    theme: &Theme,
+) -> io::Result<()> {
+    let actions = GeofenceAction::available(&fences[index]);
+    let labels: Vec<&str> = actions.iter().map(|a| a.as_str()).collect();
+    let action = actions[show_choice_selector(terminal, &fences[index].name, &labels, 0, theme)?];
+    match action {
+        GeofenceAction::LinkObjective => {
+            let quests: Vec<&Quest> = quest_log
+                .quests
+                .iter()
+                .filter(|q| q.state == QuestState::Active && q.objectives.iter().any(|o| !o.done))
+                .collect();
+            if quests.is_empty() {
+                return Ok(());
+            }
+            let titles: Vec<&str> = quests.iter().map(|q| q.title.as_str()).collect();
+            let quest = quests[show_choice_selector(terminal, "Quest", &titles, 0, theme)?];
+            let objectives: Vec<String> = quest
+                .objectives
+                .iter()
+                .map(|o| format!("{} {}", if o.done { "[X]" } else { "[ ]" }, o.text))
+                .collect();
+            let objectives: Vec<&str> = objectives.iter().map(String::as_str).collect();
+            let objective = show_choice_selector(terminal, "Objective", &objectives, 0, theme)?;
+            fences[index].objective = Some(ObjectiveLink { quest: quest.id, objective });
+        }
+        GeofenceAction::UnlinkObjective => fences[index].objective = None,
+        GeofenceAction::ChangeRadius => {
+            if let FenceShape::Circle { radius_m, .. } = &mut fences[index].shape {
+                *radius_m = show_stepped_selector(terminal, "Radius (m)", *radius_m as u32, 10, 1000, 10, theme)? as f64;
+            }
+        }
+        GeofenceAction::Rename => {
+            let name = keyboard_entry(terminal, "Fence Name (empty keeps it)", theme)?;
+            if !name.is_empty() {
+                fences[index].name = name;
+            }
+        }
+        GeofenceAction::Delete => {
+            fences.remove(index);
+        }
+    }
+    Ok(())
 }
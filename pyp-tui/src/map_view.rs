@@ -0,0 +1,558 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    symbols::Marker,
+    text::Span,
+    widgets::{
+        canvas::{Canvas, Circle, Line as CanvasLine, Map, MapResolution},
+        Block, Borders,
+    },
+    Frame,
+};
+
+use crate::coords::{bearing_deg, format_distance};
+use pyp_tui::mapdata::{FeatureKind, MapData};
+use crate::places::Place;
+use crate::position::SkyView;
+use crate::settings::Units;
+use crate::theme::Theme;
+use crate::tracks::TrackPoint;
+use crate::waypoints::{NavTarget, Waypoint};
+
+// Half the screen width at each zoom step, in meters.
+const ZOOM_LEVELS_M: [f64; 9] = [100.0, 200.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 10_000.0, 25_000.0, 50_000.0];
+const DEFAULT_ZOOM: usize = 3;
+const WORLD_ZOOM_LEVELS_M: [f64; 6] = [50_000.0, 100_000.0, 250_000.0, 500_000.0, 1_000_000.0, 2_500_000.0];
+const DEFAULT_WORLD_ZOOM: usize = 2;
+const METERS_PER_DEGREE: f64 = 111_320.0;
+// POI names start to pile on top of each other past this.
+const LABEL_MAX_HALF_WIDTH_M: f64 = 1_000.0;
+
+pub struct MapView {
+    zoom: usize,
+    center: Option<[f64; 2]>,
+    following: bool,
+}
+
+impl Default for MapView {
+    fn default() -> Self {
+        MapView {
+            zoom: DEFAULT_ZOOM,
+            center: None,
+            following: true,
+        }
+    }
+}
+
+impl MapView {
+    pub fn zoom_in(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom + 1).min(ZOOM_LEVELS_M.len() - 1);
+    }
+
+    pub fn half_width_m(&self) -> f64 {
+        ZOOM_LEVELS_M[self.zoom]
+    }
+
+    // While following, the view sits on the fix; once panned it stays put
+    // until recentered.
+    pub fn center(&self, position: Option<[f64; 2]>, fallback: Option<[f64; 2]>) -> Option<[f64; 2]> {
+        if self.following {
+            position.or(self.center).or(fallback)
+        } else {
+            self.center.or(position).or(fallback)
+        }
+    }
+
+    // Moves the view a quarter of its width per step.
+    pub fn pan(&mut self, from: Option<[f64; 2]>, east_steps: f64, north_steps: f64) {
+        let Some(from) = from else {
+            return;
+        };
+        let step = self.half_width_m() / 2.0;
+        self.center = Some(offset(from, east_steps * step, north_steps * step));
+        self.following = false;
+    }
+
+    pub fn recenter(&mut self) {
+        self.center = None;
+        self.following = true;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldView {
+    zoom: usize,
+}
+
+impl Default for WorldView {
+    fn default() -> Self {
+        WorldView { zoom: DEFAULT_WORLD_ZOOM }
+    }
+}
+
+impl WorldView {
+    pub fn zoom_in(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom + 1).min(WORLD_ZOOM_LEVELS_M.len() - 1);
+    }
+
+    pub fn half_width_m(&self) -> f64 {
+        WORLD_ZOOM_LEVELS_M[self.zoom]
+    }
+
+    // [south, west] and [north, east] corners of the view around `center`,
+    // shaped to the area the same way the local map is.
+    pub fn bounds(&self, center: [f64; 2], area: Rect) -> ([f64; 2], [f64; 2]) {
+        let cols = area.width.saturating_sub(2).max(1) as f64;
+        let rows = area.height.saturating_sub(2).max(1) as f64;
+        let half_w = self.half_width_m();
+        let half_h = half_w * 2.0 * rows / cols;
+        (offset(center, -half_w, -half_h), offset(center, half_w, half_h))
+    }
+}
+
+// Flat projection around the view center; plenty at the scales a handheld
+// map is read at.
+fn project(point: [f64; 2], center: [f64; 2]) -> (f64, f64) {
+    (
+        (point[1] - center[1]) * center[0].to_radians().cos() * METERS_PER_DEGREE,
+        (point[0] - center[0]) * METERS_PER_DEGREE,
+    )
+}
+
+fn offset(center: [f64; 2], east_m: f64, north_m: f64) -> [f64; 2] {
+    [
+        center[0] + north_m / METERS_PER_DEGREE,
+        center[1] + east_m / (METERS_PER_DEGREE * center[0].to_radians().cos().max(0.01)),
+    ]
+}
+
+// Liang-Barsky against the view box. The canvas drops any line with an end
+// off screen, which would blank every road that runs past the edge.
+fn clip(from: (f64, f64), to: (f64, f64), half_w: f64, half_h: f64) -> Option<((f64, f64), (f64, f64))> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (mut enter, mut exit) = (0.0f64, 1.0f64);
+    for (p, q) in [
+        (-dx, from.0 + half_w),
+        (dx, half_w - from.0),
+        (-dy, from.1 + half_h),
+        (dy, half_h - from.1),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            enter = enter.max(q / p);
+        } else {
+            exit = exit.min(q / p);
+        }
+    }
+    (enter <= exit).then_some((
+        (from.0 + enter * dx, from.1 + enter * dy),
+        (from.0 + exit * dx, from.1 + exit * dy),
+    ))
+}
+
+// Everything drawn over the map data itself.
+pub struct MapOverlays<'a> {
+    pub tracks: Vec<&'a [TrackPoint]>,
+    pub waypoints: &'a [Waypoint],
+    pub target: Option<&'a NavTarget>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_local_map(
+    frame: &mut Frame,
+    area: Rect,
+    map: Option<&mut MapData>,
+    view: &MapView,
+    position: Option<[f64; 2]>,
+    overlays: &MapOverlays,
+    units: Units,
+    theme: &Theme,
+) {
+    let has_map = map.is_some();
+    let fallback = map.as_ref().map(|m| m.center());
+    let center = view.center(position, fallback);
+    let half_w = view.half_width_m();
+    // Braille dots come out roughly square on a 1:2 terminal cell, so the
+    // vertical span follows the area's shape.
+    let cols = area.width.saturating_sub(2).max(1) as f64;
+    let rows = area.height.saturating_sub(2).max(1) as f64;
+    let half_h = half_w * 2.0 * rows / cols;
+
+    let title = format!(
+        " LOCAL {}{} ",
+        format_distance(half_w * 2.0, units),
+        if view.following { "" } else { " (PANNED)" }
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border));
+
+    let features = match (map, center) {
+        (Some(map), Some(center)) => map.features_in(offset(center, -half_w, -half_h), offset(center, half_w, half_h)),
+        _ => Vec::new(),
+    };
+
+    let canvas = Canvas::default()
+        .block(block)
+        .marker(Marker::Braille)
+        .x_bounds([-half_w, half_w])
+        .y_bounds([-half_h, half_h])
+        .paint(|ctx| {
+            let Some(center) = center else {
+                ctx.print(-half_w * 0.3, 0.0, Span::styled("WAITING FOR FIX", Style::default().fg(theme.dim)));
+                return;
+            };
+            if !has_map {
+                ctx.print(-half_w * 0.95, half_h * 0.9, Span::styled("NO MAP DATA", Style::default().fg(theme.dim)));
+            }
+
+            for feature in &features {
+                let color = match feature.kind {
+                    FeatureKind::Road => theme.fg,
+                    FeatureKind::Path | FeatureKind::Building => theme.dim,
+                    FeatureKind::Water | FeatureKind::Poi => theme.highlight,
+                };
+                if feature.kind == FeatureKind::Poi {
+                    let Some(&point) = feature.points.first() else {
+                        continue;
+                    };
+                    let (x, y) = project(point, center);
+                    if x.abs() <= half_w && y.abs() <= half_h {
+                        let label = match &feature.name {
+                            Some(name) if half_w <= LABEL_MAX_HALF_WIDTH_M => format!("* {}", name),
+                            _ => "*".to_string(),
+                        };
+                        ctx.print(x, y, Span::styled(label, Style::default().fg(color)));
+                    }
+                    continue;
+                }
+                for pair in feature.points.windows(2) {
+                    let from = project(pair[0], center);
+                    let to = project(pair[1], center);
+                    if let Some(((x1, y1), (x2, y2))) = clip(from, to, half_w, half_h) {
+                        ctx.draw(&CanvasLine { x1, y1, x2, y2, color });
+                    }
+                }
+            }
+
+            ctx.layer();
+            for track in &overlays.tracks {
+                for pair in track.windows(2) {
+                    let from = project(pair[0].position, center);
+                    let to = project(pair[1].position, center);
+                    if let Some(((x1, y1), (x2, y2))) = clip(from, to, half_w, half_h) {
+                        ctx.draw(&CanvasLine { x1, y1, x2, y2, color: theme.highlight });
+                    }
+                }
+            }
+            if let (Some(position), Some(target)) = (position, overlays.target) {
+                let from = project(position, center);
+                let to = project(target.position, center);
+                if let Some(((x1, y1), (x2, y2))) = clip(from, to, half_w, half_h) {
+                    ctx.draw(&CanvasLine { x1, y1, x2, y2, color: theme.warning });
+                }
+            }
+            for waypoint in overlays.waypoints {
+                let (x, y) = project(waypoint.position, center);
+                if x.abs() > half_w || y.abs() > half_h {
+                    continue;
+                }
+                let label = if half_w <= LABEL_MAX_HALF_WIDTH_M {
+                    format!("{} {}", waypoint.icon.glyph(), waypoint.name)
+                } else {
+                    waypoint.icon.glyph().to_string()
+                };
+                let is_target = overlays.target.is_some_and(|t| t.waypoint == Some(waypoint.id));
+                let color = if is_target { theme.warning } else { theme.highlight };
+                ctx.print(x, y, Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)));
+            }
+            if let Some(target) = overlays.target.filter(|t| t.waypoint.is_none()) {
+                let (x, y) = project(target.position, center);
+                if x.abs() <= half_w && y.abs() <= half_h {
+                    ctx.print(x, y, Span::styled(format!("x {}", target.name), Style::default().fg(theme.warning)));
+                }
+            }
+            if let Some(position) = position {
+                let (x, y) = project(position, center);
+                if x.abs() <= half_w && y.abs() <= half_h {
+                    ctx.print(
+                        x,
+                        y,
+                        Span::styled("@", Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD)),
+                    );
+                }
+            }
+        });
+
+    frame.render_widget(canvas, area);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_world_map(
+    frame: &mut Frame,
+    area: Rect,
+    view: &WorldView,
+    center: Option<[f64; 2]>,
+    places: &[Place],
+    target: Option<&NavTarget>,
+    units: Units,
+    theme: &Theme,
+) {
+    let title = format!(" WORLD {} ", format_distance(view.half_width_m() * 2.0, units));
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border));
+    let Some(center) = center else {
+        let canvas = Canvas::default().block(block).paint(|ctx| {
+            ctx.print(0.0, 0.0, Span::styled("WAITING FOR FIX", Style::default().fg(theme.dim)));
+        });
+        frame.render_widget(canvas, area);
+        return;
+    };
+
+    // Coastlines and borders come in plain degrees, so this canvas works in
+    // lon/lat rather than meters.
+    let (south_west, north_east) = view.bounds(center, area);
+    let canvas = Canvas::default()
+        .block(block)
+        .marker(Marker::Braille)
+        .x_bounds([south_west[1], north_east[1]])
+        .y_bounds([south_west[0], north_east[0]])
+        .paint(|ctx| {
+            ctx.draw(&Map { resolution: MapResolution::High, color: theme.dim });
+            ctx.layer();
+            if let Some(target) = target {
+                ctx.draw(&CanvasLine {
+                    x1: center[1],
+                    y1: center[0],
+                    x2: target.position[1],
+                    y2: target.position[0],
+                    color: theme.warning,
+                });
+            }
+            for place in places {
+                ctx.print(
+                    place.position[1],
+                    place.position[0],
+                    Span::styled(format!("• {}", place.name), Style::default().fg(theme.fg)),
+                );
+            }
+            ctx.print(
+                center[1],
+                center[0],
+                Span::styled("@", Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD)),
+            );
+        });
+    frame.render_widget(canvas, area);
+}
+
+// North-up rose with the course over ground and the bearing to the target
+// as needles from the middle.
+pub fn draw_compass(
+    frame: &mut Frame,
+    area: Rect,
+    heading: Option<f64>,
+    position: Option<[f64; 2]>,
+    target: Option<&NavTarget>,
+    theme: &Theme,
+) {
+    let cols = area.width.saturating_sub(2).max(1) as f64;
+    let rows = area.height.saturating_sub(2).max(1) as f64;
+    // Keeps the rose round on 1:2 terminal cells.
+    let half_w = (cols / (2.0 * rows)).max(1.0) * 1.3;
+    let half_h = (2.0 * rows / cols).max(1.0) * 1.3;
+    let point = |bearing: f64, radius: f64| {
+        let angle = bearing.to_radians();
+        (radius * angle.sin(), radius * angle.cos())
+    };
+    let bearing = match (position, target) {
+        (Some(from), Some(target)) => Some(bearing_deg(from, target.position)),
+        _ => None,
+    };
+
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .title(" COMPASS ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border)),
+        )
+        .marker(Marker::Braille)
+        .x_bounds([-half_w, half_w])
+        .y_bounds([-half_h, half_h])
+        .paint(|ctx| {
+            ctx.draw(&Circle { x: 0.0, y: 0.0, radius: 1.0, color: theme.dim });
+            for tick in (0..360).step_by(30) {
+                let (x1, y1) = point(tick as f64, 0.9);
+                let (x2, y2) = point(tick as f64, 1.0);
+                ctx.draw(&CanvasLine { x1, y1, x2, y2, color: theme.dim });
+            }
+            if let Some(bearing) = bearing {
+                let (x2, y2) = point(bearing, 0.85);
+                ctx.draw(&CanvasLine { x1: 0.0, y1: 0.0, x2, y2, color: theme.warning });
+            }
+            if let Some(heading) = heading {
+                let (x2, y2) = point(heading, 0.85);
+                ctx.draw(&CanvasLine { x1: 0.0, y1: 0.0, x2, y2, color: theme.highlight });
+            }
+            ctx.layer();
+            for (label, at) in [("N", 0.0), ("E", 90.0), ("S", 180.0), ("W", 270.0)] {
+                let (x, y) = point(at, 1.15);
+                ctx.print(x, y, Span::styled(label, Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)));
+            }
+            if let Some(bearing) = bearing {
+                let (x, y) = point(bearing, 0.95);
+                ctx.print(x, y, Span::styled("T", Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)));
+            }
+            if let Some(heading) = heading {
+                let (x, y) = point(heading, 0.95);
+                ctx.print(x, y, Span::styled("H", Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD)));
+            }
+        });
+    frame.render_widget(canvas, area);
+}
+
+// Where the satellites are: the rim is the horizon and the middle straight
+// up, with rings at 30 and 60 degrees of elevation. Bright labels are in the
+// fix, dim ones aren't heard at all.
+pub fn draw_sky_plot(frame: &mut Frame, area: Rect, sky: &SkyView, theme: &Theme) {
+    let cols = area.width.saturating_sub(2).max(1) as f64;
+    let rows = area.height.saturating_sub(2).max(1) as f64;
+    let half_w = (cols / (2.0 * rows)).max(1.0) * 1.2;
+    let half_h = (2.0 * rows / cols).max(1.0) * 1.2;
+    let point = |azimuth: f64, elevation: f64| {
+        let radius = (90.0 - elevation.clamp(0.0, 90.0)) / 90.0;
+        let angle = azimuth.to_radians();
+        (radius * angle.sin(), radius * angle.cos())
+    };
+
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .title(" SKY ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border)),
+        )
+        .marker(Marker::Braille)
+        .x_bounds([-half_w, half_w])
+        .y_bounds([-half_h, half_h])
+        .paint(|ctx| {
+            for radius in [1.0, 2.0 / 3.0, 1.0 / 3.0] {
+                ctx.draw(&Circle { x: 0.0, y: 0.0, radius, color: theme.dim });
+            }
+            ctx.draw(&CanvasLine { x1: -1.0, y1: 0.0, x2: 1.0, y2: 0.0, color: theme.dim });
+            ctx.draw(&CanvasLine { x1: 0.0, y1: -1.0, x2: 0.0, y2: 1.0, color: theme.dim });
+            ctx.layer();
+            for (label, at) in [("N", 0.0), ("E", 90.0), ("S", 180.0), ("W", 270.0)] {
+                let (x, y) = point(at, 0.0);
+                ctx.print(x * 1.12, y * 1.12, Span::styled(label, Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)));
+            }
+            for satellite in &sky.satellites {
+                let (Some(elevation), Some(azimuth)) = (satellite.elevation, satellite.azimuth) else {
+                    continue;
+                };
+                let style = match (satellite.used, satellite.snr.is_some_and(|snr| snr > 0)) {
+                    (true, _) => Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
+                    (false, true) => Style::default().fg(theme.fg),
+                    (false, false) => Style::default().fg(theme.dim),
+                };
+                let (x, y) = point(azimuth as f64, elevation as f64);
+                ctx.print(x, y, Span::styled(satellite.label(), style));
+            }
+        });
+    frame.render_widget(canvas, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Segment = ((f64, f64), (f64, f64));
+
+    // A 200 x 100 m view: x in -100..100, y in -50..50.
+    fn clipped(from: (f64, f64), to: (f64, f64)) -> Option<Segment> {
+        clip(from, to, 100.0, 50.0)
+    }
+
+    fn assert_segment(got: Option<Segment>, want: Segment) {
+        let ((a, b), (c, d)) = got.expect("segment was dropped");
+        let ((e, f), (g, h)) = want;
+        for (got, want) in [(a, e), (b, f), (c, g), (d, h)] {
+            assert!((got - want).abs() < 1e-9, "{:?} != {:?}", ((a, b), (c, d)), want);
+        }
+    }
+
+    #[test]
+    fn segments_inside_are_left_alone() {
+        assert_segment(clipped((-20.0, -10.0), (30.0, 40.0)), ((-20.0, -10.0), (30.0, 40.0)));
+        // Lying exactly on the edge still counts as inside.
+        assert_segment(clipped((-100.0, 50.0), (100.0, 50.0)), ((-100.0, 50.0), (100.0, 50.0)));
+        assert_segment(clipped((5.0, 5.0), (5.0, 5.0)), ((5.0, 5.0), (5.0, 5.0)));
+    }
+
+    #[test]
+    fn segments_crossing_an_edge_are_cut_there() {
+        assert_segment(clipped((0.0, 0.0), (200.0, 0.0)), ((0.0, 0.0), (100.0, 0.0)));
+        assert_segment(clipped((0.0, -80.0), (0.0, 0.0)), ((0.0, -50.0), (0.0, 0.0)));
+        // In one side and out the other, keeping the direction.
+        assert_segment(clipped((150.0, 25.0), (-150.0, 25.0)), ((100.0, 25.0), (-100.0, 25.0)));
+        assert_segment(clipped((-200.0, -100.0), (200.0, 100.0)), ((-100.0, -50.0), (100.0, 50.0)));
+        assert_segment(clipped((50.0, 0.0), (150.0, 100.0)), ((50.0, 0.0), (100.0, 50.0)));
+    }
+
+    #[test]
+    fn segments_outside_are_dropped() {
+        assert_eq!(clipped((120.0, 0.0), (300.0, 40.0)), None);
+        assert_eq!(clipped((-150.0, -60.0), (150.0, -90.0)), None);
+        // Both ends on different sides, but passing outside the corner.
+        assert_eq!(clipped((70.0, 90.0), (150.0, 10.0)), None);
+        assert_eq!(clipped((200.0, 200.0), (200.0, 200.0)), None);
+    }
+
+    #[test]
+    fn segments_parallel_to_an_edge() {
+        assert_eq!(clipped((-300.0, 60.0), (300.0, 60.0)), None);
+        assert_eq!(clipped((-101.0, -500.0), (-101.0, 500.0)), None);
+        assert_segment(clipped((-300.0, -20.0), (300.0, -20.0)), ((-100.0, -20.0), (100.0, -20.0)));
+        assert_segment(clipped((99.0, 500.0), (99.0, -500.0)), ((99.0, 50.0), (99.0, -50.0)));
+    }
+
+    #[test]
+    fn offset_and_project_undo_each_other() {
+        for center in [[0.0, 0.0], [51.5, -0.12], [-33.87, 151.21], [69.65, 18.96]] {
+            for (east, north) in [(0.0, 0.0), (1500.0, -250.0), (-40_000.0, 12_000.0)] {
+                let (x, y) = project(offset(center, east, north), center);
+                assert!((x - east).abs() < 1e-6 && (y - north).abs() < 1e-6, "{:?} {:?}: {:?}", center, (east, north), (x, y));
+            }
+        }
+        // North is up and east is right.
+        let (x, y) = project([51.51, -0.11], [51.5, -0.12]);
+        assert!(x > 0.0 && y > 0.0);
+    }
+
+    #[test]
+    fn world_bounds_follow_the_screen_shape() {
+        let view = WorldView::default();
+        let center = [51.5, -0.12];
+        // 80 x 20 cells inside the border: as tall as it is wide in braille dots.
+        let (south_west, north_east) = view.bounds(center, Rect::new(0, 0, 82, 22));
+        let (west, south) = project(south_west, center);
+        let (east, north) = project(north_east, center);
+        let half_w = view.half_width_m();
+        assert!((east - half_w).abs() < 1e-6 && (west + half_w).abs() < 1e-6);
+        assert!((north - half_w / 2.0).abs() < 1e-6 && (south + half_w / 2.0).abs() < 1e-6);
+    }
+}
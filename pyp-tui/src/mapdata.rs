@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+const MAP_PATH: &str = "./data/map.pypmap";
+pub const MAGIC: &[u8; 8] = b"PYPMAP\x01\x00";
+// Coordinates are stored as whole microdegrees, about 11 cm at the equator.
+pub const MICRODEGREES: f64 = 1_000_000.0;
+
+// Layout, all little-endian:
+//   magic, origin lat/lon (i32 µdeg), tile size (u32 µdeg), cols/rows (u16),
+//   then one (offset, length) u32 pair per tile, row-major from the south-west
+//   corner, followed by the tile blobs. A tile is a varint feature count and
+//   then per feature: kind byte, varint-prefixed name, varint point count and
+//   zigzag varint lat/lon deltas starting from the tile origin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FeatureKind {
+    Road,
+    Path,
+    Water,
+    Building,
+    Poi,
+}
+
+impl FeatureKind {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(FeatureKind::Road),
+            1 => Some(FeatureKind::Path),
+            2 => Some(FeatureKind::Water),
+            3 => Some(FeatureKind::Building),
+            4 => Some(FeatureKind::Poi),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Feature {
+    pub kind: FeatureKind,
+    pub name: Option<String>,
+    // [lat, lon] pairs; a single point for POIs.
+    pub points: Vec<[f64; 2]>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("bad map file: {}", message))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> io::Result<&[u8]> {
+        let end = self.at.checked_add(n).ok_or_else(|| invalid("truncated"))?;
+        let slice = self.bytes.get(self.at..end).ok_or_else(|| invalid("truncated"))?;
+        self.at += n;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    fn zigzag(&mut self) -> io::Result<i64> {
+        let raw = self.varint()?;
+        Ok((raw >> 1) as i64 ^ -((raw & 1) as i64))
+    }
+}
+
+pub struct MapData {
+    origin: [i32; 2],
+    tile_size: u32,
+    cols: u16,
+    rows: u16,
+    index: Vec<(u32, u32)>,
+    tiles: Vec<u8>,
+    // Tiles are decoded the first time they scroll into view.
+    decoded: HashMap<usize, Vec<Feature>>,
+}
+
+impl MapData {
+    pub fn parse(bytes: Vec<u8>) -> io::Result<Self> {
+        let mut reader = Reader { bytes: &bytes, at: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a pyp map"));
+        }
+        let origin = [reader.i32()?, reader.i32()?];
+        let tile_size = reader.u32()?;
+        let cols = reader.u16()?;
+        let rows = reader.u16()?;
+        if tile_size == 0 {
+            return Err(invalid("zero tile size"));
+        }
+        let index = (0..cols as usize * rows as usize)
+            .map(|_| Ok((reader.u32()?, reader.u32()?)))
+            .collect::<io::Result<Vec<_>>>()?;
+        let tiles = bytes[reader.at..].to_vec();
+        if index.iter().any(|&(offset, len)| offset as usize + len as usize > tiles.len()) {
+            return Err(invalid("tile index out of range"));
+        }
+
+        Ok(MapData { origin, tile_size, cols, rows, index, tiles, decoded: HashMap::new() })
+    }
+
+    // [south, west] and [north, east] corners of the packaged area.
+    pub fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let south_west = [self.origin[0] as f64 / MICRODEGREES, self.origin[1] as f64 / MICRODEGREES];
+        let span = self.tile_size as f64 / MICRODEGREES;
+        (
+            south_west,
+            [south_west[0] + span * self.rows as f64, south_west[1] + span * self.cols as f64],
+        )
+    }
+
+    pub fn center(&self) -> [f64; 2] {
+        let (south_west, north_east) = self.bounds();
+        [(south_west[0] + north_east[0]) / 2.0, (south_west[1] + north_east[1]) / 2.0]
+    }
+
+    fn decode_tile(&self, tile: usize) -> io::Result<Vec<Feature>> {
+        let (offset, len) = self.index[tile];
+        let mut reader = Reader { bytes: &self.tiles[offset as usize..offset as usize + len as usize], at: 0 };
+        let row = (tile / self.cols as usize) as i64;
+        let col = (tile % self.cols as usize) as i64;
+        let tile_origin = [
+            self.origin[0] as i64 + row * self.tile_size as i64,
+            self.origin[1] as i64 + col * self.tile_size as i64,
+        ];
+
+        // Garbage deltas must not overflow, whatever the file says.
+        let step = |at: i64, delta: i64| at.checked_add(delta).ok_or_else(|| invalid("coordinate out of range"));
+
+        let count = if len == 0 { 0 } else { reader.varint()? };
+        let mut features = Vec::new();
+        for _ in 0..count {
+            let kind = FeatureKind::from_byte(reader.u8()?).ok_or_else(|| invalid("unknown feature kind"))?;
+            let name_len = reader.varint()? as usize;
+            let name = match name_len {
+                0 => None,
+                n => Some(String::from_utf8_lossy(reader.take(n)?).into_owned()),
+            };
+            let mut last = tile_origin;
+            let mut points = Vec::new();
+            for _ in 0..reader.varint()? {
+                last = [step(last[0], reader.zigzag()?)?, step(last[1], reader.zigzag()?)?];
+                points.push([last[0] as f64 / MICRODEGREES, last[1] as f64 / MICRODEGREES]);
+            }
+            features.push(Feature { kind, name, points });
+        }
+        Ok(features)
+    }
+
+    // Everything on the tiles overlapping the box. Features that cross a tile
+    // edge are stored in each tile they touch, so expect some repeats.
+    pub fn features_in(&mut self, south_west: [f64; 2], north_east: [f64; 2]) -> Vec<&Feature> {
+        let tile_of = |degrees: f64, origin: i32, limit: u16| {
+            let cell = ((degrees * MICRODEGREES - origin as f64) / self.tile_size as f64).floor();
+            cell.clamp(0.0, limit as f64 - 1.0) as usize
+        };
+        let (map_south_west, map_north_east) = self.bounds();
+        let overlaps = south_west[0] < map_north_east[0]
+            && north_east[0] > map_south_west[0]
+            && south_west[1] < map_north_east[1]
+            && north_east[1] > map_south_west[1];
+        if !overlaps || self.cols == 0 || self.rows == 0 {
+            return Vec::new();
+        }
+        let (south, north) = (tile_of(south_west[0], self.origin[0], self.rows), tile_of(north_east[0], self.origin[0], self.rows));
+        let (west, east) = (tile_of(south_west[1], self.origin[1], self.cols), tile_of(north_east[1], self.origin[1], self.cols));
+        let cols = self.cols as usize;
+        let tiles: Vec<usize> = (south..=north)
+            .flat_map(|row| (west..=east).map(move |col| row * cols + col))
+            .collect();
+
+        let missing: Vec<usize> = tiles.iter().copied().filter(|tile| !self.decoded.contains_key(tile)).collect();
+        for tile in missing {
+            // A damaged tile just shows up empty rather than taking the map down.
+            let features = self.decode_tile(tile).unwrap_or_default();
+            self.decoded.insert(tile, features);
+        }
+        tiles.iter().flat_map(|tile| self.decoded[tile].iter()).collect()
+    }
+}
+
+pub fn read_map_data() -> io::Result<MapData> {
+    MapData::parse(fs::read(MAP_PATH)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1 x 1 map at (1, 1) µdeg whose only tile is `blob`.
+    fn one_tile(blob: &[u8]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for field in [1i32, 1] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(&1000u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(blob.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(blob);
+        bytes
+    }
+
+    fn everything(map: &mut MapData) -> Vec<Feature> {
+        let (south_west, north_east) = map.bounds();
+        map.features_in(south_west, north_east).into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn reads_a_hand_built_tile() {
+        // One road: two points, +2/-1 then +1/+1 µdeg from the tile origin.
+        let mut map = MapData::parse(one_tile(&[1, 0, 0, 2, 4, 1, 2, 2])).unwrap();
+        assert_eq!(
+            everything(&mut map),
+            vec![Feature { kind: FeatureKind::Road, name: None, points: vec![[3e-6, 0.0], [4e-6, 1e-6]] }]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let good = one_tile(&[]);
+        assert!(MapData::parse(b"PYPMAP\x02\x00".to_vec()).is_err());
+        for len in 0..good.len() {
+            assert!(MapData::parse(good[..len].to_vec()).is_err(), "{} bytes", len);
+        }
+        // The index points past the end of the tiles.
+        let mut overlong = one_tile(&[0]);
+        overlong.pop();
+        assert!(MapData::parse(overlong).is_err());
+    }
+
+    #[test]
+    fn damaged_tiles_come_back_empty() {
+        let overflowing = [1, 0, 0, 1, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0];
+        let huge_name = [1, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        let unknown_kind = [1, 9, 0, 0];
+        let cut_short = [2, 0, 0, 1, 0, 0];
+        for blob in [&overflowing[..], &huge_name, &unknown_kind, &cut_short] {
+            let mut map = MapData::parse(one_tile(blob)).unwrap();
+            assert!(map.decode_tile(0).is_err(), "{:?}", blob);
+            assert!(everything(&mut map).is_empty());
+        }
+    }
+}
@@ -48,6 +48,16 @@ pub enum InvSubMenu {
 }
 
 impl InvSubMenu {
+    pub const ALL: [InvSubMenu; 7] = [
+        InvSubMenu::Weapons,
+        InvSubMenu::Apparel,
+        InvSubMenu::Aid,
+        InvSubMenu::Misc,
+        InvSubMenu::Junk,
+        InvSubMenu::Mods,
+        InvSubMenu::Ammo,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             InvSubMenu::Weapons => "Weapons",
@@ -80,6 +90,8 @@ pub enum DataSubMenu {
     Quests,
     Workshops,
     Stats,
+    Notes,
+    Fences,
 }
 
 impl From<DataSubMenu> for usize {
@@ -88,6 +100,35 @@ impl From<DataSubMenu> for usize {
             DataSubMenu::Quests => 0,
             DataSubMenu::Workshops => 1,
             DataSubMenu::Stats => 2,
+            DataSubMenu::Notes => 3,
+            DataSubMenu::Fences => 4,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum MapSubMenu {
+    Local,
+    World,
+    Compass,
+    Coords,
+    Gps,
+    Nearby,
+    Waypoints,
+    Tracks,
+}
+
+impl From<MapSubMenu> for usize {
+    fn from(input: MapSubMenu) -> usize {
+        match input {
+            MapSubMenu::Local => 0,
+            MapSubMenu::World => 1,
+            MapSubMenu::Compass => 2,
+            MapSubMenu::Coords => 3,
+            MapSubMenu::Gps => 4,
+            MapSubMenu::Nearby => 5,
+            MapSubMenu::Waypoints => 6,
+            MapSubMenu::Tracks => 7,
         }
     }
 }
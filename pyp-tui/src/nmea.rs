@@ -0,0 +1,553 @@
+use chrono::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+use crate::position::{Constellation, PositionFix, SkySatellite, SkyView};
+use crate::status_bar::FixMode;
+
+const KNOTS_TO_MS: f64 = 0.514444;
+const KMH_TO_MS: f64 = 1.0 / 3.6;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum NmeaError {
+    #[error("not an NMEA sentence")]
+    NotASentence,
+    #[error("sentence has no checksum")]
+    MissingChecksum,
+    #[error("checksum mismatch: expected {expected:02X}, got {actual:02X}")]
+    BadChecksum { expected: u8, actual: u8 },
+    #[error("unsupported sentence {0}")]
+    Unsupported(String),
+    #[error("malformed {0} sentence")]
+    Malformed(&'static str),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Satellite {
+    pub prn: u16,
+    pub elevation: Option<u8>,
+    pub azimuth: Option<u16>,
+    pub snr: Option<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sentence {
+    Gga {
+        time: Option<NaiveTime>,
+        position: Option<[f64; 2]>,
+        quality: u8,
+        satellites_used: u8,
+        hdop: Option<f64>,
+        altitude: Option<f64>,
+    },
+    Rmc {
+        time: Option<NaiveTime>,
+        valid: bool,
+        position: Option<[f64; 2]>,
+        speed_knots: Option<f64>,
+        course: Option<f64>,
+        date: Option<NaiveDate>,
+    },
+    Gsa {
+        // Whose PRNs these are. A GN sentence with an NMEA 4.10 system id
+        // is filed under that system's talker instead.
+        talker: String,
+        mode: FixMode,
+        used_prns: Vec<u16>,
+        hdop: Option<f64>,
+        vdop: Option<f64>,
+    },
+    Gsv {
+        // GP, GL, GA... Each constellation cycles through its own GSV set.
+        talker: String,
+        // NMEA 4.10 sends one set per signal band, told apart by this.
+        signal: Option<u8>,
+        total: u8,
+        number: u8,
+        in_view: u8,
+        satellites: Vec<Satellite>,
+    },
+    Vtg {
+        course: Option<f64>,
+        speed_kmh: Option<f64>,
+    },
+}
+
+// XOR of every byte between '$' and '*', as two hex digits after the '*'.
+fn verify_checksum(line: &str) -> Result<&str, NmeaError> {
+    let line = line.trim();
+    let body = line.strip_prefix('$').ok_or(NmeaError::NotASentence)?;
+    let (body, checksum) = body.rsplit_once('*').ok_or(NmeaError::MissingChecksum)?;
+    let expected = u8::from_str_radix(checksum, 16).map_err(|_| NmeaError::MissingChecksum)?;
+    let actual = body.bytes().fold(0, |sum, b| sum ^ b);
+    if expected != actual {
+        return Err(NmeaError::BadChecksum { expected, actual });
+    }
+    Ok(body)
+}
+
+fn parse_number<T: std::str::FromStr>(field: Option<&&str>) -> Option<T> {
+    field.and_then(|f| f.parse().ok())
+}
+
+// hhmmss with optional fractional seconds.
+fn parse_time(field: &str) -> Option<NaiveTime> {
+    if field.len() < 6 || !field.is_ascii() {
+        return None;
+    }
+    let hour = field[0..2].parse().ok()?;
+    let minute = field[2..4].parse().ok()?;
+    let seconds: f64 = field[4..].parse().ok()?;
+    NaiveTime::from_hms_milli_opt(hour, minute, seconds as u32, (seconds.fract() * 1000.0).round() as u32)
+}
+
+// ddmmyy; NMEA dates are always this century as far as we're concerned.
+fn parse_date(field: &str) -> Option<NaiveDate> {
+    if field.len() != 6 || !field.is_ascii() {
+        return None;
+    }
+    let day = field[0..2].parse().ok()?;
+    let month = field[2..4].parse().ok()?;
+    let year: i32 = field[4..6].parse().ok()?;
+    NaiveDate::from_ymd_opt(2000 + year, month, day)
+}
+
+// (d)ddmm.mmmm plus a hemisphere letter, to signed decimal degrees.
+fn parse_coordinate(value: &str, hemisphere: &str) -> Option<f64> {
+    let raw: f64 = value.parse().ok()?;
+    let degrees = (raw / 100.0).trunc();
+    let decimal = degrees + (raw - degrees * 100.0) / 60.0;
+    match hemisphere {
+        "N" | "E" => Some(decimal),
+        "S" | "W" => Some(-decimal),
+        _ => None,
+    }
+}
+
+fn parse_position(fields: &[&str]) -> Option<[f64; 2]> {
+    Some([
+        parse_coordinate(fields[0], fields[1])?,
+        parse_coordinate(fields[2], fields[3])?,
+    ])
+}
+
+pub fn parse_sentence(line: &str) -> Result<Sentence, NmeaError> {
+    let body = verify_checksum(line)?;
+    let fields: Vec<&str> = body.split(',').collect();
+    let address = fields[0];
+    if address.len() != 5 || !address.is_ascii() {
+        return Err(NmeaError::NotASentence);
+    }
+    let (talker, kind) = address.split_at(2);
+
+    match kind {
+        "GGA" => {
+            if fields.len() < 10 {
+                return Err(NmeaError::Malformed("GGA"));
+            }
+            Ok(Sentence::Gga {
+                time: parse_time(fields[1]),
+                position: parse_position(&fields[2..6]),
+                quality: parse_number(fields.get(6)).unwrap_or(0),
+                satellites_used: parse_number(fields.get(7)).unwrap_or(0),
+                hdop: parse_number(fields.get(8)),
+                altitude: parse_number(fields.get(9)),
+            })
+        }
+        "RMC" => {
+            if fields.len() < 10 {
+                return Err(NmeaError::Malformed("RMC"));
+            }
+            Ok(Sentence::Rmc {
+                time: parse_time(fields[1]),
+                valid: fields[2] == "A",
+                position: parse_position(&fields[3..7]),
+                speed_knots: parse_number(fields.get(7)),
+                course: parse_number(fields.get(8)),
+                date: parse_date(fields[9]),
+            })
+        }
+        "GSA" => {
+            if fields.len() < 18 {
+                return Err(NmeaError::Malformed("GSA"));
+            }
+            let talker = match fields.get(18).and_then(|id| id.parse::<u8>().ok()) {
+                Some(1) => "GP",
+                Some(2) => "GL",
+                Some(3) => "GA",
+                Some(4) => "GB",
+                Some(5) => "GQ",
+                _ => talker,
+            };
+            Ok(Sentence::Gsa {
+                talker: talker.to_string(),
+                mode: match fields[2] {
+                    "3" => FixMode::Fix3D,
+                    "2" => FixMode::Fix2D,
+                    _ => FixMode::NoFix,
+                },
+                used_prns: fields[3..15].iter().filter_map(|f| f.parse().ok()).collect(),
+                hdop: parse_number(fields.get(16)),
+                vdop: parse_number(fields.get(17)),
+            })
+        }
+        "GSV" => {
+            if fields.len() < 4 {
+                return Err(NmeaError::Malformed("GSV"));
+            }
+            // NMEA 4.10 appends a signal id, which leaves a stray field after
+            // the groups of four.
+            let signal = match fields.len() % 4 {
+                1 => fields.last().and_then(|id| u8::from_str_radix(id, 16).ok()),
+                _ => None,
+            };
+            let satellites = fields[4..]
+                .chunks_exact(4)
+                .filter_map(|group| {
+                    Some(Satellite {
+                        prn: group[0].parse().ok()?,
+                        elevation: group[1].parse().ok(),
+                        azimuth: group[2].parse().ok(),
+                        snr: group[3].parse().ok(),
+                    })
+                })
+                .collect();
+            Ok(Sentence::Gsv {
+                talker: talker.to_string(),
+                signal,
+                total: parse_number(fields.get(1)).ok_or(NmeaError::Malformed("GSV"))?,
+                number: parse_number(fields.get(2)).ok_or(NmeaError::Malformed("GSV"))?,
+                in_view: parse_number(fields.get(3)).unwrap_or(0),
+                satellites,
+            })
+        }
+        "VTG" => {
+            if fields.len() < 8 {
+                return Err(NmeaError::Malformed("VTG"));
+            }
+            Ok(Sentence::Vtg {
+                course: parse_number(fields.get(1)),
+                speed_kmh: parse_number(fields.get(7)),
+            })
+        }
+        _ => Err(NmeaError::Unsupported(address.to_string())),
+    }
+}
+
+// Builds a PositionFix out of the sentence stream. Receivers spread one epoch
+// over several sentences, so the fix is published on GGA, or on RMC for a
+// receiver that's been configured not to send GGA at all. u-blox sends RMC
+// first and MTK sends it last, so RMC only counts once an epoch has passed
+// without a GGA.
+#[derive(Default)]
+pub struct NmeaReceiver {
+    pub fix: PositionFix,
+    date: Option<NaiveDate>,
+    gsa_mode: Option<FixMode>,
+    gga_this_epoch: bool,
+    rmc_seen: bool,
+    in_view: BTreeMap<String, u8>,
+    // A GSV set arrives over several sentences; it only replaces what's
+    // shown once the last one is in.
+    gsv_pending: BTreeMap<(String, Option<u8>), Vec<Satellite>>,
+    gsv_sets: BTreeMap<(String, Option<u8>), Vec<Satellite>>,
+    used: BTreeSet<(Constellation, u16)>,
+    // Set on publishing, so the next GSA starts a fresh epoch's list.
+    used_stale: bool,
+}
+
+impl NmeaReceiver {
+    // Returns true when the line completed a fix worth publishing.
+    pub fn feed_line(&mut self, line: &str) -> Result<bool, NmeaError> {
+        Ok(self.apply(parse_sentence(line)?))
+    }
+
+    // The last complete GSV set of each constellation, with GSA's used flags.
+    // Receivers repeat a satellite once per signal band, so only the strongest
+    // one is kept.
+    pub fn sky(&self) -> SkyView {
+        let mut satellites: BTreeMap<(Constellation, u16), SkySatellite> = BTreeMap::new();
+        for ((talker, _), set) in &self.gsv_sets {
+            for satellite in set {
+                let constellation = Constellation::from_nmea(talker, satellite.prn);
+                let key = (constellation, satellite.prn);
+                let entry = satellites.entry(key).or_insert_with(|| SkySatellite {
+                    constellation,
+                    prn: satellite.prn,
+                    elevation: satellite.elevation,
+                    azimuth: satellite.azimuth,
+                    snr: None,
+                    used: self.used.contains(&key),
+                });
+                entry.snr = entry.snr.max(satellite.snr);
+            }
+        }
+        SkyView { satellites: satellites.into_values().collect() }
+    }
+
+    pub fn apply(&mut self, sentence: Sentence) -> bool {
+        let published = match sentence {
+            Sentence::Gga { time, position, quality, satellites_used, hdop, altitude } => {
+                self.gga_this_epoch = true;
+                self.fix.mode = match (quality, self.gsa_mode) {
+                    (0, _) => FixMode::NoFix,
+                    (_, Some(mode)) if mode != FixMode::NoFix => mode,
+                    _ if altitude.is_some() => FixMode::Fix3D,
+                    _ => FixMode::Fix2D,
+                };
+                self.set_position(position);
+                self.fix.altitude = altitude;
+                self.fix.hdop = hdop.or(self.fix.hdop);
+                self.fix.satellites_used = satellites_used;
+                self.set_time(time);
+                true
+            }
+            Sentence::Rmc { time, valid, position, speed_knots, course, date } => {
+                let had_gga = std::mem::replace(&mut self.gga_this_epoch, false);
+                self.date = date.or(self.date);
+                self.fix.speed = speed_knots.map(|knots| knots * KNOTS_TO_MS);
+                self.fix.heading = course;
+                if had_gga {
+                    return false;
+                }
+                self.fix.mode = match (valid, self.gsa_mode) {
+                    (false, _) => FixMode::NoFix,
+                    (true, Some(mode)) if mode != FixMode::NoFix => mode,
+                    _ => FixMode::Fix2D,
+                };
+                self.set_position(position);
+                self.set_time(time);
+                std::mem::replace(&mut self.rmc_seen, true)
+            }
+            Sentence::Gsa { talker, mode, used_prns, hdop, vdop } => {
+                self.gsa_mode = Some(mode);
+                self.fix.hdop = hdop.or(self.fix.hdop);
+                self.fix.vdop = vdop.or(self.fix.vdop);
+                if std::mem::take(&mut self.used_stale) {
+                    self.used.clear();
+                }
+                self.used.extend(used_prns.iter().map(|&prn| (Constellation::from_nmea(&talker, prn), prn)));
+                if !self.gga_this_epoch {
+                    self.fix.satellites_used = used_prns.len().min(u8::MAX as usize) as u8;
+                }
+                false
+            }
+            Sentence::Gsv { talker, signal, total, number, in_view, satellites } => {
+                let key = (talker.clone(), signal);
+                let pending = self.gsv_pending.entry(key.clone()).or_default();
+                if number <= 1 {
+                    pending.clear();
+                }
+                pending.extend(satellites);
+                if number >= total {
+                    let set = std::mem::take(pending);
+                    self.gsv_sets.insert(key, set);
+                }
+                self.in_view.insert(talker, in_view);
+                let total: usize = self.in_view.values().map(|&n| n as usize).sum();
+                self.fix.satellites_visible = total.min(u8::MAX as usize) as u8;
+                false
+            }
+            Sentence::Vtg { course, speed_kmh } => {
+                if let Some(speed) = speed_kmh {
+                    self.fix.speed = Some(speed * KMH_TO_MS);
+                }
+                self.fix.heading = course.or(self.fix.heading);
+                false
+            }
+        };
+        self.used_stale |= published;
+        published
+    }
+
+    fn set_position(&mut self, position: Option<[f64; 2]>) {
+        let [lat, lon] = position.unwrap_or([f64::NAN, f64::NAN]);
+        self.fix.lat = lat;
+        self.fix.lon = lon;
+    }
+
+    fn set_time(&mut self, time: Option<NaiveTime>) {
+        self.fix.at = match (self.date, time) {
+            (Some(date), Some(time)) => date.and_time(time).and_utc(),
+            _ => Utc::now(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::distance_m;
+
+    const NEO6M_WALK: &str = include_str!("../testdata/neo6m_walk.nmea");
+
+    fn replay(log: &str) -> (NmeaReceiver, Vec<PositionFix>, usize) {
+        let mut receiver = NmeaReceiver::default();
+        let mut fixes = Vec::new();
+        let mut rejected = 0;
+        for line in log.lines().filter(|l| !l.trim().is_empty()) {
+            match receiver.feed_line(line) {
+                Ok(true) => fixes.push(receiver.fix),
+                Ok(false) => {}
+                Err(NmeaError::Unsupported(_)) => {}
+                Err(_) => rejected += 1,
+            }
+        }
+        (receiver, fixes, rejected)
+    }
+
+    #[test]
+    fn parses_reference_gga() {
+        let sentence = parse_sentence("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47").unwrap();
+        let Sentence::Gga { time, position, quality, satellites_used, hdop, altitude } = sentence else {
+            panic!("expected GGA, got {:?}", sentence);
+        };
+        assert_eq!(time, NaiveTime::from_hms_opt(12, 35, 19));
+        let [lat, lon] = position.unwrap();
+        assert!((lat - 48.1173).abs() < 1e-6);
+        assert!((lon - 11.516_666_7).abs() < 1e-6);
+        assert_eq!((quality, satellites_used), (1, 8));
+        assert_eq!(hdop, Some(0.9));
+        assert_eq!(altitude, Some(545.4));
+    }
+
+    #[test]
+    fn parses_reference_rmc_gsa_gsv_vtg() {
+        let rmc = parse_sentence("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A").unwrap();
+        assert!(matches!(
+            rmc,
+            Sentence::Rmc { valid: true, speed_knots: Some(22.4), course: Some(84.4), date: Some(d), .. }
+                if d == NaiveDate::from_ymd_opt(2094, 3, 23).unwrap()
+        ));
+
+        let gsa = parse_sentence("$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39").unwrap();
+        assert_eq!(
+            gsa,
+            Sentence::Gsa {
+                talker: "GP".into(),
+                mode: FixMode::Fix3D,
+                used_prns: vec![4, 5, 9, 12, 24],
+                hdop: Some(1.3),
+                vdop: Some(2.1),
+            }
+        );
+
+        let gsv = parse_sentence("$GPGSV,2,1,08,01,40,083,46,02,17,308,41,12,07,344,39,14,22,228,45*75").unwrap();
+        let Sentence::Gsv { number, in_view, satellites, .. } = gsv else {
+            panic!("expected GSV, got {:?}", gsv);
+        };
+        assert_eq!((number, in_view, satellites.len()), (1, 8, 4));
+        assert_eq!(satellites[1], Satellite { prn: 2, elevation: Some(17), azimuth: Some(308), snr: Some(41) });
+
+        let vtg = parse_sentence("$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*48").unwrap();
+        assert_eq!(vtg, Sentence::Vtg { course: Some(54.7), speed_kmh: Some(10.2) });
+    }
+
+    #[test]
+    fn rejects_bad_checksums_and_garbage() {
+        assert_eq!(
+            parse_sentence("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48"),
+            Err(NmeaError::BadChecksum { expected: 0x48, actual: 0x47 })
+        );
+        assert_eq!(parse_sentence("$GPGGA,0300"), Err(NmeaError::MissingChecksum));
+        assert_eq!(parse_sentence("GPGGA,123519*47"), Err(NmeaError::NotASentence));
+        assert!(matches!(parse_sentence("$GPGLL,,,,,030010.00,V,A*47"), Err(NmeaError::Unsupported(_))));
+    }
+
+    #[test]
+    fn replays_recorded_neo6m_log() {
+        let (receiver, fixes, rejected) = replay(NEO6M_WALK);
+
+        // One fix per epoch; the mangled RMC and the truncated GGA in the
+        // middle of the log are rejected without costing a fix.
+        assert_eq!(fixes.len(), 25);
+        assert_eq!(rejected, 2);
+
+        // Cold start: three epochs before the receiver locks on.
+        assert!(fixes[..3].iter().all(|fix| fix.coordinates().is_none()));
+        assert!(fixes[3..].iter().all(|fix| fix.mode == FixMode::Fix3D));
+
+        let last = receiver.fix;
+        assert_eq!(last.satellites_used, 6);
+        assert_eq!(last.satellites_visible, 8);
+        assert_eq!(last.hdop, Some(1.12));
+        assert_eq!(last.altitude, Some(1112.2));
+        assert!((last.speed.unwrap() - 1.4).abs() < 0.01);
+        assert_eq!(last.heading, Some(90.0));
+        assert_eq!(last.at, Utc.with_ymd_and_hms(2026, 9, 23, 3, 0, 34).unwrap());
+
+        // 21 one-second steps east at 1.4 m/s.
+        let start = fixes[3].coordinates().unwrap();
+        let end = last.coordinates().unwrap();
+        assert!((distance_m(start, end) - 29.4).abs() < 0.5);
+    }
+
+    #[test]
+    fn builds_the_sky_from_gsv_and_gsa() {
+        let (receiver, _, _) = replay(NEO6M_WALK);
+        let sky = receiver.sky();
+
+        assert_eq!(sky.satellites.len(), 8);
+        // PRN 17 is predicted overhead but not heard.
+        assert_eq!(sky.tracked(), 7);
+        let used: Vec<u16> = sky.satellites.iter().filter(|s| s.used).map(|s| s.prn).collect();
+        assert_eq!(used, vec![2, 5, 6, 12, 19, 25]);
+        assert_eq!(
+            sky.satellites[0],
+            SkySatellite {
+                constellation: Constellation::Gps,
+                prn: 2,
+                elevation: Some(64),
+                azimuth: Some(42),
+                snr: Some(38),
+                used: true,
+            }
+        );
+        assert_eq!(receiver.fix.vdop, Some(1.71));
+    }
+
+    #[test]
+    fn keeps_constellations_and_bands_apart() {
+        // GPS and Galileo both have a PRN 5, the GN GSAs say which system they
+        // mean, and Galileo reports two signal bands for the same satellite.
+        let log = "\
+$GPGSV,1,1,02,05,40,083,46,12,20,200,30*73
+$GAGSV,1,1,01,05,55,120,28,1*48
+$GAGSV,1,1,01,05,55,120,35,7*42
+$GNGSA,A,3,12,,,,,,,,,,,,1.8,1.0,1.5,1*3E
+$GNGSA,A,3,05,,,,,,,,,,,,1.8,1.0,1.5,3*3A";
+        let (receiver, _, rejected) = replay(log);
+        assert_eq!(rejected, 0);
+
+        let sky: Vec<(String, Option<u8>, bool)> = receiver
+            .sky()
+            .satellites
+            .iter()
+            .map(|s| (s.label(), s.snr, s.used))
+            .collect();
+        assert_eq!(
+            sky,
+            vec![
+                ("G05".to_string(), Some(46), false),
+                ("G12".to_string(), Some(30), true),
+                ("E05".to_string(), Some(35), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_rmc_without_gga() {
+        let log = NEO6M_WALK
+            .lines()
+            .filter(|line| !line.starts_with("$GPGGA"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (receiver, fixes, _) = replay(&log);
+
+        // The first RMC only tells us GGA might be missing.
+        assert_eq!(fixes.len(), 24);
+        assert_eq!(receiver.fix.mode, FixMode::Fix3D);
+        assert_eq!(receiver.fix.satellites_used, 6);
+        assert!(receiver.fix.altitude.is_none());
+    }
+}
@@ -0,0 +1,185 @@
+use reverse_geocoder::{Record, ReverseGeocoder};
+
+use crate::coords::distance_m;
+use crate::waypoints::NavTarget;
+
+// Nearby searches sample rings that start this close to the centre and each
+// sit this much further out than the last, so the grid is finest where the
+// nearest places are.
+const NEARBY_FIRST_RING_M: f64 = 100.0;
+const NEARBY_RING_GROWTH: f64 = 1.1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Place {
+    pub name: String,
+    pub admin1: String,
+    pub admin2: String,
+    pub cc: String,
+    pub position: [f64; 2],
+}
+
+impl Place {
+    // Places aren't saved anywhere, so a target is matched by where it is.
+    pub fn nav_target(&self) -> NavTarget {
+        NavTarget {
+            name: self.name.clone(),
+            position: self.position,
+            waypoint: None,
+        }
+    }
+
+    pub fn is_target(&self, target: Option<&NavTarget>) -> bool {
+        target.is_some_and(|t| t.waypoint.is_none() && t.position == self.position)
+    }
+
+    // District and state, whichever the dataset has.
+    pub fn region(&self) -> String {
+        let parts: Vec<&str> = [self.admin2.as_str(), self.admin1.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect();
+        parts.join(", ")
+    }
+}
+
+impl From<&Record> for Place {
+    fn from(record: &Record) -> Self {
+        Place {
+            name: record.name.clone(),
+            admin1: record.admin1.clone(),
+            admin2: record.admin2.clone(),
+            cc: record.cc.clone(),
+            position: [record.lat, record.lon],
+        }
+    }
+}
+
+// The geocoder only answers "what's nearest to here", so this asks that over
+// a grid spanning the box and keeps each distinct answer that lands inside
+// it. That spreads labels across the view instead of piling them around the
+// densest town.
+pub fn places_in(geocoder: &ReverseGeocoder, south_west: [f64; 2], north_east: [f64; 2], steps: usize) -> Vec<Place> {
+    let steps = steps.max(1);
+    let inside = |p: &Place| {
+        p.position[0] >= south_west[0]
+            && p.position[0] <= north_east[0]
+            && p.position[1] >= south_west[1]
+            && p.position[1] <= north_east[1]
+    };
+    let mut places: Vec<Place> = Vec::new();
+    for row in 0..=steps {
+        for col in 0..=steps {
+            let lat = south_west[0] + (north_east[0] - south_west[0]) * row as f64 / steps as f64;
+            let lon = south_west[1] + (north_east[1] - south_west[1]) * col as f64 / steps as f64;
+            let place = Place::from(geocoder.search((lat, lon)).record);
+            if inside(&place) && !places.contains(&place) {
+                places.push(place);
+            }
+        }
+    }
+    places
+}
+
+// The same trick in rings around one spot, for a "what's around me" list.
+// Samples on a ring are about as far apart as the rings, so the spacing is
+// tens of metres near the centre and kilometres at the edge. The nearest
+// places are where it's finest; only a far-off village squeezed between
+// bigger neighbours slips through. Up to `count` places within `radius_m`
+// come back nearest first.
+pub fn places_near(geocoder: &ReverseGeocoder, center: [f64; 2], radius_m: f64, count: usize) -> Vec<Place> {
+    const METERS_PER_DEGREE: f64 = 111_320.0;
+    let lon_scale = center[0].to_radians().cos().max(0.01);
+    let per_ring = (std::f64::consts::TAU / (NEARBY_RING_GROWTH - 1.0)).ceil() as usize;
+
+    let mut rings = vec![0.0];
+    let mut ring_radius = NEARBY_FIRST_RING_M;
+    while ring_radius < radius_m {
+        rings.push(ring_radius);
+        ring_radius *= NEARBY_RING_GROWTH;
+    }
+    rings.push(radius_m);
+
+    let mut places: Vec<(f64, Place)> = Vec::new();
+    for (ring, ring_radius) in rings.into_iter().enumerate() {
+        let samples = if ring == 0 { 1 } else { per_ring };
+        for sample in 0..samples {
+            // Every other ring is turned half a step so samples don't line up.
+            let angle = std::f64::consts::TAU * (sample as f64 + 0.5 * (ring % 2) as f64) / samples as f64;
+            let lat = center[0] + ring_radius * angle.cos() / METERS_PER_DEGREE;
+            let lon = center[1] + ring_radius * angle.sin() / (METERS_PER_DEGREE * lon_scale);
+            let place = Place::from(geocoder.search((lat, lon)).record);
+            let distance = distance_m(center, place.position);
+            if distance <= radius_m && !places.iter().any(|(_, p)| *p == place) {
+                places.push((distance, place));
+            }
+        }
+    }
+    places.sort_by(|a, b| a.0.total_cmp(&b.0));
+    places.into_iter().take(count).map(|(_, place)| place).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+
+    // Building the geocoder parses the whole bundled city list, so share one.
+    fn geocoder() -> &'static ReverseGeocoder {
+        static GEOCODER: OnceLock<ReverseGeocoder> = OnceLock::new();
+        GEOCODER.get_or_init(ReverseGeocoder::new)
+    }
+
+    #[test]
+    fn nearby_matches_a_brute_force_sweep() {
+        // Garut, on Java, has hundreds of villages within the default radius.
+        let center = [-7.2, 107.9];
+        let found = places_near(geocoder(), center, 50_000.0, 12);
+
+        // Every 25 m out to 3 km, which holds more than a dozen of them, so
+        // it settles which twelve are nearest.
+        let sweep_m = 3000.0;
+        let (lat_step, lon_step) = (25.0 / 111_320.0, 25.0 / (111_320.0 * center[0].to_radians().cos()));
+        let mut truth: Vec<(f64, Place)> = Vec::new();
+        for row in -120..=120 {
+            for col in -120..=120 {
+                let sample = (center[0] + row as f64 * lat_step, center[1] + col as f64 * lon_step);
+                let place = Place::from(geocoder().search(sample).record);
+                let distance = distance_m(center, place.position);
+                if distance <= sweep_m && !truth.iter().any(|(_, p)| *p == place) {
+                    truth.push((distance, place));
+                }
+            }
+        }
+        assert!(truth.len() > 12, "{}", truth.len());
+        truth.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        assert_eq!(
+            found.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+            truth.iter().take(12).map(|(_, p)| p.name.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn nearby_stays_inside_the_radius() {
+        // Middle of the Mojave: the nearest towns are kilometres out.
+        let center = [35.0, -116.0];
+        let close = places_near(geocoder(), center, 20_000.0, 50);
+        assert!(close.iter().all(|p| distance_m(center, p.position) <= 20_000.0));
+        let wide = places_near(geocoder(), center, 100_000.0, 50);
+        assert!(wide.len() > close.len());
+        assert!(close.iter().all(|p| wide.contains(p)));
+    }
+
+    #[test]
+    fn places_in_keeps_distinct_places_inside_the_box() {
+        let (south_west, north_east) = ([36.0, -115.4], [36.4, -114.9]);
+        let places = places_in(geocoder(), south_west, north_east, 6);
+        assert!(places.len() > 3, "{:?}", places);
+        for (i, place) in places.iter().enumerate() {
+            assert!((south_west[0]..=north_east[0]).contains(&place.position[0]), "{:?}", place);
+            assert!((south_west[1]..=north_east[1]).contains(&place.position[1]), "{:?}", place);
+            assert!(!places[..i].contains(place));
+        }
+        assert!(places.iter().any(|p| p.name == "Las Vegas"));
+    }
+}
@@ -0,0 +1,479 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error as StdError;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::geofences::{log_geofence_event, GeofenceWatch};
+use crate::nmea::NmeaReceiver;
+use crate::settings::Settings;
+use crate::status_bar::FixMode;
+use crate::Event;
+
+const GPSD_ADDR: &str = "127.0.0.1:2947";
+// NEO-6M factory default.
+const NMEA_BAUD: &str = "9600";
+// A recorded log is played back at the receiver's usual 1 Hz.
+const REPLAY_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// gpsd reports at least once a second while watching; a silent socket means
+// the daemon or the receiver went away.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+// Below about a slow walk the receiver's course is just noise.
+const MIN_COURSE_SPEED: f64 = 0.5;
+// Past this the position can be off by tens of meters, which would count as
+// walking.
+const MAX_MOVEMENT_HDOP: f64 = 5.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpsSource {
+    Gpsd,
+    Nmea,
+}
+
+impl GpsSource {
+    pub const ALL: [GpsSource; 2] = [GpsSource::Gpsd, GpsSource::Nmea];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GpsSource::Gpsd => "gpsd",
+            GpsSource::Nmea => "NMEA Device",
+        }
+    }
+}
+
+// Where the service reads from. The UI swaps this when settings change and
+// the service reconnects on its next line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionSource {
+    Gpsd,
+    Nmea(PathBuf),
+}
+
+impl PositionSource {
+    pub fn from_settings(settings: &Settings) -> Self {
+        match settings.gps_source {
+            GpsSource::Gpsd => PositionSource::Gpsd,
+            GpsSource::Nmea => PositionSource::Nmea(PathBuf::from(&settings.gps_device)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PositionFix {
+    pub mode: FixMode,
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude: Option<f64>,
+    pub speed: Option<f64>,
+    pub heading: Option<f64>,
+    pub hdop: Option<f64>,
+    pub vdop: Option<f64>,
+    pub satellites_used: u8,
+    pub satellites_visible: u8,
+    pub at: DateTime<Utc>,
+}
+
+impl Default for PositionFix {
+    fn default() -> Self {
+        PositionFix {
+            mode: FixMode::NoFix,
+            lat: 0.0,
+            lon: 0.0,
+            altitude: None,
+            speed: None,
+            heading: None,
+            hdop: None,
+            vdop: None,
+            satellites_used: 0,
+            satellites_visible: 0,
+            at: Utc::now(),
+        }
+    }
+}
+
+impl PositionFix {
+    // [lat, lon] when the receiver actually has a fix.
+    pub fn coordinates(&self) -> Option<[f64; 2]> {
+        let valid = self.mode != FixMode::NoFix && self.lat.is_finite() && self.lon.is_finite();
+        valid.then_some([self.lat, self.lon])
+    }
+
+    // Whether this fix is good enough to count toward distance walked: a real
+    // position with decent geometry, and moving as far as the receiver can
+    // tell. Sources that don't report HDOP or speed get the benefit of the
+    // doubt.
+    pub fn counts_as_movement(&self) -> bool {
+        self.coordinates().is_some()
+            && self.hdop.is_none_or(|hdop| hdop <= MAX_MOVEMENT_HDOP)
+            && self.speed.is_none_or(|speed| speed >= MIN_COURSE_SPEED)
+    }
+
+    // Course over ground, only while actually moving.
+    pub fn course(&self) -> Option<f64> {
+        self.heading.filter(|_| self.speed.is_some_and(|speed| speed >= MIN_COURSE_SPEED))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Constellation {
+    Gps,
+    Sbas,
+    Glonass,
+    Galileo,
+    Beidou,
+    Qzss,
+}
+
+impl Constellation {
+    // The RINEX letter, which is what goes in front of a PRN on the sky plot.
+    pub fn letter(&self) -> char {
+        match self {
+            Constellation::Gps => 'G',
+            Constellation::Sbas => 'S',
+            Constellation::Glonass => 'R',
+            Constellation::Galileo => 'E',
+            Constellation::Beidou => 'C',
+            Constellation::Qzss => 'J',
+        }
+    }
+
+    // GP and GN sentences mix systems, told apart by the NMEA PRN ranges.
+    pub fn from_nmea(talker: &str, prn: u16) -> Self {
+        match (talker, prn) {
+            ("GL", _) => Constellation::Glonass,
+            ("GA", _) => Constellation::Galileo,
+            ("GB" | "BD", _) => Constellation::Beidou,
+            ("GQ" | "QZ", _) => Constellation::Qzss,
+            (_, 33..=64 | 120..=158) => Constellation::Sbas,
+            (_, 65..=96) => Constellation::Glonass,
+            (_, 193..=202) => Constellation::Qzss,
+            _ => Constellation::Gps,
+        }
+    }
+
+    // gpsd's gnssid, falling back to the PRN for older daemons without it.
+    fn from_gpsd(gnssid: Option<u64>, prn: u16) -> Self {
+        match gnssid {
+            Some(1) => Constellation::Sbas,
+            Some(2) => Constellation::Galileo,
+            Some(3) => Constellation::Beidou,
+            Some(5) => Constellation::Qzss,
+            Some(6) => Constellation::Glonass,
+            Some(_) => Constellation::Gps,
+            None => Constellation::from_nmea("GN", prn),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkySatellite {
+    pub constellation: Constellation,
+    pub prn: u16,
+    pub elevation: Option<u8>,
+    pub azimuth: Option<u16>,
+    pub snr: Option<u8>,
+    pub used: bool,
+}
+
+impl SkySatellite {
+    pub fn label(&self) -> String {
+        format!("{}{:02}", self.constellation.letter(), self.prn)
+    }
+}
+
+// Every satellite the receiver reports, for the GPS diagnostics screen. Kept
+// out of PositionFix so that stays Copy.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkyView {
+    pub satellites: Vec<SkySatellite>,
+}
+
+impl SkyView {
+    // Satellites with a signal at all, as opposed to just predicted overhead.
+    pub fn tracked(&self) -> usize {
+        self.satellites.iter().filter(|s| s.snr.is_some_and(|snr| snr > 0)).count()
+    }
+}
+
+// The usual rule-of-thumb ratings for a dilution of precision value.
+pub fn dop_rating(dop: f64) -> &'static str {
+    match dop {
+        d if d <= 1.0 => "ideal",
+        d if d <= 2.0 => "excellent",
+        d if d <= 5.0 => "good",
+        d if d <= 10.0 => "moderate",
+        d if d <= 20.0 => "fair",
+        _ => "poor",
+    }
+}
+
+// Folds one line of gpsd JSON into the running fix. SKY reports only update
+// the satellite and DOP fields, and the sky view; a TPV report completes a
+// fix, so that's when this returns true and the caller should publish.
+pub fn apply_gpsd_report(line: &str, fix: &mut PositionFix, sky: &mut SkyView) -> bool {
+    let Ok(report) = serde_json::from_str::<Value>(line) else {
+        return false;
+    };
+    let number = |key: &str| report[key].as_f64();
+
+    match report["class"].as_str() {
+        Some("TPV") => {
+            fix.mode = match report["mode"].as_u64() {
+                Some(3) => FixMode::Fix3D,
+                Some(2) => FixMode::Fix2D,
+                _ => FixMode::NoFix,
+            };
+            fix.lat = number("lat").unwrap_or(f64::NAN);
+            fix.lon = number("lon").unwrap_or(f64::NAN);
+            fix.altitude = number("altMSL").or_else(|| number("alt"));
+            fix.speed = number("speed");
+            fix.heading = number("track");
+            fix.at = report["time"]
+                .as_str()
+                .and_then(|t| t.parse::<DateTime<Utc>>().ok())
+                .unwrap_or_else(Utc::now);
+            true
+        }
+        Some("SKY") => {
+            if let Some(hdop) = number("hdop") {
+                fix.hdop = Some(hdop);
+            }
+            if let Some(vdop) = number("vdop") {
+                fix.vdop = Some(vdop);
+            }
+            if let Some(satellites) = report["satellites"].as_array() {
+                // gpsd uses -1 and 0 for "no idea" on the angles and signal.
+                let angle = |value: &Value| value.as_f64().filter(|&a| a >= 0.0);
+                sky.satellites = satellites
+                    .iter()
+                    .filter_map(|s| {
+                        let prn = s["PRN"].as_u64()? as u16;
+                        Some(SkySatellite {
+                            constellation: Constellation::from_gpsd(s["gnssid"].as_u64(), prn),
+                            prn: s["svid"].as_u64().map_or(prn, |svid| svid as u16),
+                            elevation: angle(&s["el"]).map(|el| el.round() as u8),
+                            azimuth: angle(&s["az"]).map(|az| az.round() as u16 % 360),
+                            snr: s["ss"].as_f64().filter(|&ss| ss > 0.0).map(|ss| ss.round() as u8),
+                            used: s["used"].as_bool() == Some(true),
+                        })
+                    })
+                    .collect();
+                fix.satellites_visible = satellites.len().min(u8::MAX as usize) as u8;
+                fix.satellites_used = satellites
+                    .iter()
+                    .filter(|s| s["used"].as_bool() == Some(true))
+                    .count()
+                    .min(u8::MAX as usize) as u8;
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+// Keeps a position source open for as long as the app runs. `None` on the
+// channel means the source is unreachable, as opposed to reachable but
+// without a fix.
+pub fn spawn_position_service<I: Send + 'static>(
+    tx: Sender<Event<I>>,
+    enabled: Arc<AtomicBool>,
+    source: Arc<Mutex<PositionSource>>,
+    geofences: Arc<Mutex<GeofenceWatch>>,
+) {
+    thread::spawn(move || loop {
+        if !enabled.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_secs(1));
+            continue;
+        }
+        let current = source.lock().unwrap().clone();
+        // Stop reading as soon as GPS is switched off or pointed elsewhere.
+        let still_wanted = || enabled.load(Ordering::Relaxed) && *source.lock().unwrap() == current;
+        let result = match &current {
+            PositionSource::Gpsd => watch_gpsd(&tx, &geofences, still_wanted),
+            PositionSource::Nmea(path) => read_nmea(path, &tx, &geofences, still_wanted),
+        };
+        match result {
+            Ok(()) => return,
+            Err(_) => {
+                if tx.send(Event::Position(None)).is_err() {
+                    return;
+                }
+                thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    });
+}
+
+// Passes a fix on to the UI, followed by any fence it crossed. Checking here
+// rather than in the UI means a crossing is logged even while a menu or the
+// keyboard has the screen. False once the UI has hung up.
+fn send_fix<I>(tx: &Sender<Event<I>>, geofences: &Mutex<GeofenceWatch>, sky: SkyView, fix: PositionFix) -> bool {
+    let crossings = match fix.coordinates() {
+        Some(here) => {
+            let mut watch = geofences.lock().unwrap();
+            let GeofenceWatch { fences, monitor } = &mut *watch;
+            monitor.check(fences, here, fix.at)
+        }
+        None => Vec::new(),
+    };
+    if tx.send(Event::Sky(sky)).is_err() || tx.send(Event::Position(Some(fix))).is_err() {
+        return false;
+    }
+    crossings.into_iter().all(|event| {
+        // Same as the HR log: a missed line beats a lost GPS thread.
+        let _ = log_geofence_event(&event);
+        tx.send(Event::Geofence(event)).is_ok()
+    })
+}
+
+// Returns Ok only once the UI has hung up; any connection trouble is an error
+// so the caller reconnects.
+fn watch_gpsd<I>(tx: &Sender<Event<I>>, geofences: &Mutex<GeofenceWatch>, still_wanted: impl Fn() -> bool) -> Result<(), Box<dyn StdError>> {
+    let addr: SocketAddr = GPSD_ADDR.parse()?;
+    let mut stream = TcpStream::connect_timeout(&addr, RECONNECT_DELAY)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.write_all(b"?WATCH={\"enable\":true,\"json\":true}\n")?;
+
+    let mut fix = PositionFix::default();
+    let mut sky = SkyView::default();
+    for line in BufReader::new(stream).lines() {
+        if !still_wanted() {
+            return Err("GPS source changed".into());
+        }
+        if apply_gpsd_report(&line?, &mut fix, &mut sky) && !send_fix(tx, geofences, sky.clone(), fix) {
+            return Ok(());
+        }
+    }
+    Err("gpsd closed the connection".into())
+}
+
+// Reads NMEA straight off a UART, or replays a recorded log in a loop when the
+// path is a regular file. Same contract as watch_gpsd.
+fn read_nmea<I>(
+    path: &Path,
+    tx: &Sender<Event<I>>,
+    geofences: &Mutex<GeofenceWatch>,
+    still_wanted: impl Fn() -> bool,
+) -> Result<(), Box<dyn StdError>> {
+    let metadata = fs::metadata(path)?;
+    let replay = metadata.is_file();
+    if metadata.file_type().is_char_device() {
+        // Raw 8N1 with a 5 s read timeout, so a silent receiver reads as EOF.
+        let status = Command::new("stty")
+            .arg("-F")
+            .arg(path)
+            .args([NMEA_BAUD, "raw", "-echo", "min", "0", "time", "50"])
+            .status()?;
+        if !status.success() {
+            return Err(format!("could not configure {}", path.display()).into());
+        }
+    }
+
+    loop {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut receiver = NmeaReceiver::default();
+        let mut line = Vec::new();
+        // Serial noise isn't always valid UTF-8, so read bytes and let the
+        // checksum throw out anything mangled.
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            if !still_wanted() {
+                return Err("GPS source changed".into());
+            }
+            let published = receiver.feed_line(&String::from_utf8_lossy(&line)).unwrap_or(false);
+            line.clear();
+            if published {
+                if !send_fix(tx, geofences, receiver.sky(), receiver.fix) {
+                    return Ok(());
+                }
+                if replay {
+                    thread::sleep(REPLAY_INTERVAL);
+                }
+            }
+        }
+        if !replay {
+            return Err(format!("no data from {}", path.display()).into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_fix_from_gpsd_tpv() {
+        let mut fix = PositionFix::default();
+        let mut sky = SkyView::default();
+        let no_fix = r#"{"class":"TPV","device":"/dev/ttyAMA0","mode":1,"time":"2026-09-23T03:00:10.000Z"}"#;
+        assert!(apply_gpsd_report(no_fix, &mut fix, &mut sky));
+        assert_eq!(fix.mode, FixMode::NoFix);
+        assert_eq!(fix.coordinates(), None);
+
+        let report = r#"{"class":"TPV","device":"/dev/ttyAMA0","mode":3,"time":"2026-09-23T03:00:34.000Z",
+            "lat":35.832500,"lon":-115.434175,"alt":1085.9,"altMSL":1112.2,"speed":1.4,"track":90.0}"#
+            .replace('\n', "");
+        assert!(apply_gpsd_report(&report, &mut fix, &mut sky));
+        assert_eq!(fix.mode, FixMode::Fix3D);
+        assert_eq!(fix.coordinates(), Some([35.8325, -115.434175]));
+        // Height above sea level, not the ellipsoid, when gpsd has both.
+        assert_eq!(fix.altitude, Some(1112.2));
+        assert_eq!((fix.speed, fix.course()), (Some(1.4), Some(90.0)));
+        assert_eq!(fix.at, Utc.with_ymd_and_hms(2026, 9, 23, 3, 0, 34).unwrap());
+
+        // Anything else is ignored without touching the fix.
+        assert!(!apply_gpsd_report(r#"{"class":"VERSION","release":"3.25"}"#, &mut fix, &mut sky));
+        assert!(!apply_gpsd_report("not json", &mut fix, &mut sky));
+        assert_eq!(fix.altitude, Some(1112.2));
+    }
+
+    #[test]
+    fn only_good_moving_fixes_count_as_movement() {
+        let walking = PositionFix {
+            mode: FixMode::Fix3D,
+            lat: 35.8325,
+            lon: -115.4342,
+            hdop: Some(1.1),
+            speed: Some(1.4),
+            ..PositionFix::default()
+        };
+        assert!(walking.counts_as_movement());
+        assert!(!PositionFix { speed: Some(0.1), ..walking }.counts_as_movement());
+        assert!(!PositionFix { hdop: Some(8.0), ..walking }.counts_as_movement());
+        assert!(!PositionFix { mode: FixMode::NoFix, ..walking }.counts_as_movement());
+        assert!(PositionFix { hdop: None, speed: None, ..walking }.counts_as_movement());
+    }
+
+    #[test]
+    fn reads_satellites_from_gpsd_sky() {
+        let report = r#"{"class":"SKY","device":"/dev/ttyAMA0","hdop":1.12,"vdop":1.71,"satellites":[
+            {"PRN":2,"el":64.0,"az":42.0,"ss":38.0,"used":true,"gnssid":0,"svid":2},
+            {"PRN":17,"el":9.0,"az":37.0,"ss":0.0,"used":false,"gnssid":0,"svid":17},
+            {"PRN":84,"el":-1.0,"az":-1.0,"ss":22.0,"used":false,"gnssid":6,"svid":20},
+            {"PRN":136,"el":40.0,"az":210.0,"ss":31.0,"used":false}]}"#
+            .replace('\n', "");
+        let mut fix = PositionFix::default();
+        let mut sky = SkyView::default();
+
+        // SKY never completes a fix by itself.
+        assert!(!apply_gpsd_report(&report, &mut fix, &mut sky));
+        assert_eq!((fix.hdop, fix.vdop), (Some(1.12), Some(1.71)));
+        assert_eq!((fix.satellites_used, fix.satellites_visible), (1, 4));
+
+        let labels: Vec<String> = sky.satellites.iter().map(SkySatellite::label).collect();
+        assert_eq!(labels, ["G02", "G17", "R20", "S136"]);
+        assert_eq!(sky.satellites[1].snr, None);
+        assert_eq!((sky.satellites[2].elevation, sky.satellites[2].azimuth), (None, None));
+        assert_eq!(sky.tracked(), 3);
+    }
+}
@@ -0,0 +1,182 @@
+use chrono::prelude::*;
+use max3010x::{Led, Max3010x, SampleAveraging, SamplingRate};
+use std::collections::VecDeque;
+use std::error::Error as StdError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::Event;
+
+const I2C_DEVICE: &str = "/dev/i2c-1";
+// Sps400 with 4-sample averaging leaves 100 samples per second in the FIFO.
+const SAMPLE_RATE: f32 = 100.0;
+const WINDOW_LEN: usize = 400;
+const FINGER_THRESHOLD: f32 = 50_000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PulseReading {
+    pub bpm: u16,
+    pub spo2: Option<u8>,
+    pub at: DateTime<Utc>,
+}
+
+pub fn spawn_pulse_monitor<I: Send + 'static>(tx: Sender<Event<I>>, enabled: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        // No sensor on the bus just means no readings; the UI keeps running.
+        let _ = run_sensor(&tx, &enabled);
+    });
+}
+
+fn run_sensor<I>(tx: &Sender<Event<I>>, enabled: &AtomicBool) -> Result<(), Box<dyn StdError>> {
+    let dev = hal::I2cdev::new(I2C_DEVICE)?;
+    let sensor_err = |e| format!("MAX30102 error: {:?}", e);
+
+    let mut sensor = Max3010x::new_max30102(dev).into_oximeter().map_err(sensor_err)?;
+    sensor.set_sample_averaging(SampleAveraging::Sa4).map_err(sensor_err)?;
+    sensor.set_sampling_rate(SamplingRate::Sps400).map_err(sensor_err)?;
+    sensor.set_pulse_amplitude(Led::All, 15).map_err(sensor_err)?;
+    sensor.enable_fifo_rollover().map_err(sensor_err)?;
+
+    let mut window: VecDeque<(u32, u32)> = VecDeque::with_capacity(WINDOW_LEN);
+    let mut data = [0u32; 64];
+    let mut since_estimate = 0;
+
+    loop {
+        if !enabled.load(Ordering::Relaxed) {
+            window.clear();
+            sensor.clear_fifo().map_err(sensor_err)?;
+            thread::sleep(Duration::from_secs(1));
+            continue;
+        }
+
+        let read = sensor.read_fifo(&mut data).map_err(sensor_err)? as usize;
+        for sample in data[..read * 2].chunks_exact(2) {
+            if window.len() == WINDOW_LEN {
+                window.pop_front();
+            }
+            window.push_back((sample[0], sample[1]));
+        }
+        since_estimate += read;
+
+        if window.len() == WINDOW_LEN && since_estimate >= SAMPLE_RATE as usize {
+            since_estimate = 0;
+            let samples: Vec<(u32, u32)> = window.iter().copied().collect();
+            if let Some((bpm, spo2)) = estimate_pulse(&samples, SAMPLE_RATE) {
+                let reading = PulseReading { bpm, spo2, at: Utc::now() };
+                if tx.send(Event::Pulse(reading)).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Estimates heart rate and SpO2 from a window of (red, IR) samples.
+///
+/// Returns `None` when no finger is on the sensor or the signal is too noisy
+/// to find a plausible rate.
+pub fn estimate_pulse(samples: &[(u32, u32)], sample_rate: f32) -> Option<(u16, Option<u8>)> {
+    if samples.len() < 4 {
+        return None;
+    }
+
+    let red: Vec<f32> = samples.iter().map(|s| s.0 as f32).collect();
+    let ir: Vec<f32> = samples.iter().map(|s| s.1 as f32).collect();
+
+    let dc_red = red.iter().sum::<f32>() / red.len() as f32;
+    let dc_ir = ir.iter().sum::<f32>() / ir.len() as f32;
+    if dc_ir < FINGER_THRESHOLD || dc_red <= 0.0 {
+        return None;
+    }
+
+    // Blood volume peaks absorb more light, so the pulse shows up as dips in IR.
+    let ac_ir: Vec<f32> = smooth(&ir).iter().map(|v| dc_ir - v).collect();
+    let ac_red: Vec<f32> = smooth(&red).iter().map(|v| dc_red - v).collect();
+
+    let peak = ac_ir.iter().cloned().fold(f32::MIN, f32::max);
+    if peak <= 0.0 {
+        return None;
+    }
+    let threshold = peak * 0.4;
+    let min_distance = (sample_rate * 0.3) as usize;
+
+    let mut peaks: Vec<usize> = Vec::new();
+    for i in 1..ac_ir.len() - 1 {
+        let is_peak = ac_ir[i] > threshold && ac_ir[i] >= ac_ir[i - 1] && ac_ir[i] > ac_ir[i + 1];
+        if is_peak && peaks.last().is_none_or(|&last| i - last >= min_distance) {
+            peaks.push(i);
+        }
+    }
+    if peaks.len() < 2 {
+        return None;
+    }
+
+    let interval = (peaks[peaks.len() - 1] - peaks[0]) as f32 / (peaks.len() - 1) as f32;
+    let bpm = 60.0 * sample_rate / interval;
+    if !(30.0..=220.0).contains(&bpm) {
+        return None;
+    }
+
+    let rms = |v: &[f32]| (v.iter().map(|x| x * x).sum::<f32>() / v.len() as f32).sqrt();
+    let ratio = (rms(&ac_red) / dc_red) / (rms(&ac_ir) / dc_ir);
+    let spo2 = 110.0 - 25.0 * ratio;
+    let spo2 = if (70.0..=100.0).contains(&spo2) {
+        Some(spo2.round() as u8)
+    } else {
+        None
+    };
+
+    Some((bpm.round() as u16, spo2))
+}
+
+fn smooth(values: &[f32]) -> Vec<f32> {
+    const SPAN: usize = 4;
+    (0..values.len())
+        .map(|i| {
+            let start = i.saturating_sub(SPAN - 1);
+            let slice = &values[start..=i];
+            slice.iter().sum::<f32>() / slice.len() as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Four seconds at 100 Hz of a pulse that dips IR by `ir_swing` and red by
+    // `red_swing` around fixed DC levels.
+    fn window(bpm: f32, red: (f32, f32), ir: (f32, f32)) -> Vec<(u32, u32)> {
+        (0..WINDOW_LEN)
+            .map(|i| {
+                let phase = (2.0 * std::f32::consts::PI * bpm / 60.0 * i as f32 / SAMPLE_RATE).sin();
+                ((red.0 + red.1 * phase) as u32, (ir.0 + ir.1 * phase) as u32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_rate_and_saturation_of_a_clean_pulse() {
+        // Red swings half as much relative to its DC as IR, so R = 0.5.
+        let (bpm, spo2) = estimate_pulse(&window(72.0, (80_000.0, 400.0), (100_000.0, 1000.0)), SAMPLE_RATE).unwrap();
+        assert!((70..=74).contains(&bpm), "{}", bpm);
+        assert!(spo2.is_some_and(|s| (96..=99).contains(&s)), "{:?}", spo2);
+
+        let (bpm, _) = estimate_pulse(&window(120.0, (80_000.0, 400.0), (100_000.0, 1000.0)), SAMPLE_RATE).unwrap();
+        assert!((117..=123).contains(&bpm), "{}", bpm);
+    }
+
+    #[test]
+    fn no_finger_or_no_pulse_gives_nothing() {
+        assert_eq!(estimate_pulse(&window(72.0, (10_000.0, 100.0), (20_000.0, 200.0)), SAMPLE_RATE), None);
+        assert_eq!(estimate_pulse(&window(72.0, (80_000.0, 0.0), (100_000.0, 0.0)), SAMPLE_RATE), None);
+        // Ten beats a minute is below anything plausible.
+        assert_eq!(estimate_pulse(&window(10.0, (80_000.0, 400.0), (100_000.0, 1000.0)), SAMPLE_RATE), None);
+        assert_eq!(estimate_pulse(&[(80_000, 100_000); 3], SAMPLE_RATE), None);
+    }
+}
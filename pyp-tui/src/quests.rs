@@ -0,0 +1,239 @@
+use chrono::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::Error;
+
+const QUESTS_PATH: &str = "./data/quests.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuestState {
+    Active,
+    Completed,
+    Failed,
+}
+
+impl QuestState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestState::Active => "ACTIVE",
+            QuestState::Completed => "COMPLETED",
+            QuestState::Failed => "FAILED",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Objective {
+    pub text: String,
+    pub done: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Quest {
+    pub id: usize,
+    pub title: String,
+    pub description: String,
+    pub objectives: Vec<Objective>,
+    pub state: QuestState,
+    pub due: Option<NaiveDate>,
+    pub waypoint: Option<[f64; 2]>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    // Set the first time the quest is completed. Reactivating it doesn't
+    // clear this, so finishing it again isn't worth XP again.
+    #[serde(default)]
+    pub rewarded: bool,
+}
+
+impl Quest {
+    pub fn new(title: String, description: String, objectives: Vec<String>) -> Self {
+        Quest {
+            id: thread_rng().gen_range(0, 9999999),
+            title,
+            description,
+            objectives: objectives
+                .into_iter()
+                .map(|text| Objective { text, done: false })
+                .collect(),
+            state: QuestState::Active,
+            due: None,
+            waypoint: None,
+            created_at: Utc::now(),
+            finished_at: None,
+            rewarded: false,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let (done, total) = self.progress();
+        format!("{} {}/{}", self.title, done, total)
+    }
+
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            self.objectives.iter().filter(|o| o.done).count(),
+            self.objectives.len(),
+        )
+    }
+
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.state == QuestState::Active && self.due.is_some_and(|due| due < today)
+    }
+
+    // Returns true when this call is what first finished the quest, so the
+    // caller knows to hand out XP exactly once.
+    pub fn set_state(&mut self, state: QuestState) -> bool {
+        if self.state == state {
+            return false;
+        }
+        self.state = state;
+        self.finished_at = match state {
+            QuestState::Active => None,
+            _ => Some(Utc::now()),
+        };
+        state == QuestState::Completed && !std::mem::replace(&mut self.rewarded, true)
+    }
+
+    // Ticking off the last open objective completes the quest.
+    pub fn toggle_objective(&mut self, index: usize) -> bool {
+        let Some(objective) = self.objectives.get_mut(index) else {
+            return false;
+        };
+        objective.done = !objective.done;
+        let (done, total) = self.progress();
+        self.state == QuestState::Active && done == total && self.set_state(QuestState::Completed)
+    }
+
+    // Ticks an objective off without ever unticking it, for things that
+    // happen on their own. None when it was already done; otherwise whether
+    // that finished the quest.
+    pub fn complete_objective(&mut self, index: usize) -> Option<bool> {
+        let open = self.state == QuestState::Active && self.objectives.get(index).is_some_and(|o| !o.done);
+        open.then(|| self.toggle_objective(index))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuestAction {
+    SetCurrent,
+    ToggleObjective,
+    AddObjective,
+    SetDueDate,
+    SetWaypoint,
+    Complete,
+    Fail,
+    Reactivate,
+    Delete,
+}
+
+impl QuestAction {
+    pub fn available(state: QuestState) -> Vec<QuestAction> {
+        match state {
+            QuestState::Active => vec![
+                QuestAction::SetCurrent,
+                QuestAction::ToggleObjective,
+                QuestAction::AddObjective,
+                QuestAction::SetDueDate,
+                QuestAction::SetWaypoint,
+                QuestAction::Complete,
+                QuestAction::Fail,
+                QuestAction::Delete,
+            ],
+            _ => vec![QuestAction::Reactivate, QuestAction::Delete],
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestAction::SetCurrent => "Set As Current",
+            QuestAction::ToggleObjective => "Toggle Objective",
+            QuestAction::AddObjective => "Add Objective",
+            QuestAction::SetDueDate => "Set Due Date",
+            QuestAction::SetWaypoint => "Set Waypoint Here",
+            QuestAction::Complete => "Mark Completed",
+            QuestAction::Fail => "Mark Failed",
+            QuestAction::Reactivate => "Reactivate",
+            QuestAction::Delete => "Delete",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct QuestLog {
+    pub quests: Vec<Quest>,
+    pub current: Option<usize>,
+}
+
+impl QuestLog {
+    pub fn current_quest(&self) -> Option<&Quest> {
+        let id = self.current?;
+        self.quests.iter().find(|q| q.id == id && q.state == QuestState::Active)
+    }
+
+    pub fn add(&mut self, quest: Quest) {
+        if self.current_quest().is_none() {
+            self.current = Some(quest.id);
+        }
+        self.quests.insert(0, quest);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        let quest = self.quests.remove(index);
+        if self.current == Some(quest.id) {
+            self.current = None;
+        }
+    }
+}
+
+pub fn read_quest_log() -> Result<QuestLog, Error> {
+    match fs::read_to_string(QUESTS_PATH) {
+        Ok(content) => {
+            let mut log: QuestLog = serde_json::from_str(&content)?;
+            // Logs saved before `rewarded` existed already paid out for these.
+            for quest in &mut log.quests {
+                quest.rewarded |= quest.state == QuestState::Completed;
+            }
+            Ok(log)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(QuestLog::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_quest_log(log: &QuestLog) -> Result<(), Error> {
+    fs::write(QUESTS_PATH, &serde_json::to_vec(log)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completing_again_after_reactivating_pays_nothing() {
+        let mut quest = Quest::new("Supply Run".into(), String::new(), vec!["Reach the depot".into()]);
+        assert!(quest.set_state(QuestState::Completed));
+
+        assert!(!quest.set_state(QuestState::Active));
+        assert!(!quest.set_state(QuestState::Completed));
+
+        // Nor by unticking and re-ticking the last objective.
+        assert!(!quest.set_state(QuestState::Active));
+        assert!(!quest.toggle_objective(0));
+        assert!(!quest.toggle_objective(0));
+        assert_eq!(quest.state, QuestState::Completed);
+    }
+
+    #[test]
+    fn last_objective_completes_the_quest_once() {
+        let mut quest = Quest::new("Supply Run".into(), String::new(), vec!["Reach the depot".into(), "Get back".into()]);
+        assert!(!quest.toggle_objective(0));
+        assert!(quest.toggle_objective(1));
+        assert_eq!(quest.state, QuestState::Completed);
+        assert!(quest.rewarded);
+    }
+}
@@ -1,28 +1,43 @@
 use reverse_geocoder::ReverseGeocoder;
 use chrono::prelude::*;
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::io;
-use std::time::Duration;
 use ratatui::prelude::*;
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use crossterm::event::{self, Event, KeyCode};
 use std::fs;
 use thiserror::Error;
-use rand::{distributions::Alphanumeric, prelude::*};
+use rand::prelude::*;
 
 use crate::kb;
+use crate::files::write_atomic;
+use crate::hr_log::HrHistory;
+use crate::alarms::AlarmEvent;
+use crate::battery::BatteryStatus;
+use crate::settings::{SettingField, SettingValue, Settings, TimeFormat, Units};
+use crate::coords::{bearing_deg, compass_point, distance_m, format_coordinates, format_distance, format_height, format_speed, CoordinateFormat};
+use crate::character::{xp_to_next_level, Character, Special};
+use crate::theme::Theme;
+use crate::quests::{Quest, QuestLog, QuestState};
+use crate::workshops::Workshop;
+use crate::stats::{StatsPeriod, StatsSummary};
+use crate::holotapes::Holotape;
+use crate::waypoints::{NavTarget, WaypointBook};
+use crate::position::{dop_rating, PositionFix, SkyView};
+use crate::status_bar::FixMode;
+use crate::tracks::Track;
+use crate::places::Place;
+use crate::geofences::{Geofence, GeofenceEvent};
+use crate::astro::{moon_illumination, moon_position, sun_position, DayTimes, SkyPosition};
 use kb::{show_virtual_keyboard, centered_rect};
 
 const DB_PATH: &str = "./data/db.json";
 
 use ratatui::widgets::{
-    Block, BorderType, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table, Wrap
+    Axis, Bar, BarChart, BarGroup, Block, BorderType, Borders, Chart, Dataset, GraphType, List, ListItem, ListState, Paragraph, Wrap
 };
-use ratatui::layout::{Alignment, Constraint};
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::layout::Alignment;
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Span, Line, Text};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -43,25 +58,25 @@ pub enum Error {
     ParseDBError(#[from] serde_json::Error),
 }
 
-pub fn get_map_data<'a>(coordinates: [f64; 2]) -> String {
-    let geocoder = ReverseGeocoder::new();
-    let spatial_time = spatialtime::osm::lookup(coordinates[0], coordinates[1]).unwrap();
+// The geocoder loads the whole places table, so the caller keeps one around.
+// The zone is the one already looked up for the status bar.
+pub fn get_map_data(geocoder: &ReverseGeocoder, coordinates: [f64; 2], timezone: Option<Tz>, format: CoordinateFormat) -> String {
+    let timezone = timezone.map_or("Unknown", |tz| tz.name());
     let coord_tuple: (f64, f64) = (coordinates[0], coordinates[1]);
     let search_result = geocoder.search(coord_tuple);
     format!(
-        "Latitude: {}\nLongitude: {}\nAddress: {}, {}, {}, {}\nTime Zone: {:?}",
-        coordinates[0],
-        coordinates[1],
+        "Position: {}\nAddress: {}, {}, {}, {}\nTime Zone: {}",
+        format_coordinates(coordinates, format),
         search_result.record.name,
         search_result.record.admin1,
         search_result.record.admin2,
         search_result.record.cc,
-        spatial_time.tzid
+        timezone
     )
 }
 
-pub fn render_map<'a>(map_text: Option<String>) -> Paragraph<'a> {
-    let bold_style = Style::default().add_modifier(Modifier::BOLD);
+pub fn render_map<'a>(map_text: Option<String>, theme: &Theme) -> Paragraph<'a> {
+    let bold_style = Style::default().fg(theme.fg).add_modifier(Modifier::BOLD);
     let text_content: std::borrow::Cow<'a, str> = match map_text {
         Some(s) => std::borrow::Cow::Owned(s),
         None => std::borrow::Cow::Borrowed("Map data not available"),
@@ -74,12 +89,248 @@ pub fn render_map<'a>(map_text: Option<String>) -> Paragraph<'a> {
         .block(
             Block::default()
                 .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
                 .title("Map")
                 .border_type(BorderType::Plain),
         )
 }
 
-pub fn render_stat<'a>() -> Paragraph<'a> {
+fn readout<'a>(label: &str, value: String, theme: &Theme) -> Line<'a> {
+    Line::from(vec![
+        Span::styled(format!("{:<11}", label), Style::default().fg(theme.highlight)),
+        Span::styled(value, Style::default().fg(theme.fg)),
+    ])
+}
+
+pub fn render_coords<'a>(fix: Option<&PositionFix>, units: Units, theme: &Theme) -> Paragraph<'a> {
+    let block = Block::default()
+        .title("Coordinates")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+    let Some(fix) = fix else {
+        return Paragraph::new("No GPS").style(Style::default().fg(theme.dim)).block(block);
+    };
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "--".into());
+
+    let mode = match fix.mode {
+        FixMode::Fix3D => "3D",
+        FixMode::Fix2D => "2D",
+        FixMode::NoFix => "NO FIX",
+    };
+    let mut lines = vec![readout("Fix", mode.to_string(), theme), Line::from("")];
+    match fix.coordinates() {
+        Some(coordinates) => lines.extend(
+            CoordinateFormat::ALL
+                .iter()
+                .map(|&format| readout(format.as_str(), format_coordinates(coordinates, format), theme)),
+        ),
+        None => lines.push(Line::from(Span::styled("Waiting for a position", Style::default().fg(theme.dim)))),
+    }
+    lines.extend([
+        Line::from(""),
+        readout("Altitude", or_dash(fix.altitude.map(|a| format_height(a, units))), theme),
+        readout("Speed", or_dash(fix.speed.map(|s| format_speed(s, units))), theme),
+        readout("Course", or_dash(fix.course().map(|c| format!("{:03.0}° {}", c, compass_point(c)))), theme),
+        readout("Satellites", format!("{} used / {} seen", fix.satellites_used, fix.satellites_visible), theme),
+        readout("HDOP", or_dash(fix.hdop.map(|h| format!("{:.1}", h))), theme),
+        readout("Fix Time", fix.at.format("%H:%M:%S UTC").to_string(), theme),
+    ]);
+
+    Paragraph::new(lines).block(block)
+}
+
+// A plain-words reason for the fix being what it is, and whether it's a
+// problem.
+fn gps_diagnosis(fix: Option<&PositionFix>, sky: &SkyView, enabled: bool) -> (String, bool) {
+    let Some(fix) = fix else {
+        return if enabled {
+            ("No data from the receiver. Check the GPS source in Settings, the wiring, or gpsd.".into(), true)
+        } else {
+            ("GPS is switched off in Settings.".into(), true)
+        };
+    };
+    // Not every receiver reports a sky view; the fix's own count stands in.
+    let heard = if sky.satellites.is_empty() { fix.satellites_visible as usize } else { sky.tracked() };
+    match fix.mode {
+        FixMode::NoFix if heard == 0 => ("No satellites heard. Get a clear view of the sky.".into(), true),
+        FixMode::NoFix if heard < 4 => (
+            format!("Hearing {} satellites, a fix needs at least 4. Get a clearer view of the sky.", heard),
+            true,
+        ),
+        FixMode::NoFix => (
+            "Signal is good; waiting on the receiver to download orbits. A cold start can take a few minutes.".into(),
+            true,
+        ),
+        FixMode::Fix2D => ("2D fix only, so no altitude. More satellites will give a 3D fix.".into(), true),
+        FixMode::Fix3D if fix.hdop.is_some_and(|hdop| hdop > 5.0) => (
+            "Satellites are bunched together in the sky, so the position may be off by tens of meters.".into(),
+            true,
+        ),
+        FixMode::Fix3D => ("Good fix.".into(), false),
+    }
+}
+
+pub fn render_gps_status<'a>(fix: Option<&PositionFix>, sky: &SkyView, enabled: bool, theme: &Theme) -> Paragraph<'a> {
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "--".into());
+    let dop = |dop: Option<f64>| or_dash(dop.map(|d| format!("{:.1} ({})", d, dop_rating(d))));
+    let (diagnosis, problem) = gps_diagnosis(fix, sky, enabled);
+
+    let mut lines = match fix {
+        Some(fix) => vec![
+            readout(
+                "Fix",
+                match fix.mode {
+                    FixMode::Fix3D => "3D",
+                    FixMode::Fix2D => "2D",
+                    FixMode::NoFix => "NO FIX",
+                }
+                .to_string(),
+                theme,
+            ),
+            readout(
+                "Satellites",
+                format!("{} used / {} in view / {} heard", fix.satellites_used, fix.satellites_visible, sky.tracked()),
+                theme,
+            ),
+            readout("HDOP", dop(fix.hdop), theme),
+            readout("VDOP", dop(fix.vdop), theme),
+            readout("Fix Time", fix.at.format("%H:%M:%S UTC").to_string(), theme),
+        ],
+        None => vec![readout("Fix", "NO GPS".to_string(), theme)],
+    };
+    let style = Style::default().fg(if problem { theme.warning } else { theme.highlight });
+    lines.extend([Line::from(""), Line::from(Span::styled(diagnosis, style))]);
+
+    Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("GPS Status")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .wrap(Wrap { trim: true })
+}
+
+// Signal strength per satellite, the ones in the fix first and then the
+// strongest, since a narrow screen only fits so many bars.
+pub fn render_snr_bars<'a>(sky: &SkyView, theme: &Theme) -> BarChart<'a> {
+    let mut satellites: Vec<_> = sky.satellites.iter().collect();
+    satellites.sort_by_key(|s| (!s.used, std::cmp::Reverse(s.snr)));
+    let bars: Vec<Bar> = satellites
+        .iter()
+        .map(|s| {
+            let color = if s.used { theme.highlight } else { theme.dim };
+            Bar::default()
+                .value(s.snr.unwrap_or(0) as u64)
+                .label(Line::from(s.label()))
+                .style(Style::default().fg(color))
+                .value_style(Style::default().fg(theme.background).bg(color))
+        })
+        .collect();
+
+    BarChart::default()
+        .block(
+            Block::default()
+                .title("Signal (dB-Hz)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(3)
+        .bar_gap(1)
+        .max(50)
+}
+
+// Sun and moon for today at `position`, in the zone the status bar uses.
+pub fn render_sky<'a>(position: Option<[f64; 2]>, times: Option<&DayTimes>, now: DateTime<Utc>, timezone: Option<Tz>, time_format: TimeFormat, theme: &Theme) -> Paragraph<'a> {
+    let block = Block::default()
+        .title("Sun & Moon")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+    let (Some(position), Some(times)) = (position, times) else {
+        return Paragraph::new("Waiting for a position").style(Style::default().fg(theme.dim)).block(block);
+    };
+
+    let local = |at: DateTime<Utc>| match timezone {
+        Some(tz) => at.with_timezone(&tz).format(time_format.short_pattern()).to_string(),
+        None => at.with_timezone(&Local).format(time_format.short_pattern()).to_string(),
+    };
+    let or_dash = |at: Option<DateTime<Utc>>| at.map(local).unwrap_or_else(|| "--".into());
+    // No rise and no set means the body stayed on one side of the horizon.
+    let rise_set = |rise: Option<DateTime<Utc>>, set: Option<DateTime<Utc>>, up: bool| match (rise, set) {
+        (None, None) if up => ("Up all day".to_string(), "--".to_string()),
+        (None, None) => ("Down all day".to_string(), "--".to_string()),
+        _ => (or_dash(rise), or_dash(set)),
+    };
+    let sky = |p: SkyPosition| format!("{:03.0}° {:<2} {:+.1}°", p.azimuth, compass_point(p.azimuth), p.elevation);
+
+    let (sun, moon) = (times.sun, times.moon);
+    let phase = moon_illumination(now);
+    let (sunrise, sunset) = rise_set(sun.sunrise, sun.sunset, sun.up_at_start);
+    let (moonrise, moonset) = rise_set(moon.moonrise, moon.moonset, moon.up_at_start);
+    let lines = vec![
+        readout("Sunrise", sunrise, theme),
+        readout("Sunset", sunset, theme),
+        readout("Solar Noon", or_dash(sun.solar_noon), theme),
+        readout("Civil", format!("{} - {}", or_dash(sun.civil_dawn), or_dash(sun.civil_dusk)), theme),
+        readout("Nautical", format!("{} - {}", or_dash(sun.nautical_dawn), or_dash(sun.nautical_dusk)), theme),
+        readout("Sun", sky(sun_position(now, position)), theme),
+        Line::from(""),
+        readout("Phase", format!("{} {:.0}%", phase.phase.as_str(), phase.fraction * 100.0), theme),
+        readout("Moon Age", format!("{:.1} days", phase.age_days), theme),
+        readout("Moonrise", moonrise, theme),
+        readout("Moonset", moonset, theme),
+        readout("Moon", sky(moon_position(now, position)), theme),
+    ];
+
+    Paragraph::new(lines).block(block)
+}
+
+pub fn render_compass_readout<'a>(fix: Option<&PositionFix>, target: Option<&NavTarget>, units: Units, theme: &Theme) -> Paragraph<'a> {
+    let course = fix.and_then(PositionFix::course);
+    let position = fix.and_then(PositionFix::coordinates);
+    let mut lines = vec![
+        readout("Course", course.map(|c| format!("{:03.0}° {}", c, compass_point(c))).unwrap_or_else(|| "-- (not moving)".into()), theme),
+        readout("Speed", fix.and_then(|f| f.speed).map(|s| format_speed(s, units)).unwrap_or_else(|| "--".into()), theme),
+        Line::from(""),
+    ];
+    match target {
+        Some(target) => {
+            lines.push(readout("Target", target.name.clone(), theme));
+            lines.push(readout("Range", range_to(position, Some(target.position), units), theme));
+            if let (Some(from), Some(course)) = (position, course) {
+                // Which way to turn, from -180 (hard left) to 180 (hard right).
+                let turn = (bearing_deg(from, target.position) - course + 540.0).rem_euclid(360.0) - 180.0;
+                let advice = match turn {
+                    t if t.abs() < 10.0 => "Straight ahead".to_string(),
+                    t if t < 0.0 => format!("{:.0}° left", -t),
+                    t => format!("{:.0}° right", t),
+                };
+                lines.push(readout("Turn", advice, theme));
+            }
+        }
+        None => lines.push(Line::from(Span::styled(
+            "No target. Pick one under WAYPOINTS or TRACKS.",
+            Style::default().fg(theme.dim),
+        ))),
+    }
+
+    Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Heading")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .wrap(Wrap { trim: true })
+}
+
+pub fn render_stat<'a>(theme: &Theme) -> Paragraph<'a> {
     Paragraph::new(vec![
         Line::from(vec![Span::raw("")]),
         Line::from(vec![Span::raw("")]),
@@ -88,22 +339,24 @@ pub fn render_stat<'a>() -> Paragraph<'a> {
         Line::from(vec![Span::raw("")]),
         Line::from(vec![Span::raw("")]),
         Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::styled("Unable to fit inside of the case :/", Style::default().fg(Color::LightBlue))]),
+        Line::from(vec![Span::styled("Unable to fit inside of the case :/", Style::default().fg(theme.highlight))]),
     ])
     .alignment(Alignment::Center)
     .block(
         Block::default()
             .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White))
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().fg(theme.fg))
             .title("Radio")
             .border_type(BorderType::Plain),
     )
 }
 
-pub fn render_inv<'a>(mut inv_list_state: &ListState, category_filter: &'a str) -> (List<'a>, Paragraph<'a>) {
+pub fn render_inv<'a>(inv_list_state: &ListState, category_filter: &'a str, theme: &Theme) -> (List<'a>, Paragraph<'a>) {
     let invs = Block::default()
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::White))
+        .border_style(Style::default().fg(theme.border))
+        .style(Style::default().fg(theme.fg))
         .title(category_filter)
         .border_type(BorderType::Plain);
 
@@ -128,7 +381,7 @@ pub fn render_inv<'a>(mut inv_list_state: &ListState, category_filter: &'a str)
 
     items.push(ListItem::new(Line::from(vec![Span::styled(
         "+ Add New",
-        Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
     )])));
 
     let selected_item = filtered_items
@@ -143,24 +396,19 @@ pub fn render_inv<'a>(mut inv_list_state: &ListState, category_filter: &'a str)
             created_at: chrono::Utc::now(),
         });
 
-    let list = List::new(items).block(invs).highlight_style(
-        Style::default()
-            .bg(Color::Yellow)
-            .fg(Color::Black)
-            .add_modifier(Modifier::BOLD),
-    );
+    let list = List::new(items).block(invs).highlight_style(theme.selected());
 
     let detail_lines = vec![
         Line::from(vec![Span::styled(
             format!("Name: {}", selected_item.name),
-            Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
         )]),
-        Line::from(vec![Span::raw(format!("Created: {}", selected_item.created_at))]),
+        Line::from(vec![Span::styled(format!("Created: {}", selected_item.created_at), Style::default().fg(theme.dim))]),
         Line::from(""),
-        Line::from(vec![Span::styled("Details:", Style::default().fg(Color::LightBlue))]),
-        Line::from(vec![Span::raw(format!("{}", selected_item.details))]),
+        Line::from(vec![Span::styled("Details:", Style::default().fg(theme.highlight))]),
+        Line::from(vec![Span::raw(selected_item.details.clone())]),
         Line::from(""),
-        Line::from(vec![Span::styled("Quantity:", Style::default().fg(Color::LightBlue))]),
+        Line::from(vec![Span::styled("Quantity:", Style::default().fg(theme.highlight))]),
         Line::from(vec![Span::raw(format!("{}", selected_item.quantity))])
     ];
 
@@ -169,6 +417,7 @@ pub fn render_inv<'a>(mut inv_list_state: &ListState, category_filter: &'a str)
             Block::default()
                 .title("Item Detail")
                 .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
                 .border_type(BorderType::Plain),
         )
         .wrap(Wrap { trim: true });
@@ -177,21 +426,21 @@ pub fn render_inv<'a>(mut inv_list_state: &ListState, category_filter: &'a str)
 }
 
 
-pub fn add_item_to_db() -> Result<Vec<Item>, Error> {
-    let mut stdout = io::stdout();
+pub fn add_item_to_db(theme: &Theme) -> Result<Vec<Item>, Error> {
+    let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     let mut rng = rand::thread_rng();
     let db_content = fs::read_to_string(DB_PATH)?;
     let mut parsed: Vec<Item> = serde_json::from_str(&db_content)?;
 
-    let name = show_virtual_keyboard(&mut terminal, "Item Name")
+    let name = show_virtual_keyboard(&mut terminal, "Item Name", theme)
         .map_err(|e| Error::ReadDBError(io::Error::new(io::ErrorKind::Other, e.to_string())))?;
-    let category = show_category_selector(&mut terminal)
+    let category = show_category_selector(&mut terminal, theme)
         .map_err(|e| Error::ReadDBError(io::Error::new(io::ErrorKind::Other, e.to_string())))?;
-    let details = show_virtual_keyboard(&mut terminal, "Item Details")
+    let details = show_virtual_keyboard(&mut terminal, "Item Details", theme)
         .map_err(|e| Error::ReadDBError(io::Error::new(io::ErrorKind::Other, e.to_string())))?;
-    let quantity = show_quantity_selector(&mut terminal, 0)
+    let quantity = show_quantity_selector(&mut terminal, 0, theme)
         .map_err(|e| Error::ReadDBError(io::Error::new(io::ErrorKind::Other, e.to_string())))?;
     let new_item = Item {
         id: rng.gen_range(0, 9999999),
@@ -203,29 +452,29 @@ pub fn add_item_to_db() -> Result<Vec<Item>, Error> {
     };
 
     parsed.push(new_item);
-    fs::write(DB_PATH, &serde_json::to_vec(&parsed)?)?;
+    write_db(&parsed)?;
     Ok(parsed)
 }
 
-pub fn show_category_selector<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<String> {
-    let categories = vec!["Weapons", "Apparel", "Aid", "Misc", "Junk", "Mods", "Ammo"];
+pub fn show_category_selector<B: Backend>(terminal: &mut Terminal<B>, theme: &Theme) -> io::Result<String> {
+    let categories = ["Weapons", "Apparel", "Aid", "Misc", "Junk", "Mods", "Ammo"];
+    let selected = show_choice_selector(terminal, "Select Category", &categories, 0, theme)?;
+    Ok(categories[selected].to_string())
+}
+
+pub fn show_choice_selector<B: Backend>(terminal: &mut Terminal<B>, title: &str, options: &[&str], initial: usize, theme: &Theme) -> io::Result<usize> {
     let mut state = ListState::default();
-    state.select(Some(0));
+    state.select(Some(initial.min(options.len().saturating_sub(1))));
 
     loop {
-        terminal.clear();
+        terminal.clear()?;
         terminal.draw(|f| {
             let size = centered_rect(70, 50, f.area());
-            let items: Vec<ListItem> = categories.iter().map(|c| ListItem::new(*c)).collect();
+            let items: Vec<ListItem> = options.iter().map(|c| ListItem::new(*c)).collect();
 
             let list = List::new(items)
-                .block(Block::default().borders(Borders::ALL).title("Select Category"))
-                .highlight_style(
-                    Style::default()
-                        .bg(Color::LightBlue)
-                        .fg(Color::Black)
-                        .add_modifier(Modifier::BOLD),
-                );
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(theme.border)).title(title))
+                .highlight_style(theme.selected());
 
             f.render_stateful_widget(list, size, &mut state);
         })?;
@@ -241,18 +490,18 @@ pub fn show_category_selector<B: Backend>(terminal: &mut Terminal<B>) -> io::Res
                 }
                 KeyCode::Char('s') | KeyCode::Char('S') => {
                     let i = match state.selected() {
-                        Some(i) if i < categories.len() - 1 => i + 1,
-                        _ => categories.len() - 1,
+                        Some(i) if i < options.len() - 1 => i + 1,
+                        _ => options.len() - 1,
                     };
                     state.select(Some(i));
                 }
                 KeyCode::Enter => {
                     if let Some(i) = state.selected() {
-                        return Ok(categories[i].to_string());
+                        return Ok(i);
                     }
                 }
                 KeyCode::Esc => {
-                    return Err(io::Error::new(io::ErrorKind::Interrupted, "Category selection cancelled"));
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "Selection cancelled"));
                 }
                 _ => {}
             }
@@ -260,25 +509,28 @@ pub fn show_category_selector<B: Backend>(terminal: &mut Terminal<B>) -> io::Res
     }
 }
 
-pub fn show_quantity_selector<B: Backend>(terminal: &mut Terminal<B>, initial_quantity: u32) -> io::Result<u32> {
-    let categories: Vec<String> = (0..101).map(|i| i.to_string()).collect();
+pub fn show_quantity_selector<B: Backend>(terminal: &mut Terminal<B>, initial_quantity: u32, theme: &Theme) -> io::Result<u32> {
+    show_number_selector(terminal, "Select Quantity", initial_quantity, 100, theme)
+}
+
+pub fn show_number_selector<B: Backend>(terminal: &mut Terminal<B>, title: &str, initial: u32, max: u32, theme: &Theme) -> io::Result<u32> {
+    show_stepped_selector(terminal, title, initial, 0, max, 1, theme)
+}
+
+pub fn show_stepped_selector<B: Backend>(terminal: &mut Terminal<B>, title: &str, initial: u32, min: u32, max: u32, step: u32, theme: &Theme) -> io::Result<u32> {
+    let categories: Vec<String> = (min..=max).step_by(step.max(1) as usize).map(|i| i.to_string()).collect();
     let mut state = ListState::default();
-    state.select(Some(initial_quantity as usize));
+    state.select(Some((initial.clamp(min, max) - min) as usize / step.max(1) as usize));
 
     loop {
-        terminal.clear();
+        terminal.clear()?;
         terminal.draw(|f| {
             let size = centered_rect(70, 50, f.area());
             let items: Vec<ListItem> = categories.iter().map(|c| ListItem::new(c.as_str())).collect();
 
             let list = List::new(items)
-                .block(Block::default().borders(Borders::ALL).title("Select Quantity"))
-                .highlight_style(
-                    Style::default()
-                        .bg(Color::LightBlue)
-                        .fg(Color::Black)
-                        .add_modifier(Modifier::BOLD),
-                );
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(theme.border)).title(title))
+                .highlight_style(theme.selected());
 
             f.render_stateful_widget(list, size, &mut state);
         })?;
@@ -317,29 +569,1020 @@ pub fn show_quantity_selector<B: Backend>(terminal: &mut Terminal<B>, initial_qu
     }
 }
 
-pub fn render_data<'a>() -> Paragraph<'a> {
+pub fn render_hr_summary<'a>(hr_history: &HrHistory, export_note: Option<&str>, theme: &Theme) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD);
+    let mut lines = Vec::new();
+
+    match hr_history.readings.last() {
+        Some(last) => {
+            let spo2 = last.spo2.map(|s| format!("{}%", s)).unwrap_or_else(|| "--".into());
+            lines.push(Line::from(vec![
+                Span::styled("Last: ", label),
+                Span::raw(format!("{} BPM  SpO2 {}", last.bpm, spo2)),
+            ]));
+        }
+        None => lines.push(Line::from(Span::styled("No pulse data recorded", label))),
+    }
+
+    let resting = hr_history
+        .resting
+        .map(|r| format!("{} BPM", r))
+        .unwrap_or_else(|| "--".into());
+    lines.push(Line::from(vec![Span::styled("Resting: ", label), Span::raw(resting)]));
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("HOURLY  MIN/AVG/MAX", label)));
+    for (start, s) in &hr_history.hourly {
+        lines.push(Line::from(format!("{}  {}/{}/{}", start.format("%H:00"), s.min, s.avg, s.max)));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("DAILY   MIN/AVG/MAX", label)));
+    for (start, s) in &hr_history.daily {
+        lines.push(Line::from(format!("{}  {}/{}/{}", start.format("%a %d"), s.min, s.avg, s.max)));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        export_note.unwrap_or("Press Enter to export CSV").to_string(),
+        Style::default().fg(theme.highlight),
+    )));
+
+    Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Heart Rate")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .wrap(Wrap { trim: true })
+}
+
+pub fn render_hr_chart<'a>(points: &'a [(f64, f64)], theme: &Theme) -> Chart<'a> {
+    let (low, high) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+    let (low, high) = if points.is_empty() {
+        (40.0, 180.0)
+    } else {
+        ((low - 10.0).max(0.0), high + 10.0)
+    };
+
+    let dataset = Dataset::default()
+        .name("BPM")
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(theme.fg))
+        .data(points);
+
+    Chart::new(vec![dataset])
+        .block(
+            Block::default()
+                .title("Last 24h")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-24.0, 0.0])
+                .labels(["-24h", "-12h", "now"]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([low, high])
+                .labels([format!("{:.0}", low), format!("{:.0}", (low + high) / 2.0), format!("{:.0}", high)]),
+        )
+}
+
+fn stat_line<'a>(label: &str, value: String, theme: &Theme) -> Line<'a> {
+    Line::from(vec![
+        Span::styled(format!("{:.<24}", format!("{} ", label)), Style::default().fg(theme.dim)),
+        Span::styled(format!(" {:>10}", value), Style::default().fg(theme.fg)),
+    ])
+}
+
+pub fn render_activity_stats<'a>(summary: &StatsSummary, period: StatsPeriod, units: Units, theme: &Theme) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD);
+    let activity = &summary.activity;
+    let uptime = activity.uptime_s / 60;
+    let or_dash = |value: Option<u16>| value.map(|v| format!("{} BPM", v)).unwrap_or_else(|| "--".into());
+    let count_or_dash = |count: u64| if count > 0 { count.to_string() } else { "--".into() };
+
+    let mut lines = vec![
+        Line::from(Span::styled(format!("< {} >", period.as_str()), label)),
+        Line::from(""),
+        stat_line("Distance Walked", format_distance(activity.distance_m, units), theme),
+        stat_line("Time Powered On", format!("{}h {:02}m", uptime / 60, uptime % 60), theme),
+        // Nothing records steps or radio stations yet, so they read as
+        // dashes until something does.
+        stat_line("Steps Taken", count_or_dash(activity.steps), theme),
+        stat_line("GPS Fixes Recorded", activity.gps_fixes.to_string(), theme),
+        stat_line("Radio Stations Found", count_or_dash(activity.radio_stations), theme),
+        stat_line("Quests Completed", summary.quests_completed.to_string(), theme),
+        stat_line("Highest Heart Rate", or_dash(summary.hr_high), theme),
+        stat_line("Lowest Heart Rate", or_dash(summary.hr_low), theme),
+        Line::from(""),
+        Line::from(Span::styled("ITEMS COLLECTED", label)),
+    ];
+    for (category, count) in &summary.items_by_category {
+        lines.push(stat_line(category, count.to_string(), theme));
+    }
+
+    Paragraph::new(lines).block(
+        Block::default()
+            .title("Statistics")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .border_type(BorderType::Plain),
+    )
+}
+
+pub fn render_settings<'a>(settings: &Settings, theme: &Theme) -> List<'a> {
+    let items: Vec<ListItem> = SettingField::all()
+        .iter()
+        .map(|field| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<17}", field.label()), Style::default().fg(theme.highlight)),
+                Span::raw(settings.get(*field).display()),
+            ]))
+        })
+        .collect();
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Settings")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+pub fn show_setting_editor(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    title: &str,
+    value: SettingValue,
+    theme: &Theme,
+) -> io::Result<SettingValue> {
+    match value {
+        SettingValue::Bool(on) => {
+            let selected = show_choice_selector(terminal, title, &["ON", "OFF"], if on { 0 } else { 1 }, theme)?;
+            Ok(SettingValue::Bool(selected == 0))
+        }
+        SettingValue::Choice { selected, options } => {
+            let selected = show_choice_selector(terminal, title, &options, selected, theme)?;
+            Ok(SettingValue::Choice { selected, options })
+        }
+        SettingValue::Number { value, min, max, step } => {
+            let value = show_stepped_selector(terminal, title, value, min, max, step, theme)?;
+            Ok(SettingValue::Number { value, min, max, step })
+        }
+        SettingValue::Text(current) => {
+            let text = show_virtual_keyboard(terminal, title, theme)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            Ok(SettingValue::Text(if text.trim().is_empty() { current } else { text }))
+        }
+    }
+}
+
+pub fn render_alarm_alert<'a>(alarm: &AlarmEvent, flash_on: bool, theme: &Theme) -> Paragraph<'a> {
+    let (fg, bg) = if flash_on { (theme.background, theme.warning) } else { (theme.warning, theme.background) };
+    let style = Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD);
+    let spo2 = alarm.spo2.map(|s| format!("{}%", s)).unwrap_or_else(|| "--".into());
+
     Paragraph::new(vec![
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw("Welcome")]),
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw("to")]),
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::styled("Pyp-Boy", Style::default().fg(Color::LightBlue))]),
-        Line::from(vec![Span::raw("")]),
-        Line::from(vec![Span::raw("Press 'i' to access inventory, 'a' to add random new items and 'd' to delete the currently selected item.")]),
+        Line::from(""),
+        Line::from("!!! WARNING !!!"),
+        Line::from(""),
+        Line::from(alarm.kind.as_str()),
+        Line::from(""),
+        Line::from(format!("{} BPM   SpO2 {}", alarm.bpm, spo2)),
+        Line::from(format!("at {}", alarm.at.with_timezone(&Local).format("%H:%M:%S"))),
+        Line::from(""),
+        Line::from("Press the main button to acknowledge"),
     ])
     .alignment(Alignment::Center)
+    .style(style)
     .block(
         Block::default()
             .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White))
-            .title("Home")
-            .border_type(BorderType::Plain),
+            .border_style(Style::default().fg(theme.border))
+            .border_type(BorderType::Double)
+            .style(style),
+    )
+}
+
+pub fn render_shutdown_warning<'a>(battery: Option<&BatteryStatus>, remaining: std::time::Duration, flash_on: bool, theme: &Theme) -> Paragraph<'a> {
+    let (fg, bg) = if flash_on { (theme.background, theme.warning) } else { (theme.warning, theme.background) };
+    let style = Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD);
+
+    Paragraph::new(vec![
+        Line::from(""),
+        Line::from("!!! BATTERY CRITICAL !!!"),
+        Line::from(""),
+        Line::from(battery.map(BatteryStatus::summary).unwrap_or_default()),
+        Line::from(""),
+        Line::from(format!("Shutting down in {} s", remaining.as_secs())),
+        Line::from(""),
+        Line::from("Plug in the charger to cancel"),
+    ])
+    .alignment(Alignment::Center)
+    .style(style)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .border_type(BorderType::Double)
+            .style(style),
     )
 }
 
+fn condition_bar(fraction: f32, width: usize) -> String {
+    let filled = ((fraction.clamp(0.0, 1.0) * width as f32).round()) as usize;
+    format!("[{}{}]", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+pub fn render_character_figure<'a>(character: &Character, battery_percent: Option<u8>, theme: &Theme) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD);
+    let needed = xp_to_next_level(character.level);
+    let xp_fraction = character.xp as f32 / needed as f32;
+    // The device's own charge, since nothing else here has hit points.
+    let battery = match battery_percent {
+        Some(percent) => format!("{} {:>3}%", condition_bar(percent as f32 / 100.0, 12), percent),
+        None => format!("{} --", condition_bar(0.0, 12)),
+    };
+
+    let lines = vec![
+        Line::from(Span::styled(character.name.clone(), label)),
+        Line::from(format!("LEVEL {}   XP {}/{}", character.level, character.xp, needed)),
+        Line::from(condition_bar(xp_fraction, 20)),
+        Line::from(""),
+        Line::from("   ___   "),
+        Line::from("  (o o)  "),
+        Line::from("__\\ - /__"),
+        Line::from("  |   |  "),
+        Line::from("   |___|   "),
+        Line::from("   /   \\   "),
+        Line::from("  /     \\  "),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("BATTERY ", label),
+            Span::raw(battery),
+        ]),
+    ];
+
+    Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Status")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+}
+
+pub fn render_character_fields<'a>(character: &Character, theme: &Theme) -> List<'a> {
+    let label = Style::default().fg(theme.highlight);
+    let mut items = vec![ListItem::new(Line::from(vec![
+        Span::styled(format!("{:<13}", "NAME"), label),
+        Span::raw(character.name.clone()),
+    ]))];
+
+    for (i, name) in Special::NAMES.iter().enumerate() {
+        let value = character.special.get(i);
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(format!("{:<13}", name), label),
+            Span::raw(format!("{:>2} {}", value, "▮".repeat(value as usize))),
+        ])));
+    }
+
+    for perk in &character.perks {
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(format!("{:<13}", "PERK"), label),
+            Span::raw(perk.clone()),
+        ])));
+    }
+    items.push(ListItem::new(Line::from(vec![Span::styled(
+        "+ Add Perk",
+        Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
+    )])));
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("S.P.E.C.I.A.L.")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+pub fn render_quest_list<'a>(log: &QuestLog, theme: &Theme) -> List<'a> {
+    let today = Local::now().date_naive();
+    let mut items: Vec<ListItem> = log
+        .quests
+        .iter()
+        .map(|quest| {
+            let marker = if log.current == Some(quest.id) { "» " } else { "  " };
+            let style = match quest.state {
+                QuestState::Active if quest.is_overdue(today) => Style::default().fg(theme.warning),
+                QuestState::Active => Style::default().fg(theme.fg),
+                QuestState::Completed => Style::default().fg(theme.dim),
+                QuestState::Failed => Style::default().fg(theme.dim).add_modifier(Modifier::CROSSED_OUT),
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(marker),
+                Span::styled(quest.title.clone(), style),
+            ]))
+        })
+        .collect();
+
+    items.push(ListItem::new(Line::from(vec![Span::styled(
+        "+ New Quest",
+        Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
+    )])));
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Quests")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+pub fn render_quest_detail<'a>(quest: Option<&Quest>, is_current: bool, format: CoordinateFormat, theme: &Theme) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight);
+    let block = Block::default()
+        .title("Quest Detail")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+
+    let Some(quest) = quest else {
+        return Paragraph::new("Press Enter to start a new quest")
+            .style(Style::default().fg(theme.dim))
+            .block(block);
+    };
+
+    let today = Local::now().date_naive();
+    let state = if is_current {
+        format!("{} (CURRENT)", quest.state.as_str())
+    } else {
+        quest.state.as_str().to_string()
+    };
+    let due = match quest.due {
+        Some(due) => Span::styled(
+            due.format("%a %d %b %Y").to_string(),
+            if quest.is_overdue(today) { Style::default().fg(theme.warning) } else { Style::default() },
+        ),
+        None => Span::raw("--"),
+    };
+    let waypoint = quest
+        .waypoint
+        .map(|w| format_coordinates(w, format))
+        .unwrap_or_else(|| "--".into());
+
+    let mut lines = vec![
+        Line::from(Span::styled(quest.title.clone(), label.add_modifier(Modifier::BOLD))),
+        Line::from(vec![Span::styled("State: ", label), Span::raw(state)]),
+        Line::from(vec![Span::styled("Due: ", label), due]),
+        Line::from(vec![Span::styled("Waypoint: ", label), Span::raw(waypoint)]),
+        Line::from(""),
+        Line::from(quest.description.clone()),
+        Line::from(""),
+        Line::from(Span::styled("Objectives:", label)),
+    ];
+    for objective in &quest.objectives {
+        let (check, style) = if objective.done {
+            ("[X] ", Style::default().fg(theme.dim))
+        } else {
+            ("[ ] ", Style::default())
+        };
+        lines.push(Line::from(Span::styled(format!("{}{}", check, objective.text), style)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Press Enter for actions", Style::default().fg(theme.dim))));
+
+    Paragraph::new(lines).block(block).wrap(Wrap { trim: true })
+}
+
+fn range_to(position: Option<[f64; 2]>, target: Option<[f64; 2]>, units: Units) -> String {
+    match (position, target) {
+        (Some(from), Some(to)) => {
+            let bearing = bearing_deg(from, to);
+            format!(
+                "{} {:03.0}° {}",
+                format_distance(distance_m(from, to), units),
+                bearing,
+                compass_point(bearing)
+            )
+        }
+        _ => "--".to_string(),
+    }
+}
+
+pub fn render_workshop_list<'a>(workshops: &[Workshop], position: Option<[f64; 2]>, units: Units, theme: &Theme) -> List<'a> {
+    let mut items: Vec<ListItem> = workshops
+        .iter()
+        .map(|workshop| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<14}", workshop.name), Style::default().fg(theme.fg)),
+                Span::styled(range_to(position, workshop.position, units), Style::default().fg(theme.dim)),
+            ]))
+        })
+        .collect();
+
+    items.push(ListItem::new(Line::from(vec![Span::styled(
+        "+ New Workshop",
+        Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
+    )])));
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Workshops")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+pub fn render_workshop_detail<'a>(
+    workshop: Option<&Workshop>,
+    position: Option<[f64; 2]>,
+    format: CoordinateFormat,
+    units: Units,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight);
+    let block = Block::default()
+        .title("Workshop Detail")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+
+    let Some(workshop) = workshop else {
+        return Paragraph::new("Press Enter to save this spot as a workshop")
+            .style(Style::default().fg(theme.dim))
+            .block(block);
+    };
+
+    let location = workshop
+        .position
+        .map(|p| format_coordinates(p, format))
+        .unwrap_or_else(|| "--".into());
+
+    let mut lines = vec![
+        Line::from(Span::styled(workshop.name.clone(), label.add_modifier(Modifier::BOLD))),
+        Line::from(vec![Span::styled("Position: ", label), Span::raw(location)]),
+        Line::from(vec![Span::styled("Range: ", label), Span::raw(range_to(position, workshop.position, units))]),
+        Line::from(""),
+        Line::from(workshop.notes.clone()),
+        Line::from(""),
+        Line::from(Span::styled("Stash:", label)),
+    ];
+    if workshop.stash.is_empty() {
+        lines.push(Line::from(Span::styled("(empty)", Style::default().fg(theme.dim))));
+    }
+    for item in &workshop.stash {
+        lines.push(Line::from(format!("{} x{} ({})", item.name, item.quantity, item.category)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Press Enter for actions", Style::default().fg(theme.dim))));
+
+    Paragraph::new(lines).block(block).wrap(Wrap { trim: true })
+}
+
+pub fn render_waypoint_list<'a>(book: &WaypointBook, position: Option<[f64; 2]>, units: Units, theme: &Theme) -> List<'a> {
+    let mut items: Vec<ListItem> = book
+        .waypoints
+        .iter()
+        .map(|waypoint| {
+            let marker = if book.is_target(waypoint) { "» " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::raw(marker),
+                Span::styled(format!("{} ", waypoint.icon.glyph()), Style::default().fg(theme.highlight)),
+                Span::styled(format!("{:<14}", waypoint.name), Style::default().fg(theme.fg)),
+                Span::styled(range_to(position, Some(waypoint.position), units), Style::default().fg(theme.dim)),
+            ]))
+        })
+        .collect();
+
+    for label in ["+ Mark Here", "+ Enter Coordinates"] {
+        items.push(ListItem::new(Line::from(vec![Span::styled(
+            label,
+            Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
+        )])));
+    }
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Waypoints")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+pub fn render_waypoint_detail<'a>(
+    book: &WaypointBook,
+    selected: usize,
+    position: Option<[f64; 2]>,
+    format: CoordinateFormat,
+    units: Units,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight);
+    let block = Block::default()
+        .title("Waypoint Detail")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+
+    let Some(waypoint) = book.waypoints.get(selected) else {
+        let hint = match (selected == book.waypoints.len(), position) {
+            (true, Some(here)) => format!("Press Enter to mark {}", format_coordinates(here, format)),
+            (true, None) => "Waiting for a GPS fix to mark this spot".to_string(),
+            (false, _) => "Press Enter to type in a position".to_string(),
+        };
+        return Paragraph::new(hint).style(Style::default().fg(theme.dim)).block(block);
+    };
+
+    let name = if book.is_target(waypoint) {
+        format!("{} (NAVIGATING)", waypoint.name)
+    } else {
+        waypoint.name.clone()
+    };
+    let lines = vec![
+        Line::from(Span::styled(name, label.add_modifier(Modifier::BOLD))),
+        Line::from(vec![
+            Span::styled("Icon: ", label),
+            Span::raw(format!("{} {}", waypoint.icon.glyph(), waypoint.icon.as_str())),
+        ]),
+        Line::from(vec![Span::styled("Position: ", label), Span::raw(format_coordinates(waypoint.position, format))]),
+        Line::from(vec![Span::styled("Range: ", label), Span::raw(range_to(position, Some(waypoint.position), units))]),
+        Line::from(vec![
+            Span::styled("Saved: ", label),
+            Span::raw(waypoint.created_at.with_timezone(&Local).format("%a %d %b %Y %H:%M").to_string()),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled("Press Enter for actions", Style::default().fg(theme.dim))),
+    ];
+
+    Paragraph::new(lines).block(block).wrap(Wrap { trim: true })
+}
+
+pub fn render_nearby_list<'a>(places: &[Place], position: Option<[f64; 2]>, target: Option<&NavTarget>, units: Units, theme: &Theme) -> List<'a> {
+    let items: Vec<ListItem> = places
+        .iter()
+        .map(|place| {
+            let marker = if place.is_target(target) { "» " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::raw(marker),
+                Span::styled(format!("{:<16}", place.name), Style::default().fg(theme.fg)),
+                Span::styled(range_to(position, Some(place.position), units), Style::default().fg(theme.dim)),
+            ]))
+        })
+        .collect();
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Nearby")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+pub fn render_nearby_detail<'a>(
+    place: Option<&Place>,
+    position: Option<[f64; 2]>,
+    target: Option<&NavTarget>,
+    radius_km: u32,
+    format: CoordinateFormat,
+    units: Units,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight);
+    let block = Block::default()
+        .title("Place Detail")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+
+    let Some(place) = place else {
+        let hint = match position {
+            Some(_) => format!("No towns within {}", format_distance(radius_km as f64 * 1000.0, units)),
+            None => "Waiting for a GPS fix".to_string(),
+        };
+        return Paragraph::new(hint).style(Style::default().fg(theme.dim)).block(block);
+    };
+
+    let name = if place.is_target(target) {
+        format!("{} (NAVIGATING)", place.name)
+    } else {
+        place.name.clone()
+    };
+    let lines = vec![
+        Line::from(Span::styled(name, label.add_modifier(Modifier::BOLD))),
+        Line::from(vec![Span::styled("Region: ", label), Span::raw(place.region())]),
+        Line::from(vec![Span::styled("Country: ", label), Span::raw(place.cc.clone())]),
+        Line::from(vec![Span::styled("Position: ", label), Span::raw(format_coordinates(place.position, format))]),
+        Line::from(vec![Span::styled("Range: ", label), Span::raw(range_to(position, Some(place.position), units))]),
+        Line::from(""),
+        Line::from(Span::styled("Press Enter for actions", Style::default().fg(theme.dim))),
+    ];
+
+    Paragraph::new(lines).block(block).wrap(Wrap { trim: true })
+}
+
+pub const GEOFENCE_CREATE_ROWS: [&str; 3] = ["+ Circle Around Waypoint", "+ Circle Around Workshop", "+ Polygon From Waypoints"];
+
+// `inside` is whichever side the monitor last saw each fence from.
+pub fn render_geofence_list<'a>(fences: &[Geofence], inside: &[Option<bool>], theme: &Theme) -> List<'a> {
+    let mut items: Vec<ListItem> = fences
+        .iter()
+        .zip(inside)
+        .map(|(fence, inside)| {
+            let marker = if *inside == Some(true) { "● " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(theme.highlight)),
+                Span::styled(fence.name.clone(), Style::default().fg(theme.fg)),
+            ]))
+        })
+        .collect();
+
+    for label in GEOFENCE_CREATE_ROWS {
+        items.push(ListItem::new(Line::from(vec![Span::styled(
+            label,
+            Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
+        )])));
+    }
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Geofences")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_geofence_detail<'a>(
+    fence: Option<&Geofence>,
+    inside: Option<bool>,
+    hint: &str,
+    history: &[GeofenceEvent],
+    quest_log: &QuestLog,
+    position: Option<[f64; 2]>,
+    units: Units,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight);
+    let block = Block::default()
+        .title("Geofence Detail")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+
+    let mut lines = match fence {
+        Some(fence) => {
+            let state = match inside {
+                Some(true) => "INSIDE",
+                Some(false) => "OUTSIDE",
+                None => "WAITING FOR FIX",
+            };
+            let edge = position.map(|here| {
+                let distance = fence.signed_distance_m(here);
+                let side = if distance <= 0.0 { "inside" } else { "outside" };
+                format!("{} {}", format_distance(distance.abs(), units), side)
+            });
+            let objective = fence.objective.and_then(|link| {
+                let quest = quest_log.quests.iter().find(|q| q.id == link.quest)?;
+                let objective = quest.objectives.get(link.objective)?;
+                Some(format!("{}{}: {}", if objective.done { "[X] " } else { "" }, quest.title, objective.text))
+            });
+            vec![
+                Line::from(Span::styled(format!("{} ({})", fence.name, state), label.add_modifier(Modifier::BOLD))),
+                Line::from(vec![Span::styled("Shape: ", label), Span::raw(fence.shape.describe())]),
+                Line::from(vec![Span::styled("Edge: ", label), Span::raw(edge.unwrap_or_else(|| "--".into()))]),
+                Line::from(vec![Span::styled("Objective: ", label), Span::raw(objective.unwrap_or_else(|| "--".into()))]),
+                Line::from(Span::styled("Press Enter for actions", Style::default().fg(theme.dim))),
+            ]
+        }
+        None => vec![Line::from(Span::styled(hint.to_string(), Style::default().fg(theme.dim)))],
+    };
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("History", label.add_modifier(Modifier::BOLD))));
+    if history.is_empty() {
+        lines.push(Line::from(Span::styled("Nothing crossed yet", Style::default().fg(theme.dim))));
+    }
+    for event in history.iter().rev() {
+        lines.push(Line::from(vec![
+            Span::styled(event.at.with_timezone(&Local).format("%d %b %H:%M ").to_string(), Style::default().fg(theme.dim)),
+            Span::raw(format!("{} {}", event.kind.as_str(), event.name)),
+        ]));
+    }
+
+    Paragraph::new(lines).block(block).wrap(Wrap { trim: true })
+}
+
+fn format_elapsed(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+// Row 0 is the recorder, then the saved tracks, then the import row.
+pub fn render_track_list<'a>(tracks: &[Track], recording: Option<&Track>, following: Option<&std::path::Path>, units: Units, theme: &Theme) -> List<'a> {
+    let action = Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD);
+    let recorder = match recording {
+        Some(track) => Line::from(vec![
+            Span::styled("■ Stop Recording ", Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)),
+            Span::styled(format_distance(track.stats().distance_m, units), Style::default().fg(theme.dim)),
+        ]),
+        None => Line::from(Span::styled("● Start Recording", action)),
+    };
+    let mut items = vec![ListItem::new(recorder)];
+
+    items.extend(tracks.iter().map(|track| {
+        let marker = if following == Some(track.path.as_path()) {
+            "» "
+        } else if track.shown {
+            "* "
+        } else {
+            "  "
+        };
+        ListItem::new(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{:<18}", track.name), Style::default().fg(theme.fg)),
+            Span::styled(format_distance(track.stats().distance_m, units), Style::default().fg(theme.dim)),
+        ]))
+    }));
+
+    items.push(ListItem::new(Line::from(Span::styled("+ Import GPX", action))));
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Tracks")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+pub fn render_track_detail<'a>(
+    track: Option<&Track>,
+    recorder_row: bool,
+    note: Option<&str>,
+    interval_s: u32,
+    units: Units,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let label = Style::default().fg(theme.highlight);
+    let block = Block::default()
+        .title("Track Detail")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+
+    let Some(track) = track else {
+        let hint = if recorder_row {
+            format!("Press Enter to start logging a fix every {} s", interval_s)
+        } else {
+            "Press Enter to import .gpx files from the data directory".to_string()
+        };
+        let mut lines = vec![Line::from(Span::styled(hint, Style::default().fg(theme.dim)))];
+        if let Some(note) = note {
+            lines.push(Line::from(""));
+            lines.push(Line::from(note.to_string()));
+        }
+        return Paragraph::new(lines).block(block).wrap(Wrap { trim: true });
+    };
+
+    let stats = track.stats();
+    let average = stats
+        .duration
+        .filter(|d| d.num_seconds() > 0)
+        .map(|d| format_speed(stats.distance_m / d.num_seconds() as f64, units))
+        .unwrap_or_else(|| "--".into());
+    let title = if recorder_row { format!("{} (RECORDING)", track.name) } else { track.name.clone() };
+    let mut lines = vec![
+        Line::from(Span::styled(title, label.add_modifier(Modifier::BOLD))),
+        Line::from(vec![Span::styled("Points: ", label), Span::raw(track.points.len().to_string())]),
+        Line::from(vec![Span::styled("Distance: ", label), Span::raw(format_distance(stats.distance_m, units))]),
+        Line::from(vec![
+            Span::styled("Duration: ", label),
+            Span::raw(stats.duration.map(format_elapsed).unwrap_or_else(|| "--".into())),
+        ]),
+        Line::from(vec![Span::styled("Avg Speed: ", label), Span::raw(average)]),
+        Line::from(vec![Span::styled("Elevation Gain: ", label), Span::raw(format_height(stats.elevation_gain_m, units))]),
+        Line::from(vec![
+            Span::styled("Started: ", label),
+            Span::raw(track.created_at.with_timezone(&Local).format("%a %d %b %Y %H:%M").to_string()),
+        ]),
+    ];
+    if let Some(source) = &track.source {
+        lines.push(Line::from(vec![Span::styled("Imported From: ", label), Span::raw(source.clone())]));
+    }
+    if let Some(note) = note {
+        lines.push(Line::from(""));
+        lines.push(Line::from(note.to_string()));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Press Enter for actions", Style::default().fg(theme.dim))));
+
+    Paragraph::new(lines).block(block).wrap(Wrap { trim: true })
+}
+
+pub fn render_holotape_list<'a>(holotapes: &[Holotape], theme: &Theme) -> List<'a> {
+    let mut items: Vec<ListItem> = holotapes
+        .iter()
+        .map(|tape| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    tape.created_at.with_timezone(&Local).format("%d %b ").to_string(),
+                    Style::default().fg(theme.dim),
+                ),
+                Span::styled(tape.title.clone(), Style::default().fg(theme.fg)),
+            ]))
+        })
+        .collect();
+
+    for action in ["+ Record Holotape", "+ Import Files"] {
+        items.push(ListItem::new(Line::from(vec![Span::styled(
+            action,
+            Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD),
+        )])));
+    }
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("Holotapes")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selected())
+}
+
+fn holotape_lines<'a>(tape: &Holotape, format: CoordinateFormat, theme: &Theme) -> Vec<Line<'a>> {
+    let label = Style::default().fg(theme.highlight);
+    let position = tape
+        .position
+        .map(|p| format_coordinates(p, format))
+        .unwrap_or_else(|| "--".into());
+
+    let mut lines = vec![
+        Line::from(Span::styled(tape.title.clone(), label.add_modifier(Modifier::BOLD))),
+        Line::from(vec![
+            Span::styled("Recorded: ", label),
+            Span::raw(tape.created_at.with_timezone(&Local).format("%a %d %b %Y %H:%M").to_string()),
+        ]),
+        Line::from(vec![Span::styled("Location: ", label), Span::raw(position)]),
+    ];
+    if let Some(source) = &tape.source {
+        lines.push(Line::from(vec![Span::styled("Source: ", label), Span::raw(source.clone())]));
+    }
+    lines.push(Line::from(""));
+    lines.extend(tape.body.lines().map(|line| Line::from(line.to_string())));
+    lines
+}
+
+pub fn render_holotape_detail<'a>(tape: Option<&Holotape>, format: CoordinateFormat, theme: &Theme) -> Paragraph<'a> {
+    let block = Block::default()
+        .title("Playback")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+        .border_type(BorderType::Plain);
+
+    match tape {
+        Some(tape) => Paragraph::new(holotape_lines(tape, format, theme))
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        None => Paragraph::new("Record a new holotape, or drop .txt/.md files into the data folder and import them")
+            .style(Style::default().fg(theme.dim))
+            .block(block)
+            .wrap(Wrap { trim: true }),
+    }
+}
+
+pub fn show_holotape_reader<B: Backend>(terminal: &mut Terminal<B>, tape: &Holotape, format: CoordinateFormat, theme: &Theme) -> io::Result<()> {
+    let mut scroll: u16 = 0;
+
+    loop {
+        terminal.clear()?;
+        terminal.draw(|f| {
+            let area = centered_rect(90, 90, f.area());
+            let reader = Paragraph::new(holotape_lines(tape, format, theme))
+                .style(Style::default().fg(theme.fg))
+                .block(
+                    Block::default()
+                        .title("Holotape (W/S scroll, Enter closes)")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(theme.border)),
+                )
+                .wrap(Wrap { trim: false })
+                .scroll((scroll, 0));
+            f.render_widget(reader, area);
+        })?;
+
+        if let Event::Key(key) = event::read().map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))? {
+            match key.code {
+                KeyCode::Char('w') | KeyCode::Char('W') | KeyCode::Up => scroll = scroll.saturating_sub(1),
+                KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Down => {
+                    let lines = 5 + tape.body.lines().count() as u16;
+                    scroll = (scroll + 1).min(lines);
+                }
+                KeyCode::Enter | KeyCode::Esc => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
 pub fn read_db() -> Result<Vec<Item>, Error> {
     let db_content = fs::read_to_string(DB_PATH)?;
     let parsed: Vec<Item> = serde_json::from_str(&db_content)?;
     Ok(parsed)
 }
+
+pub fn write_db(items: &[Item]) -> Result<(), Error> {
+    write_atomic(DB_PATH, &serde_json::to_vec(items)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{Constellation, SkySatellite};
+
+    fn fix(mode: FixMode, satellites_visible: u8, hdop: Option<f64>) -> PositionFix {
+        PositionFix { mode, satellites_visible, hdop, ..PositionFix::default() }
+    }
+
+    // `heard` satellites with a signal plus one predicted overhead but silent.
+    fn sky(heard: u8) -> SkyView {
+        let satellite = |prn, snr| SkySatellite {
+            constellation: Constellation::Gps,
+            prn,
+            elevation: Some(40),
+            azimuth: Some(180),
+            snr,
+            used: false,
+        };
+        let mut satellites: Vec<SkySatellite> = (1..=heard as u16).map(|prn| satellite(prn, Some(30))).collect();
+        satellites.push(satellite(99, None));
+        SkyView { satellites }
+    }
+
+    fn diagnose(fix: Option<PositionFix>, sky: &SkyView, enabled: bool) -> (String, bool) {
+        gps_diagnosis(fix.as_ref(), sky, enabled)
+    }
+
+    fn says(diagnosis: (String, bool), words: &str, problem: bool) {
+        assert!(diagnosis.0.contains(words), "{:?} doesn't say {:?}", diagnosis.0, words);
+        assert_eq!(diagnosis.1, problem, "{:?}", diagnosis.0);
+    }
+
+    #[test]
+    fn no_fix_at_all() {
+        says(diagnose(None, &SkyView::default(), true), "No data from the receiver", true);
+        says(diagnose(None, &SkyView::default(), false), "switched off in Settings", true);
+    }
+
+    #[test]
+    fn no_fix_counts_the_satellites_heard() {
+        says(diagnose(Some(fix(FixMode::NoFix, 0, None)), &sky(0), true), "No satellites heard", true);
+        says(diagnose(Some(fix(FixMode::NoFix, 9, None)), &sky(3), true), "Hearing 3 satellites", true);
+        says(diagnose(Some(fix(FixMode::NoFix, 0, None)), &sky(4), true), "download orbits", true);
+        // Without a sky view, the fix's own count of satellites in view is used.
+        says(diagnose(Some(fix(FixMode::NoFix, 2, None)), &SkyView::default(), true), "Hearing 2 satellites", true);
+        says(diagnose(Some(fix(FixMode::NoFix, 7, None)), &SkyView::default(), true), "download orbits", true);
+    }
+
+    #[test]
+    fn fixes_by_quality() {
+        says(diagnose(Some(fix(FixMode::Fix2D, 5, Some(1.2))), &sky(5), true), "2D fix only", true);
+        says(diagnose(Some(fix(FixMode::Fix3D, 5, Some(6.5))), &sky(5), true), "bunched together", true);
+        says(diagnose(Some(fix(FixMode::Fix3D, 5, Some(5.0))), &sky(5), true), "Good fix", false);
+        says(diagnose(Some(fix(FixMode::Fix3D, 8, None)), &sky(8), true), "Good fix", false);
+    }
+}
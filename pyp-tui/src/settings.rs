@@ -0,0 +1,383 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::alarms::{AlarmThresholds, ThresholdField};
+use crate::coords::CoordinateFormat;
+use crate::idle::IdleAction;
+use crate::position::GpsSource;
+use crate::theme::{format_hex_color, parse_hex_color, ColorDepth, ThemePreset};
+use crate::Error;
+
+const SETTINGS_PATH: &str = "./data/settings.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Units {
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub const ALL: [Units; 2] = [Units::Metric, Units::Imperial];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Units::Metric => "Metric",
+            Units::Imperial => "Imperial",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeFormat {
+    H24,
+    H12,
+}
+
+impl TimeFormat {
+    pub const ALL: [TimeFormat; 2] = [TimeFormat::H24, TimeFormat::H12];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeFormat::H24 => "24 Hour",
+            TimeFormat::H12 => "12 Hour",
+        }
+    }
+
+    pub fn clock_pattern(&self) -> &'static str {
+        match self {
+            TimeFormat::H24 => "%H:%M:%S",
+            TimeFormat::H12 => "%I:%M:%S %p",
+        }
+    }
+
+    // For times of day that don't need seconds.
+    pub fn short_pattern(&self) -> &'static str {
+        match self {
+            TimeFormat::H24 => "%H:%M",
+            TimeFormat::H12 => "%I:%M %p",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: ThemePreset,
+    pub custom_color: [u8; 3],
+    pub color_depth: ColorDepth,
+    pub crt_effects: bool,
+    pub boot_sequence: bool,
+    pub idle_timeout_s: u32,
+    pub idle_action: IdleAction,
+    pub tick_rate_ms: u32,
+    pub units: Units,
+    pub coordinate_format: CoordinateFormat,
+    pub time_format: TimeFormat,
+    pub pulse_sensor: bool,
+    pub battery_monitor: bool,
+    pub gps: bool,
+    pub gps_source: GpsSource,
+    pub gps_device: String,
+    pub track_interval_s: u32,
+    pub nearby_radius_km: u32,
+    pub alarms: AlarmThresholds,
+    pub active_profile: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: ThemePreset::Green,
+            custom_color: [255, 255, 255],
+            color_depth: ColorDepth::Auto,
+            crt_effects: true,
+            boot_sequence: true,
+            idle_timeout_s: 120,
+            idle_action: IdleAction::Clock,
+            tick_rate_ms: 200,
+            units: Units::Metric,
+            coordinate_format: CoordinateFormat::Decimal,
+            time_format: TimeFormat::H24,
+            pulse_sensor: true,
+            battery_monitor: true,
+            gps: true,
+            gps_source: GpsSource::Gpsd,
+            gps_device: "/dev/serial0".to_string(),
+            track_interval_s: 5,
+            nearby_radius_km: 50,
+            alarms: AlarmThresholds::default(),
+            active_profile: "default".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingValue {
+    Bool(bool),
+    Choice { selected: usize, options: Vec<&'static str> },
+    Number { value: u32, min: u32, max: u32, step: u32 },
+    Text(String),
+}
+
+impl SettingValue {
+    pub fn display(&self) -> String {
+        match self {
+            SettingValue::Bool(true) => "ON".to_string(),
+            SettingValue::Bool(false) => "OFF".to_string(),
+            SettingValue::Choice { selected, options } => options[*selected].to_string(),
+            SettingValue::Number { value, .. } => value.to_string(),
+            SettingValue::Text(text) => text.clone(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SettingField {
+    Theme,
+    CustomColor,
+    ColorDepth,
+    CrtEffects,
+    BootSequence,
+    TickRate,
+    IdleTimeout,
+    IdleAction,
+    Units,
+    CoordinateFormat,
+    TimeFormat,
+    PulseSensor,
+    BatteryMonitor,
+    Gps,
+    GpsSource,
+    GpsDevice,
+    TrackInterval,
+    NearbyRadius,
+    Alarm(ThresholdField),
+    ActiveProfile,
+}
+
+impl SettingField {
+    pub fn all() -> Vec<SettingField> {
+        let mut fields = vec![
+            SettingField::Theme,
+            SettingField::CustomColor,
+            SettingField::ColorDepth,
+            SettingField::CrtEffects,
+            SettingField::BootSequence,
+            SettingField::TickRate,
+            SettingField::IdleTimeout,
+            SettingField::IdleAction,
+            SettingField::Units,
+            SettingField::CoordinateFormat,
+            SettingField::TimeFormat,
+            SettingField::PulseSensor,
+            SettingField::BatteryMonitor,
+            SettingField::Gps,
+            SettingField::GpsSource,
+            SettingField::GpsDevice,
+            SettingField::TrackInterval,
+            SettingField::NearbyRadius,
+        ];
+        fields.extend(ThresholdField::ALL.iter().map(|f| SettingField::Alarm(*f)));
+        fields.push(SettingField::ActiveProfile);
+        fields
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingField::Theme => "Theme Color",
+            SettingField::CustomColor => "Custom RGB",
+            SettingField::ColorDepth => "Color Depth",
+            SettingField::CrtEffects => "CRT Effects",
+            SettingField::BootSequence => "Boot Sequence",
+            SettingField::TickRate => "Tick Rate (ms)",
+            SettingField::IdleTimeout => "Idle After (s)",
+            SettingField::IdleAction => "Idle Screen",
+            SettingField::Units => "Units",
+            SettingField::CoordinateFormat => "Coordinates",
+            SettingField::TimeFormat => "Time Format",
+            SettingField::PulseSensor => "Pulse Sensor",
+            SettingField::BatteryMonitor => "Battery Monitor",
+            SettingField::Gps => "GPS",
+            SettingField::GpsSource => "GPS Source",
+            SettingField::GpsDevice => "NMEA Device",
+            SettingField::TrackInterval => "Track Every (s)",
+            SettingField::NearbyRadius => "Nearby Within (km)",
+            SettingField::Alarm(field) => field.as_str(),
+            SettingField::ActiveProfile => "Active Profile",
+        }
+    }
+}
+
+fn choice<T: PartialEq>(current: T, all: &[T], as_str: fn(&T) -> &'static str) -> SettingValue {
+    SettingValue::Choice {
+        selected: all.iter().position(|v| *v == current).unwrap_or(0),
+        options: all.iter().map(as_str).collect(),
+    }
+}
+
+impl Settings {
+    pub fn get(&self, field: SettingField) -> SettingValue {
+        match field {
+            SettingField::Theme => choice(self.theme, &ThemePreset::ALL, ThemePreset::as_str),
+            SettingField::CustomColor => SettingValue::Text(format_hex_color(self.custom_color)),
+            SettingField::ColorDepth => choice(self.color_depth, &ColorDepth::ALL, ColorDepth::as_str),
+            SettingField::CrtEffects => SettingValue::Bool(self.crt_effects),
+            SettingField::BootSequence => SettingValue::Bool(self.boot_sequence),
+            SettingField::TickRate => SettingValue::Number { value: self.tick_rate_ms, min: 50, max: 1000, step: 50 },
+            SettingField::IdleTimeout => SettingValue::Number { value: self.idle_timeout_s, min: 0, max: 600, step: 15 },
+            SettingField::IdleAction => choice(self.idle_action, &IdleAction::ALL, IdleAction::as_str),
+            SettingField::Units => choice(self.units, &Units::ALL, Units::as_str),
+            SettingField::CoordinateFormat => choice(self.coordinate_format, &CoordinateFormat::ALL, CoordinateFormat::as_str),
+            SettingField::TimeFormat => choice(self.time_format, &TimeFormat::ALL, TimeFormat::as_str),
+            SettingField::PulseSensor => SettingValue::Bool(self.pulse_sensor),
+            SettingField::BatteryMonitor => SettingValue::Bool(self.battery_monitor),
+            SettingField::Gps => SettingValue::Bool(self.gps),
+            SettingField::GpsSource => choice(self.gps_source, &GpsSource::ALL, GpsSource::as_str),
+            SettingField::GpsDevice => SettingValue::Text(self.gps_device.clone()),
+            SettingField::TrackInterval => SettingValue::Number { value: self.track_interval_s, min: 1, max: 60, step: 1 },
+            SettingField::NearbyRadius => SettingValue::Number { value: self.nearby_radius_km, min: 5, max: 250, step: 5 },
+            SettingField::Alarm(field) => {
+                let (min, max) = self.alarms.range(field);
+                SettingValue::Number { value: self.alarms.get(field), min, max, step: 1 }
+            }
+            SettingField::ActiveProfile => SettingValue::Text(self.active_profile.clone()),
+        }
+    }
+
+    pub fn set(&mut self, field: SettingField, value: SettingValue) {
+        match (field, value) {
+            (SettingField::Theme, SettingValue::Choice { selected, .. }) => self.theme = ThemePreset::ALL[selected],
+            (SettingField::CustomColor, SettingValue::Text(text)) => {
+                // Leaving the editor with the color as it was keeps the preset.
+                if let Some(rgb) = parse_hex_color(&text).filter(|rgb| *rgb != self.custom_color) {
+                    self.custom_color = rgb;
+                    self.theme = ThemePreset::Custom;
+                }
+            }
+            (SettingField::ColorDepth, SettingValue::Choice { selected, .. }) => self.color_depth = ColorDepth::ALL[selected],
+            (SettingField::CrtEffects, SettingValue::Bool(on)) => self.crt_effects = on,
+            (SettingField::BootSequence, SettingValue::Bool(on)) => self.boot_sequence = on,
+            (SettingField::TickRate, SettingValue::Number { value, .. }) => self.tick_rate_ms = value.clamp(50, 1000),
+            (SettingField::IdleTimeout, SettingValue::Number { value, .. }) => self.idle_timeout_s = value.min(600),
+            (SettingField::IdleAction, SettingValue::Choice { selected, .. }) => self.idle_action = IdleAction::ALL[selected],
+            (SettingField::Units, SettingValue::Choice { selected, .. }) => self.units = Units::ALL[selected],
+            (SettingField::CoordinateFormat, SettingValue::Choice { selected, .. }) => {
+                self.coordinate_format = CoordinateFormat::ALL[selected]
+            }
+            (SettingField::TimeFormat, SettingValue::Choice { selected, .. }) => self.time_format = TimeFormat::ALL[selected],
+            (SettingField::PulseSensor, SettingValue::Bool(on)) => self.pulse_sensor = on,
+            (SettingField::BatteryMonitor, SettingValue::Bool(on)) => self.battery_monitor = on,
+            (SettingField::Gps, SettingValue::Bool(on)) => self.gps = on,
+            (SettingField::GpsSource, SettingValue::Choice { selected, .. }) => self.gps_source = GpsSource::ALL[selected],
+            (SettingField::GpsDevice, SettingValue::Text(text)) => {
+                if !text.trim().is_empty() {
+                    self.gps_device = text.trim().to_string();
+                }
+            }
+            (SettingField::TrackInterval, SettingValue::Number { value, .. }) => self.track_interval_s = value.clamp(1, 60),
+            (SettingField::NearbyRadius, SettingValue::Number { value, .. }) => self.nearby_radius_km = value.clamp(5, 250),
+            (SettingField::Alarm(field), SettingValue::Number { value, .. }) => self.alarms.set(field, value),
+            (SettingField::ActiveProfile, SettingValue::Text(text)) => {
+                let profile: String = text
+                    .trim()
+                    .chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == ' ')
+                    .collect();
+                if !profile.trim().is_empty() {
+                    self.active_profile = profile.trim().replace(' ', "_").to_lowercase();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn read_settings() -> Result<Settings, Error> {
+    match fs::read_to_string(SETTINGS_PATH) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_settings(settings: &Settings) -> Result<(), Error> {
+    fs::write(SETTINGS_PATH, &serde_json::to_vec_pretty(settings)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Some other value the editor could hand back for the field.
+    fn changed(field: SettingField, value: SettingValue) -> SettingValue {
+        match (field, value) {
+            (_, SettingValue::Bool(on)) => SettingValue::Bool(!on),
+            (_, SettingValue::Choice { selected, options }) => SettingValue::Choice { selected: (selected + 1) % options.len(), options },
+            (_, SettingValue::Number { value, min, max, step }) => {
+                SettingValue::Number { value: if value == max { min } else { max }, min, max, step }
+            }
+            (SettingField::CustomColor, _) => SettingValue::Text("1A2B3C".into()),
+            (SettingField::ActiveProfile, _) => SettingValue::Text("scout".into()),
+            (_, SettingValue::Text(_)) => SettingValue::Text("/dev/ttyUSB0".into()),
+        }
+    }
+
+    #[test]
+    fn every_field_reads_back_what_was_set() {
+        for field in SettingField::all() {
+            let mut settings = Settings::default();
+            settings.set(field, settings.get(field));
+            assert_eq!(settings, Settings::default(), "{:?}", field);
+
+            let value = changed(field, settings.get(field));
+            settings.set(field, value.clone());
+            assert_eq!(settings.get(field), value, "{:?}", field);
+        }
+    }
+
+    #[test]
+    fn numbers_are_clamped_to_their_range() {
+        let number = |value| SettingValue::Number { value, min: 0, max: 0, step: 1 };
+        let mut settings = Settings::default();
+        let cases = [
+            (SettingField::TickRate, 0, 50),
+            (SettingField::TickRate, 5000, 1000),
+            (SettingField::IdleTimeout, 9999, 600),
+            (SettingField::TrackInterval, 0, 1),
+            (SettingField::TrackInterval, 300, 60),
+            (SettingField::NearbyRadius, 0, 5),
+            (SettingField::NearbyRadius, 1000, 250),
+        ];
+        for (field, value, expected) in cases {
+            settings.set(field, number(value));
+            assert_eq!(settings.get(field).display(), expected.to_string(), "{:?} set to {}", field, value);
+        }
+    }
+
+    #[test]
+    fn profile_names_are_safe_file_names() {
+        let mut settings = Settings::default();
+        let cases = [
+            ("  Night Shift  ", "night_shift"),
+            ("../../etc/passwd", "etcpasswd"),
+            ("Vault-101!", "vault101"),
+            // Nothing usable left, so the profile stays as it was.
+            ("../!?", "vault101"),
+            ("   ", "vault101"),
+        ];
+        for (typed, profile) in cases {
+            settings.set(SettingField::ActiveProfile, SettingValue::Text(typed.into()));
+            assert_eq!(settings.active_profile, profile, "{:?}", typed);
+        }
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        assert_eq!(serde_json::from_str::<Settings>("{}").unwrap(), Settings::default());
+
+        let settings: Settings = serde_json::from_str(r#"{"units": "Imperial", "tick_rate_ms": 100, "retired": true}"#).unwrap();
+        assert_eq!(settings, Settings { units: Units::Imperial, tick_rate_ms: 100, ..Settings::default() });
+    }
+}
@@ -0,0 +1,300 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+use crate::menus::InvSubMenu;
+use crate::hr_log::HrHistory;
+use crate::quests::{QuestLog, QuestState};
+use crate::render_tabs::Item;
+use crate::Error;
+
+const ACTIVITY_PATH: &str = "./data/activity.json";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Today,
+    Week,
+    AllTime,
+}
+
+impl StatsPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsPeriod::Today => "TODAY",
+            StatsPeriod::Week => "THIS WEEK",
+            StatsPeriod::AllTime => "ALL TIME",
+        }
+    }
+
+    pub fn next(&self) -> StatsPeriod {
+        match self {
+            StatsPeriod::Today => StatsPeriod::Week,
+            StatsPeriod::Week => StatsPeriod::AllTime,
+            StatsPeriod::AllTime => StatsPeriod::Today,
+        }
+    }
+
+    pub fn previous(&self) -> StatsPeriod {
+        match self {
+            StatsPeriod::Today => StatsPeriod::AllTime,
+            StatsPeriod::Week => StatsPeriod::Today,
+            StatsPeriod::AllTime => StatsPeriod::Week,
+        }
+    }
+
+    // First local day included in the period; the week is the last seven days.
+    pub fn first_day(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            StatsPeriod::Today => Some(today),
+            StatsPeriod::Week => Some(today - chrono::Duration::days(6)),
+            StatsPeriod::AllTime => None,
+        }
+    }
+
+    // Days after today, logged while the clock was wrong, only count towards
+    // all time.
+    fn includes_day(&self, day: NaiveDate, today: NaiveDate) -> bool {
+        self.first_day(today).is_none_or(|first| (first..=today).contains(&day))
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>, today: NaiveDate) -> bool {
+        self.includes_day(at.with_timezone(&Local).date_naive(), today)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DayStats {
+    pub distance_m: f64,
+    pub uptime_s: u64,
+    pub steps: u64,
+    pub gps_fixes: u64,
+    pub radio_stations: u64,
+}
+
+// Counters that have no other log behind them, bucketed by local day so the
+// period filters can sum them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ActivityLog {
+    pub days: BTreeMap<NaiveDate, DayStats>,
+}
+
+impl ActivityLog {
+    fn today(&mut self) -> &mut DayStats {
+        self.days.entry(Local::now().date_naive()).or_default()
+    }
+
+    pub fn record_uptime(&mut self, seconds: u64) {
+        self.today().uptime_s += seconds;
+    }
+
+    pub fn record_distance(&mut self, meters: f64) {
+        self.today().distance_m += meters;
+    }
+
+    pub fn record_gps_fix(&mut self) {
+        self.today().gps_fixes += 1;
+    }
+
+    pub fn totals(&self, period: StatsPeriod, today: NaiveDate) -> DayStats {
+        self.days
+            .iter()
+            .filter(|(day, _)| period.includes_day(**day, today))
+            .fold(DayStats::default(), |total, (_, day)| DayStats {
+                distance_m: total.distance_m + day.distance_m,
+                uptime_s: total.uptime_s + day.uptime_s,
+                steps: total.steps + day.steps,
+                gps_fixes: total.gps_fixes + day.gps_fixes,
+                radio_stations: total.radio_stations + day.radio_stations,
+            })
+    }
+}
+
+pub struct StatsSummary {
+    pub activity: DayStats,
+    pub items_by_category: Vec<(&'static str, usize)>,
+    pub quests_completed: usize,
+    pub hr_high: Option<u16>,
+    pub hr_low: Option<u16>,
+}
+
+pub fn summarize_stats(
+    activity: &ActivityLog,
+    items: &[Item],
+    quests: &QuestLog,
+    hr_history: &HrHistory,
+    period: StatsPeriod,
+) -> StatsSummary {
+    summarize_stats_on(activity, items, quests, hr_history, period, Local::now().date_naive())
+}
+
+fn summarize_stats_on(
+    activity: &ActivityLog,
+    items: &[Item],
+    quests: &QuestLog,
+    hr_history: &HrHistory,
+    period: StatsPeriod,
+    today: NaiveDate,
+) -> StatsSummary {
+    let items_by_category = InvSubMenu::ALL
+        .iter()
+        .map(|category| {
+            let count = items
+                .iter()
+                .filter(|item| item.category.eq_ignore_ascii_case(category.as_str()))
+                .filter(|item| period.contains(item.created_at, today))
+                .count();
+            (category.as_str(), count)
+        })
+        .collect();
+    let quests_completed = quests
+        .quests
+        .iter()
+        .filter(|q| q.state == QuestState::Completed)
+        .filter(|q| q.finished_at.is_some_and(|at| period.contains(at, today)))
+        .count();
+    let bpm = || hr_history.readings.iter().filter(|r| period.contains(r.at, today)).map(|r| r.bpm);
+    let (hr_high, hr_low) = match period {
+        StatsPeriod::AllTime => (hr_history.high, hr_history.low),
+        _ => (bpm().max(), bpm().min()),
+    };
+
+    StatsSummary {
+        activity: activity.totals(period, today),
+        items_by_category,
+        quests_completed,
+        hr_high,
+        hr_low,
+    }
+}
+
+pub fn read_activity_log() -> Result<ActivityLog, Error> {
+    match fs::read_to_string(ACTIVITY_PATH) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(ActivityLog::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_activity_log(log: &ActivityLog) -> Result<(), Error> {
+    fs::write(ACTIVITY_PATH, &serde_json::to_vec(log)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pulse::PulseReading;
+    use crate::quests::Quest;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 14).unwrap()
+    }
+
+    fn local(day: NaiveDate, hour: u32, minute: u32) -> DateTime<Utc> {
+        Local
+            .from_local_datetime(&day.and_hms_opt(hour, minute, 0).unwrap())
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn days_ago(days: i64) -> NaiveDate {
+        today() - chrono::Duration::days(days)
+    }
+
+    fn item(category: &str, created_at: DateTime<Utc>) -> Item {
+        Item {
+            id: 0,
+            name: "Stimpak".into(),
+            details: String::new(),
+            quantity: 1,
+            category: category.into(),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn periods_start_at_local_midnight() {
+        let today = today();
+        assert!(StatsPeriod::Today.contains(local(today, 0, 0), today));
+        assert!(StatsPeriod::Today.contains(local(today, 23, 59), today));
+        assert!(!StatsPeriod::Today.contains(local(days_ago(1), 23, 59), today));
+        assert!(!StatsPeriod::Today.contains(local(days_ago(-1), 0, 0), today));
+
+        assert_eq!(StatsPeriod::Week.first_day(today), Some(days_ago(6)));
+        assert!(StatsPeriod::Week.contains(local(days_ago(6), 0, 0), today));
+        assert!(!StatsPeriod::Week.contains(local(days_ago(7), 23, 59), today));
+
+        assert_eq!(StatsPeriod::AllTime.first_day(today), None);
+        assert!(StatsPeriod::AllTime.contains(local(days_ago(5000), 12, 0), today));
+    }
+
+    #[test]
+    fn totals_only_add_up_days_in_the_period() {
+        let mut log = ActivityLog::default();
+        let day = |distance_m, gps_fixes| DayStats { distance_m, gps_fixes, ..DayStats::default() };
+        log.days.insert(today(), day(100.0, 1));
+        log.days.insert(days_ago(1), day(20.0, 2));
+        log.days.insert(days_ago(6), day(3.0, 4));
+        log.days.insert(days_ago(7), day(0.5, 8));
+        log.days.insert(today() + chrono::Duration::days(1), day(0.25, 16));
+
+        let totals = |period| log.totals(period, today());
+        assert_eq!(totals(StatsPeriod::Today), day(100.0, 1));
+        assert_eq!(totals(StatsPeriod::Week), day(123.0, 7));
+        assert_eq!(totals(StatsPeriod::AllTime), day(123.75, 31));
+    }
+
+    #[test]
+    fn summary_filters_items_quests_and_heart_rate_by_period() {
+        let items = vec![
+            item("aid", local(today(), 0, 0)),
+            item("Aid", local(days_ago(1), 23, 59)),
+            item("Junk", local(days_ago(6), 0, 0)),
+            item("Junk", local(days_ago(7), 23, 59)),
+        ];
+        let mut quests = QuestLog::default();
+        for finished in [local(today(), 8, 0), local(days_ago(7), 8, 0)] {
+            let mut quest = Quest::new("Supply Run".into(), String::new(), Vec::new());
+            quest.state = QuestState::Completed;
+            quest.finished_at = Some(finished);
+            quests.quests.push(quest);
+        }
+        let mut abandoned = Quest::new("Lost Cause".into(), String::new(), Vec::new());
+        abandoned.finished_at = Some(local(today(), 9, 0));
+        quests.quests.push(abandoned);
+        let reading = |bpm, at| PulseReading { bpm, spo2: None, at };
+        let hr = HrHistory::new(
+            vec![
+                reading(70, local(today(), 7, 0)),
+                reading(90, local(today(), 9, 0)),
+                reading(150, local(days_ago(1), 18, 0)),
+                reading(50, local(days_ago(3), 3, 0)),
+            ],
+            local(today(), 12, 0),
+        );
+        let count = |summary: &StatsSummary, category| {
+            summary.items_by_category.iter().find(|(name, _)| *name == category).unwrap().1
+        };
+        let summary = |period| summarize_stats_on(&ActivityLog::default(), &items, &quests, &hr, period, today());
+
+        let today = summary(StatsPeriod::Today);
+        assert_eq!((count(&today, "Aid"), count(&today, "Junk")), (1, 0));
+        assert_eq!(today.quests_completed, 1);
+        assert_eq!((today.hr_high, today.hr_low), (Some(90), Some(70)));
+
+        let week = summary(StatsPeriod::Week);
+        assert_eq!((count(&week, "Aid"), count(&week, "Junk")), (2, 1));
+        assert_eq!(week.quests_completed, 1);
+        assert_eq!((week.hr_high, week.hr_low), (Some(150), Some(50)));
+
+        let all = summary(StatsPeriod::AllTime);
+        assert_eq!((count(&all, "Aid"), count(&all, "Junk")), (2, 2));
+        assert_eq!(all.quests_completed, 2);
+        assert_eq!((all.hr_high, all.hr_low), (Some(150), Some(50)));
+    }
+}
@@ -0,0 +1,242 @@
+use chrono::prelude::*;
+use chrono_tz::Tz;
+use ratatui::layout::Alignment;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Borders, Paragraph};
+
+use crate::battery::{BatteryLevel, BatteryStatus};
+use crate::pulse::PulseReading;
+use crate::settings::TimeFormat;
+use crate::theme::Theme;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FixMode {
+    NoFix,
+    Fix2D,
+    Fix3D,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GpsStatus {
+    pub fix: FixMode,
+    pub satellites: u8,
+}
+
+pub struct StatusBar {
+    pub clock: DateTime<Utc>,
+    pub timezone: Option<Tz>,
+    pub gps: Option<GpsStatus>,
+    pub battery: Option<BatteryStatus>,
+    pub last_pulse: Option<PulseReading>,
+    pub time_format: TimeFormat,
+    pub quest: Option<String>,
+    pub nav: Option<String>,
+}
+
+impl StatusBar {
+    pub fn new(timezone: Option<Tz>, gps: Option<GpsStatus>, time_format: TimeFormat) -> Self {
+        StatusBar {
+            clock: Utc::now(),
+            timezone,
+            gps,
+            battery: None,
+            last_pulse: None,
+            time_format,
+            quest: None,
+            nav: None,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.clock = Utc::now();
+    }
+
+    pub fn local_time(&self, pattern: &str) -> String {
+        match self.timezone {
+            Some(tz) => self.clock.with_timezone(&tz).format(pattern).to_string(),
+            None => self.clock.with_timezone(&Local).format(pattern).to_string(),
+        }
+    }
+}
+
+// Looks up the IANA zone for a [lat, lon] pair, falling back to None over the
+// ocean or when the offline dataset has no polygon for the point.
+pub fn lookup_timezone(coordinates: [f64; 2]) -> Option<Tz> {
+    spatialtime::osm::lookup(coordinates[1], coordinates[0]).ok()?.tzid.parse().ok()
+}
+
+pub fn render_status_bar<'a>(status: &StatusBar, theme: &Theme) -> Paragraph<'a> {
+    let divider = || Span::styled(" | ", Style::default().fg(theme.dim));
+    let text = Style::default().fg(theme.fg);
+
+    let time = status.local_time(status.time_format.clock_pattern());
+    let date = status.local_time("%a %d %b %Y");
+
+    let gps = match status.gps {
+        Some(GpsStatus { fix: FixMode::Fix3D, satellites }) => format!("GPS 3D {} SAT", satellites),
+        Some(GpsStatus { fix: FixMode::Fix2D, satellites }) => format!("GPS 2D {} SAT", satellites),
+        Some(GpsStatus { fix: FixMode::NoFix, satellites }) => format!("NO FIX {} SAT", satellites),
+        None => "NO GPS".to_string(),
+    };
+
+    let (battery, battery_style) = match status.battery {
+        Some(b) if b.level == BatteryLevel::Critical => (b.summary(), Style::default().fg(theme.background).bg(theme.warning)),
+        Some(b) if b.level == BatteryLevel::Low => (
+            format!("LOW {}", b.summary()),
+            Style::default().fg(theme.warning).add_modifier(Modifier::BOLD),
+        ),
+        Some(b) => (b.summary(), text),
+        None => ("BAT --".to_string(), text),
+    };
+
+    let pulse = match status.last_pulse {
+        Some(reading) => format!("{} BPM", reading.bpm),
+        None => "-- BPM".to_string(),
+    };
+
+    Paragraph::new(Line::from(vec![
+        Span::styled(time, text.add_modifier(Modifier::BOLD)),
+        divider(),
+        Span::styled(date.to_uppercase(), text),
+        divider(),
+        Span::styled(gps, text),
+        divider(),
+        Span::styled(battery, battery_style),
+        divider(),
+        Span::styled(pulse, text),
+    ]))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().fg(theme.fg))
+            .title("COPYRIGHT 2075 ROBCO(R)")
+            .title(
+                Line::from(status.quest.as_ref().map(|q| format!(" QUEST: {} ", q)).unwrap_or_default())
+                    .right_aligned(),
+            )
+            .title_bottom(
+                Line::from(status.nav.as_ref().map(|n| format!(" {} ", n)).unwrap_or_default())
+                    .right_aligned(),
+            )
+            .border_type(BorderType::Plain),
+    )
+}
+
+pub fn render_idle_clock<'a>(status: &StatusBar, theme: &Theme, height: u16) -> Paragraph<'a> {
+    let mut lines = vec![Line::from(""); (height as usize).saturating_sub(2) / 2];
+    lines.push(Line::from(Span::styled(
+        status.local_time(status.time_format.clock_pattern()),
+        Style::default().fg(theme.dim).add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(Span::styled(
+        status.local_time("%a %d %b").to_uppercase(),
+        Style::default().fg(theme.dim),
+    )));
+    Paragraph::new(lines).alignment(Alignment::Center)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::style::Color;
+    use ratatui::widgets::Widget;
+    use std::time::Duration;
+
+    use crate::settings::Units;
+    use crate::theme::ColorDepth;
+    use crate::waypoints::NavTarget;
+
+    fn theme() -> Theme {
+        Theme::new([26, 255, 128], ColorDepth::TrueColor)
+    }
+
+    fn status() -> StatusBar {
+        let mut status = StatusBar::new(Some(chrono_tz::UTC), None, TimeFormat::H24);
+        status.clock = Utc.with_ymd_and_hms(2026, 10, 19, 7, 5, 9).unwrap();
+        status
+    }
+
+    fn battery(percent: u8, runtime_m: Option<u64>, level: BatteryLevel) -> BatteryStatus {
+        BatteryStatus {
+            percent: Some(percent),
+            voltage: None,
+            low_battery_pin: false,
+            runtime: runtime_m.map(|m| Duration::from_secs(m * 60)),
+            level,
+        }
+    }
+
+    fn draw(status: &StatusBar) -> Buffer {
+        let area = Rect::new(0, 0, 110, 3);
+        let mut buffer = Buffer::empty(area);
+        render_status_bar(status, &theme()).render(area, &mut buffer);
+        buffer
+    }
+
+    fn row(buffer: &Buffer, y: u16) -> String {
+        (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect()
+    }
+
+    // Style of the first cell of `text` on the middle row.
+    fn style_of(buffer: &Buffer, text: &str) -> (Color, Color) {
+        let line = row(buffer, 1);
+        let column = line[..line.find(text).unwrap()].chars().count() as u16;
+        let cell = &buffer[(column, 1)];
+        (cell.fg, cell.bg)
+    }
+
+    #[test]
+    fn gps_shows_fix_and_satellites() {
+        let cases = [
+            (None, "NO GPS"),
+            (Some(GpsStatus { fix: FixMode::NoFix, satellites: 2 }), "NO FIX 2 SAT"),
+            (Some(GpsStatus { fix: FixMode::Fix2D, satellites: 4 }), "GPS 2D 4 SAT"),
+            (Some(GpsStatus { fix: FixMode::Fix3D, satellites: 11 }), "GPS 3D 11 SAT"),
+        ];
+        for (gps, text) in cases {
+            let status = StatusBar { gps, ..status() };
+            let line = row(&draw(&status), 1);
+            assert!(line.contains(&format!("07:05:09 | MON 19 OCT 2026 | {} |", text)), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn battery_warns_as_it_runs_down() {
+        let theme = theme();
+        let cases = [
+            (None, "BAT --", (theme.fg, Color::Reset)),
+            (Some(battery(80, Some(125), BatteryLevel::Ok)), "BAT 80% ~2h05m", (theme.fg, Color::Reset)),
+            (Some(battery(12, None, BatteryLevel::Low)), "LOW BAT 12%", (theme.warning, Color::Reset)),
+            (Some(battery(3, Some(4), BatteryLevel::Critical)), "BAT 3% ~0h04m", (theme.background, theme.warning)),
+        ];
+        for (battery, text, colors) in cases {
+            let status = StatusBar { battery, ..status() };
+            let buffer = draw(&status);
+            assert!(row(&buffer, 1).contains(&format!("| {} | -- BPM", text)), "{:?}", row(&buffer, 1));
+            assert_eq!(style_of(&buffer, text), colors, "{}", text);
+        }
+    }
+
+    #[test]
+    fn nav_and_quest_ride_on_the_border() {
+        let target = NavTarget { name: "DEPOT".into(), position: [51.51, -0.1], waypoint: None };
+        let mut status = status();
+        status.quest = Some("Supply Run 1/3".into());
+        status.nav = Some(target.guidance(Some([51.50, -0.1]), Units::Metric));
+        let buffer = draw(&status);
+        assert!(row(&buffer, 0).trim_end().ends_with(" QUEST: Supply Run 1/3 ┐"), "{:?}", row(&buffer, 0));
+        assert!(row(&buffer, 2).trim_end().ends_with(" ↑ 1.1 km DEPOT ┘"), "{:?}", row(&buffer, 2));
+
+        status.nav = Some(target.guidance(None, Units::Imperial));
+        assert!(row(&draw(&status), 2).contains(" NAV DEPOT -- ┘"));
+        status.nav = Some(target.guidance(Some([51.51, -0.1]), Units::Imperial));
+        assert!(row(&draw(&status), 2).contains(" AT DEPOT ┘"));
+        status.nav = Some(target.guidance(Some([51.505, -0.1]), Units::Imperial));
+        assert!(row(&draw(&status), 2).contains(" ↑ 1824 ft DEPOT ┘"), "{:?}", row(&draw(&status), 2));
+    }
+}
@@ -0,0 +1,11 @@
+use std::fs;
+use std::path::PathBuf;
+
+// A fresh, empty directory for one test. Names are shared by every test in
+// the binary, so prefix them with the module.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pyp-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
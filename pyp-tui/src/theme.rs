@@ -0,0 +1,280 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use crate::settings::Settings;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemePreset {
+    Green,
+    Amber,
+    Blue,
+    White,
+    Custom,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 5] = [
+        ThemePreset::Green,
+        ThemePreset::Amber,
+        ThemePreset::Blue,
+        ThemePreset::White,
+        ThemePreset::Custom,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemePreset::Green => "Green",
+            ThemePreset::Amber => "Amber",
+            ThemePreset::Blue => "Blue",
+            ThemePreset::White => "White",
+            ThemePreset::Custom => "Custom",
+        }
+    }
+
+    // Phosphor colors of the classic monochrome tubes.
+    pub fn rgb(&self, custom: [u8; 3]) -> [u8; 3] {
+        match self {
+            ThemePreset::Green => [26, 255, 128],
+            ThemePreset::Amber => [255, 182, 0],
+            ThemePreset::Blue => [46, 207, 255],
+            ThemePreset::White => [230, 236, 240],
+            ThemePreset::Custom => custom,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorDepth {
+    Auto,
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub const ALL: [ColorDepth; 4] = [ColorDepth::Auto, ColorDepth::TrueColor, ColorDepth::Ansi256, ColorDepth::Ansi16];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorDepth::Auto => "Auto",
+            ColorDepth::TrueColor => "True Color",
+            ColorDepth::Ansi256 => "256 Colors",
+            ColorDepth::Ansi16 => "16 Colors",
+        }
+    }
+
+    // The Linux framebuffer console reports TERM=linux and only has the 16
+    // base colors, so anything richer has to be asked for explicitly.
+    pub fn resolve(&self) -> ColorDepth {
+        match self {
+            ColorDepth::Auto => depth_for_terminal(
+                &env::var("COLORTERM").unwrap_or_default(),
+                &env::var("TERM").unwrap_or_default(),
+            ),
+            depth => *depth,
+        }
+    }
+}
+
+fn depth_for_terminal(colorterm: &str, term: &str) -> ColorDepth {
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    }
+}
+
+pub fn parse_hex_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn format_hex_color(rgb: [u8; 3]) -> String {
+    format!("{:02X}{:02X}{:02X}", rgb[0], rgb[1], rgb[2])
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub fg: Color,
+    pub dim: Color,
+    pub highlight: Color,
+    pub warning: Color,
+    pub border: Color,
+    // Dark text drawn over a lit role: selections, bar values, flashing alerts.
+    pub background: Color,
+}
+
+impl Theme {
+    // Every role is a shade of the one phosphor color except warnings, which
+    // stay red so alarms read as alarms on any tube.
+    pub fn new(rgb: [u8; 3], depth: ColorDepth) -> Theme {
+        let depth = depth.resolve();
+        Theme {
+            fg: to_color(scale(rgb, 0.85), depth),
+            dim: to_color(scale(rgb, 0.45), depth),
+            highlight: to_color(lighten(rgb, 0.2), depth),
+            warning: to_color([255, 64, 48], depth),
+            border: to_color(scale(rgb, 0.6), depth),
+            background: to_color(scale(rgb, 0.08), depth),
+        }
+    }
+
+    pub fn from_settings(settings: &Settings) -> Theme {
+        Theme::new(settings.theme.rgb(settings.custom_color), settings.color_depth)
+    }
+
+    pub fn selected(&self) -> Style {
+        Style::default()
+            .bg(self.highlight)
+            .fg(self.background)
+            .add_modifier(Modifier::BOLD)
+    }
+}
+
+fn scale(rgb: [u8; 3], factor: f32) -> [u8; 3] {
+    rgb.map(|c| (c as f32 * factor).round() as u8)
+}
+
+fn lighten(rgb: [u8; 3], amount: f32) -> [u8; 3] {
+    rgb.map(|c| (c as f32 + (255.0 - c as f32) * amount).round() as u8)
+}
+
+fn to_color(rgb: [u8; 3], depth: ColorDepth) -> Color {
+    match depth {
+        ColorDepth::Ansi256 => Color::Indexed(to_ansi256(rgb)),
+        ColorDepth::Ansi16 => to_ansi16(rgb),
+        _ => Color::Rgb(rgb[0], rgb[1], rgb[2]),
+    }
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (*x as i32 - *y as i32).pow(2) as u32)
+        .sum()
+}
+
+// Picks the closer of the 6x6x6 color cube and the 24-step gray ramp.
+fn to_ansi256(rgb: [u8; 3]) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let nearest_level = |c: u8| {
+        (0..6)
+            .min_by_key(|i| (LEVELS[*i] as i32 - c as i32).abs())
+            .unwrap_or(0)
+    };
+    let [r, g, b] = rgb.map(nearest_level);
+    let cube = [LEVELS[r], LEVELS[g], LEVELS[b]];
+
+    let average = rgb.iter().map(|c| *c as u32).sum::<u32>() / 3;
+    let gray_index = ((average.saturating_sub(8)) / 10).min(23) as u8;
+    let gray_level = 8 + gray_index * 10;
+
+    if distance(rgb, [gray_level; 3]) < distance(rgb, cube) {
+        232 + gray_index
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
+    }
+}
+
+fn to_ansi16(rgb: [u8; 3]) -> Color {
+    const PALETTE: [(Color, [u8; 3]); 16] = [
+        (Color::Black, [0, 0, 0]),
+        (Color::Red, [170, 0, 0]),
+        (Color::Green, [0, 170, 0]),
+        (Color::Yellow, [170, 85, 0]),
+        (Color::Blue, [0, 0, 170]),
+        (Color::Magenta, [170, 0, 170]),
+        (Color::Cyan, [0, 170, 170]),
+        (Color::Gray, [170, 170, 170]),
+        (Color::DarkGray, [85, 85, 85]),
+        (Color::LightRed, [255, 85, 85]),
+        (Color::LightGreen, [85, 255, 85]),
+        (Color::LightYellow, [255, 255, 85]),
+        (Color::LightBlue, [85, 85, 255]),
+        (Color::LightMagenta, [255, 85, 255]),
+        (Color::LightCyan, [85, 255, 255]),
+        (Color::White, [255, 255, 255]),
+    ];
+    PALETTE
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::White)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_depth_follows_the_terminal() {
+        let cases = [
+            ("", "linux", ColorDepth::Ansi16),
+            ("", "", ColorDepth::Ansi16),
+            ("", "xterm", ColorDepth::Ansi16),
+            ("", "xterm-256color", ColorDepth::Ansi256),
+            ("", "screen-256color", ColorDepth::Ansi256),
+            ("truecolor", "xterm-256color", ColorDepth::TrueColor),
+            ("24bit", "linux", ColorDepth::TrueColor),
+            ("yes", "linux", ColorDepth::Ansi16),
+        ];
+        for (colorterm, term, depth) in cases {
+            assert_eq!(depth_for_terminal(colorterm, term), depth, "COLORTERM={} TERM={}", colorterm, term);
+        }
+        for depth in [ColorDepth::TrueColor, ColorDepth::Ansi256, ColorDepth::Ansi16] {
+            assert_eq!(depth.resolve(), depth);
+        }
+    }
+
+    #[test]
+    fn ansi256_picks_the_nearer_of_cube_and_gray_ramp() {
+        let cases = [
+            ([0, 0, 0], 16),
+            ([255, 255, 255], 231),
+            ([255, 0, 0], 196),
+            ([26, 255, 128], 48),
+            ([255, 182, 0], 214),
+            ([8, 8, 8], 232),
+            ([128, 128, 128], 244),
+            ([238, 238, 238], 255),
+        ];
+        for (rgb, index) in cases {
+            assert_eq!(to_ansi256(rgb), index, "{:?}", rgb);
+        }
+    }
+
+    #[test]
+    fn ansi16_picks_the_nearest_console_color() {
+        let cases = [
+            ([0, 0, 0], Color::Black),
+            ([26, 255, 128], Color::LightGreen),
+            ([22, 217, 109], Color::LightGreen),
+            ([12, 115, 58], Color::Green),
+            ([255, 182, 0], Color::LightYellow),
+            ([46, 207, 255], Color::LightCyan),
+            ([230, 236, 240], Color::White),
+            ([255, 64, 48], Color::LightRed),
+        ];
+        for (rgb, color) in cases {
+            assert_eq!(to_ansi16(rgb), color, "{:?}", rgb);
+        }
+    }
+
+    #[test]
+    fn every_role_fits_the_console_palette() {
+        for preset in ThemePreset::ALL {
+            let theme = Theme::new(preset.rgb([200, 40, 160]), ColorDepth::Ansi16);
+            let roles = [theme.fg, theme.dim, theme.highlight, theme.warning, theme.border, theme.background];
+            assert!(roles.iter().all(|c| !matches!(c, Color::Rgb(..) | Color::Indexed(_))), "{:?}", preset);
+            assert_eq!(theme.background, Color::Black, "{:?}", preset);
+            assert_ne!(theme.selected().fg, theme.selected().bg, "{:?}", preset);
+        }
+    }
+}
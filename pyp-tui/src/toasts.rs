@@ -0,0 +1,58 @@
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, BorderType, Borders, Paragraph};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::theme::Theme;
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+// Short notices over whatever screen is up that go away on their own,
+// unlike alarms, which wait to be acknowledged. One at a time, in order.
+#[derive(Default)]
+pub struct Toasts {
+    queue: VecDeque<String>,
+    shown_since: Option<Instant>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: String) {
+        self.queue.push_back(text);
+    }
+
+    // The toast to show now, moving on to the next once it's had its time.
+    pub fn current(&mut self) -> Option<&str> {
+        if self.shown_since.is_some_and(|since| since.elapsed() >= TOAST_DURATION) {
+            self.queue.pop_front();
+            self.shown_since = None;
+        }
+        if !self.queue.is_empty() && self.shown_since.is_none() {
+            self.shown_since = Some(Instant::now());
+        }
+        self.queue.front().map(String::as_str)
+    }
+}
+
+// A strip across the top of the content, just under the tabs.
+pub fn toast_area(area: Rect) -> Rect {
+    let width = area.width.saturating_sub(8).min(48);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + 3.min(area.height),
+        width,
+        height: 3.min(area.height.saturating_sub(3)),
+    }
+}
+
+pub fn render_toast<'a>(text: &str, theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(text.to_string())
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .border_type(BorderType::Double),
+        )
+}
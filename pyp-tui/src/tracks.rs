@@ -0,0 +1,598 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::coords::distance_m;
+use crate::position::PositionFix;
+use crate::waypoints::NavTarget;
+
+const TRACKS_DIR: &str = "./data/tracks";
+const IMPORT_DIR: &str = "./data";
+const IMPORTED_PATH: &str = "./data/gpx_imports.json";
+// Barometer-less GPS altitude wanders by a few meters even standing still.
+const ELEVATION_NOISE_M: f64 = 3.0;
+// A breadcrumb counts as reached inside this radius.
+const BREADCRUMB_RADIUS_M: f64 = 25.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TrackPoint {
+    pub position: [f64; 2],
+    pub elevation: Option<f64>,
+    pub speed: Option<f64>,
+    // Route points from a GPX file have no times.
+    pub at: Option<DateTime<Utc>>,
+}
+
+// First line of a track file; every line after it is one TrackPoint, so a
+// recording can be appended to as it goes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct TrackHeader {
+    name: String,
+    created_at: DateTime<Utc>,
+    // GPX file the track was imported from, so a rescan doesn't duplicate it.
+    source: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackStats {
+    pub distance_m: f64,
+    pub duration: Option<chrono::Duration>,
+    pub elevation_gain_m: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub source: Option<String>,
+    pub points: Vec<TrackPoint>,
+    pub path: PathBuf,
+    pub shown: bool,
+}
+
+impl Track {
+    fn header(&self) -> TrackHeader {
+        TrackHeader {
+            name: self.name.clone(),
+            created_at: self.created_at,
+            source: self.source.clone(),
+        }
+    }
+
+    fn write(&self) -> io::Result<()> {
+        let mut file = File::create(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&self.header())?)?;
+        for point in &self.points {
+            writeln!(file, "{}", serde_json::to_string(point)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> TrackStats {
+        let distance_m = self
+            .points
+            .windows(2)
+            .map(|pair| distance_m(pair[0].position, pair[1].position))
+            .sum();
+        let times: Vec<DateTime<Utc>> = self.points.iter().filter_map(|p| p.at).collect();
+        let duration = match (times.first(), times.last()) {
+            (Some(&first), Some(&last)) if times.len() > 1 => Some(last - first),
+            _ => None,
+        };
+
+        // Only count a climb once it clears the noise floor above the last
+        // low point, otherwise jitter on flat ground adds up to a mountain.
+        let mut elevation_gain_m = 0.0;
+        let mut reference: Option<f64> = None;
+        for elevation in self.points.iter().filter_map(|p| p.elevation) {
+            match reference {
+                Some(low) if elevation - low >= ELEVATION_NOISE_M => {
+                    elevation_gain_m += elevation - low;
+                    reference = Some(elevation);
+                }
+                Some(low) if elevation < low => reference = Some(elevation),
+                Some(_) => {}
+                None => reference = Some(elevation),
+            }
+        }
+
+        TrackStats { distance_m, duration, elevation_gain_m }
+    }
+}
+
+pub struct TrackRecorder {
+    pub track: Track,
+}
+
+impl TrackRecorder {
+    pub fn start() -> io::Result<Self> {
+        fs::create_dir_all(TRACKS_DIR)?;
+        let now = Utc::now();
+        let local = now.with_timezone(&Local);
+        let track = Track {
+            name: local.format("TRACK %d %b %H%M").to_string().to_uppercase(),
+            created_at: now,
+            source: None,
+            points: Vec::new(),
+            path: Path::new(TRACKS_DIR).join(format!("{}.jsonl", local.format("%Y%m%d-%H%M%S"))),
+            shown: true,
+        };
+        track.write()?;
+        Ok(TrackRecorder { track })
+    }
+
+    // Logs the fix if at least `interval_s` has passed since the last point.
+    // A replayed log jumps back in time when it loops, which also logs.
+    pub fn record(&mut self, fix: &PositionFix, interval_s: u32) -> io::Result<bool> {
+        let Some(position) = fix.coordinates() else {
+            return Ok(false);
+        };
+        let due = match self.track.points.last().and_then(|p| p.at) {
+            Some(last) => fix.at < last || (fix.at - last).num_seconds() >= interval_s as i64,
+            None => true,
+        };
+        if !due {
+            return Ok(false);
+        }
+
+        let point = TrackPoint {
+            position,
+            elevation: fix.altitude,
+            speed: fix.speed,
+            at: Some(fix.at),
+        };
+        let mut file = OpenOptions::new().append(true).open(&self.track.path)?;
+        writeln!(file, "{}", serde_json::to_string(&point)?)?;
+        self.track.points.push(point);
+        Ok(true)
+    }
+
+    // A recording that never got a fix isn't worth keeping around.
+    pub fn stop(self) -> io::Result<Option<Track>> {
+        if self.track.points.is_empty() {
+            fs::remove_file(&self.track.path)?;
+            return Ok(None);
+        }
+        Ok(Some(self.track))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrackAction {
+    Show,
+    Hide,
+    Follow,
+    StopFollowing,
+    ExportGpx,
+    Delete,
+}
+
+impl TrackAction {
+    pub fn available(shown: bool, following: bool) -> Vec<TrackAction> {
+        vec![
+            if shown { TrackAction::Hide } else { TrackAction::Show },
+            if following { TrackAction::StopFollowing } else { TrackAction::Follow },
+            TrackAction::ExportGpx,
+            TrackAction::Delete,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackAction::Show => "Show On Map",
+            TrackAction::Hide => "Hide From Map",
+            TrackAction::Follow => "Follow Breadcrumbs",
+            TrackAction::StopFollowing => "Stop Following",
+            TrackAction::ExportGpx => "Export GPX",
+            TrackAction::Delete => "Delete",
+        }
+    }
+}
+
+// Walks a track one point at a time, handing the next unreached point to the
+// navigation arrow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breadcrumbs {
+    pub path: PathBuf,
+    name: String,
+    points: Vec<[f64; 2]>,
+    next: usize,
+}
+
+impl Breadcrumbs {
+    // Joins the trail at the closest point rather than sending you back to
+    // the start.
+    pub fn new(track: &Track, here: Option<[f64; 2]>) -> Self {
+        let points: Vec<[f64; 2]> = track.points.iter().map(|p| p.position).collect();
+        let next = here
+            .and_then(|here| {
+                (0..points.len()).min_by(|&a, &b| distance_m(here, points[a]).total_cmp(&distance_m(here, points[b])))
+            })
+            .unwrap_or(0);
+        Breadcrumbs { path: track.path.clone(), name: track.name.clone(), points, next }
+    }
+
+    pub fn advance(&mut self, here: [f64; 2]) {
+        while self.next < self.points.len() && distance_m(here, self.points[self.next]) < BREADCRUMB_RADIUS_M {
+            self.next += 1;
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.points.len()
+    }
+
+    pub fn target(&self) -> Option<NavTarget> {
+        let position = *self.points.get(self.next)?;
+        Some(NavTarget {
+            name: format!("{} {}/{}", self.name, self.next + 1, self.points.len()),
+            position,
+            waypoint: None,
+        })
+    }
+}
+
+fn read_track(path: &Path) -> io::Result<Track> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header: TrackHeader = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData, "empty track file")),
+    };
+    let mut points = Vec::new();
+    for line in lines {
+        // A recording cut off by a dead battery can end mid-line.
+        match serde_json::from_str(&line?) {
+            Ok(point) => points.push(point),
+            Err(_) => break,
+        }
+    }
+    Ok(Track {
+        name: header.name,
+        created_at: header.created_at,
+        source: header.source,
+        points,
+        path: path.to_path_buf(),
+        shown: false,
+    })
+}
+
+pub fn read_tracks() -> io::Result<Vec<Track>> {
+    let entries = match fs::read_dir(TRACKS_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut tracks = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "jsonl") {
+            if let Ok(track) = read_track(&path) {
+                tracks.push(track);
+            }
+        }
+    }
+    tracks.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+    Ok(tracks)
+}
+
+pub fn delete_track(track: &Track) -> io::Result<()> {
+    fs::remove_file(&track.path)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn to_gpx(track: &Track) -> String {
+    let mut gpx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str("<gpx version=\"1.1\" creator=\"Pyp-Boy\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+    gpx.push_str(&format!(
+        "  <metadata><time>{}</time></metadata>\n",
+        track.created_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+    ));
+    gpx.push_str(&format!("  <trk>\n    <name>{}</name>\n    <trkseg>\n", escape_xml(&track.name)));
+    // GPX 1.1 dropped the per-point speed element, so speed stays in the
+    // track file only.
+    for point in &track.points {
+        gpx.push_str(&format!("      <trkpt lat=\"{:.7}\" lon=\"{:.7}\">", point.position[0], point.position[1]));
+        if let Some(elevation) = point.elevation {
+            gpx.push_str(&format!("<ele>{:.1}</ele>", elevation));
+        }
+        if let Some(at) = point.at {
+            gpx.push_str(&format!("<time>{}</time>", at.to_rfc3339_opts(SecondsFormat::Secs, true)));
+        }
+        gpx.push_str("</trkpt>\n");
+    }
+    gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    gpx
+}
+
+// Writes the track next to its track file and returns where it went.
+pub fn export_gpx(track: &Track) -> io::Result<PathBuf> {
+    let path = track.path.with_extension("gpx");
+    fs::write(&path, to_gpx(track))?;
+    Ok(path)
+}
+
+fn gpx_point(node: roxmltree::Node) -> Option<TrackPoint> {
+    let lat: f64 = node.attribute("lat")?.trim().parse().ok()?;
+    let lon: f64 = node.attribute("lon")?.trim().parse().ok()?;
+    let child = |name: &str| {
+        node.children()
+            .find(|c| c.tag_name().name() == name)
+            .and_then(|c| c.text())
+            .map(str::trim)
+    };
+    Some(TrackPoint {
+        position: [lat, lon],
+        elevation: child("ele").and_then(|e| e.parse().ok()),
+        speed: None,
+        at: child("time").and_then(|t| t.parse().ok()),
+    })
+}
+
+// A <trk> or <rte> from a GPX file: its name, if any, and its points.
+pub type GpxLine = (Option<String>, Vec<TrackPoint>);
+
+// Every track and route in a GPX document. Track segments are joined into
+// one line.
+pub fn parse_gpx(content: &str) -> Result<Vec<GpxLine>, roxmltree::Error> {
+    let document = roxmltree::Document::parse(content)?;
+    let name_of = |node: roxmltree::Node| {
+        node.children()
+            .find(|c| c.tag_name().name() == "name")
+            .and_then(|c| c.text())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    };
+    let mut lines = Vec::new();
+    for node in document.root_element().children().filter(|n| n.is_element()) {
+        let points: Vec<TrackPoint> = match node.tag_name().name() {
+            "trk" => node
+                .descendants()
+                .filter(|n| n.tag_name().name() == "trkpt")
+                .filter_map(gpx_point)
+                .collect(),
+            "rte" => node
+                .children()
+                .filter(|n| n.tag_name().name() == "rtept")
+                .filter_map(gpx_point)
+                .collect(),
+            _ => continue,
+        };
+        if !points.is_empty() {
+            lines.push((name_of(node), points));
+        }
+    }
+    Ok(lines)
+}
+
+// Picks up any .gpx files dropped into the data directory that haven't been
+// imported yet. Returns how many tracks were added and how many files could
+// not be read as GPX.
+pub fn import_gpx(tracks: &mut Vec<Track>) -> io::Result<(usize, usize)> {
+    let mut imported = match fs::read_to_string(IMPORTED_PATH) {
+        Ok(content) => serde_json::from_str(&content)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeSet::new(),
+        Err(e) => return Err(e),
+    };
+    let before = imported.clone();
+    let counts = import_gpx_from(Path::new(IMPORT_DIR), Path::new(TRACKS_DIR), tracks, &mut imported)?;
+    if imported != before {
+        fs::write(IMPORTED_PATH, serde_json::to_vec(&imported)?)?;
+    }
+    Ok(counts)
+}
+
+// `imported` remembers every file name ever taken in, so a deleted import
+// stays deleted. Tracks imported before that list existed count too.
+fn import_gpx_from(
+    dir: &Path,
+    tracks_dir: &Path,
+    tracks: &mut Vec<Track>,
+    imported: &mut BTreeSet<String>,
+) -> io::Result<(usize, usize)> {
+    imported.extend(tracks.iter().filter_map(|t| t.source.clone()));
+    let (mut count, mut skipped) = (0, 0);
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_gpx = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gpx"));
+        let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        if !is_gpx || imported.contains(&file_name) {
+            continue;
+        }
+
+        // The XML parser only takes UTF-8, so a Latin-1 export is as
+        // unreadable as a broken one.
+        let Ok(content) = String::from_utf8(fs::read(&path)?) else {
+            skipped += 1;
+            continue;
+        };
+        let Ok(lines) = parse_gpx(&content) else {
+            skipped += 1;
+            continue;
+        };
+        fs::create_dir_all(tracks_dir)?;
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        for (index, (name, points)) in lines.into_iter().enumerate() {
+            let track = Track {
+                name: name.unwrap_or_else(|| stem.to_uppercase()),
+                created_at: points.iter().find_map(|p| p.at).unwrap_or_else(Utc::now),
+                source: Some(file_name.clone()),
+                points,
+                path: tracks_dir.join(format!("import-{}-{}.jsonl", stem, index)),
+                shown: true,
+            };
+            track.write()?;
+            tracks.push(track);
+            count += 1;
+        }
+        imported.insert(file_name);
+    }
+    tracks.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+    Ok((count, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::scratch_dir;
+    use crate::status_bar::FixMode;
+
+    fn at(second: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 9, 23, 3, 0, 0).unwrap() + chrono::Duration::seconds(second)
+    }
+
+    // `meters` east of a fixed start, roughly, at the given height and time.
+    fn point(meters: f64, elevation: Option<f64>, second: Option<i64>) -> TrackPoint {
+        TrackPoint {
+            position: [35.8325, -115.4345 + meters / 90_250.0],
+            elevation,
+            speed: None,
+            at: second.map(at),
+        }
+    }
+
+    fn track(points: Vec<TrackPoint>, path: PathBuf) -> Track {
+        Track {
+            name: "WALK & <BACK>".into(),
+            created_at: at(0),
+            source: None,
+            points,
+            path,
+            shown: true,
+        }
+    }
+
+    #[test]
+    fn stats_add_up_distance_time_and_climb() {
+        let elevations = [1000.0, 1002.0, 999.0, 1001.5, 1003.0, 1001.0, 1010.0];
+        let points: Vec<TrackPoint> = elevations
+            .iter()
+            .enumerate()
+            .map(|(i, &e)| point(i as f64 * 100.0, Some(e), Some(i as i64 * 60)))
+            .collect();
+        let stats = track(points, PathBuf::new()).stats();
+
+        assert!((stats.distance_m - 600.0).abs() < 2.0, "{}", stats.distance_m);
+        assert_eq!(stats.duration, Some(chrono::Duration::minutes(6)));
+        // The 2 m wobbles don't count; 999 -> 1003 and 1001 -> 1010 do.
+        assert!((stats.elevation_gain_m - 13.0).abs() < 1e-9, "{}", stats.elevation_gain_m);
+    }
+
+    #[test]
+    fn stats_without_times_or_heights() {
+        let stats = track(vec![point(0.0, None, None), point(50.0, None, None)], PathBuf::new()).stats();
+        assert_eq!(stats.duration, None);
+        assert_eq!(stats.elevation_gain_m, 0.0);
+        assert_eq!(track(Vec::new(), PathBuf::new()).stats().distance_m, 0.0);
+    }
+
+    #[test]
+    fn recorder_keeps_one_point_per_interval() {
+        let dir = scratch_dir("tracks-recorder");
+        let path = dir.join("walk.jsonl");
+        let mut recorder = TrackRecorder { track: track(Vec::new(), path.clone()) };
+        recorder.track.write().unwrap();
+
+        let fix = |second: i64, mode: FixMode| PositionFix {
+            mode,
+            lat: 35.8325,
+            lon: -115.4345,
+            at: at(second),
+            ..PositionFix::default()
+        };
+        let fixes = [
+            (0, FixMode::Fix3D),
+            (3, FixMode::Fix3D),
+            (5, FixMode::Fix3D),
+            (12, FixMode::NoFix),
+            (12, FixMode::Fix3D),
+        ];
+        let logged: Vec<bool> = fixes
+            .into_iter()
+            .map(|(second, mode)| recorder.record(&fix(second, mode), 5).unwrap())
+            .collect();
+        assert_eq!(logged, [true, false, true, false, true]);
+        // A replayed log looping back to its start logs straight away.
+        assert!(recorder.record(&fix(1, FixMode::Fix3D), 5).unwrap());
+
+        // What's on disk matches what's in memory.
+        assert_eq!(read_track(&path).unwrap().points, recorder.track.points);
+        assert_eq!(recorder.track.points.len(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn breadcrumbs_join_nearby_and_walk_on() {
+        let trail = track((0..5).map(|i| point(i as f64 * 100.0, None, None)).collect(), PathBuf::new());
+        let mut crumbs = Breadcrumbs::new(&trail, Some(point(210.0, None, None).position));
+        assert_eq!(crumbs.target().unwrap().position, trail.points[2].position);
+
+        // Too far to count as reached.
+        crumbs.advance(point(250.0, None, None).position);
+        assert_eq!(crumbs.target().unwrap().name, "WALK & <BACK> 3/5");
+
+        crumbs.advance(point(205.0, None, None).position);
+        assert_eq!(crumbs.target().unwrap().position, trail.points[3].position);
+        // Only the next point counts, even when a later one is closer.
+        crumbs.advance(point(395.0, None, None).position);
+        assert_eq!(crumbs.target().unwrap().position, trail.points[3].position);
+        crumbs.advance(point(310.0, None, None).position);
+        crumbs.advance(point(400.0, None, None).position);
+        assert!(crumbs.is_finished());
+        assert_eq!(crumbs.target(), None);
+    }
+
+    #[test]
+    fn gpx_round_trip() {
+        let points = vec![
+            point(0.0, Some(1112.2), Some(0)),
+            point(30.0, Some(1113.0), Some(20)),
+            point(60.0, None, None),
+        ];
+        let original = track(points, PathBuf::new());
+        let lines = parse_gpx(&to_gpx(&original)).unwrap();
+
+        assert_eq!(lines.len(), 1);
+        let (name, parsed) = &lines[0];
+        assert_eq!(name.as_deref(), Some("WALK & <BACK>"));
+        assert_eq!(parsed.len(), 3);
+        for (before, after) in original.points.iter().zip(parsed) {
+            assert!((before.position[0] - after.position[0]).abs() < 1e-7);
+            assert!((before.position[1] - after.position[1]).abs() < 1e-7);
+            assert_eq!(before.elevation, after.elevation);
+            assert_eq!(before.at, after.at);
+        }
+    }
+
+    #[test]
+    fn imports_skip_bad_files_and_remember_deletions() {
+        let dir = scratch_dir("tracks-import");
+        let tracks_dir = dir.join("tracks");
+        let walk = track(vec![point(0.0, None, None), point(10.0, None, None)], PathBuf::new());
+        fs::write(dir.join("walk.gpx"), to_gpx(&walk)).unwrap();
+        // An ISO-8859-1 export, and something that isn't GPX at all.
+        fs::write(dir.join("latin1.gpx"), b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><gpx><trk><name>Caf\xe9</name></trk></gpx>").unwrap();
+        fs::write(dir.join("broken.gpx"), "<gpx><trk>").unwrap();
+
+        let mut tracks = Vec::new();
+        let mut imported = BTreeSet::new();
+        assert_eq!(import_gpx_from(&dir, &tracks_dir, &mut tracks, &mut imported).unwrap(), (1, 2));
+        assert_eq!(tracks[0].name, "WALK & <BACK>");
+
+        delete_track(&tracks[0]).unwrap();
+        tracks.clear();
+        assert_eq!(import_gpx_from(&dir, &tracks_dir, &mut tracks, &mut imported).unwrap(), (0, 2));
+        assert!(tracks.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
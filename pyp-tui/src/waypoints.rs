@@ -0,0 +1,197 @@
+use chrono::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::coords::{arrow_for_bearing, bearing_deg, distance_m, format_distance};
+use crate::settings::Units;
+use crate::Error;
+
+const WAYPOINTS_PATH: &str = "./data/waypoints.json";
+// Inside this the receiver can't really tell you apart from the spot.
+const ARRIVAL_RADIUS_M: f64 = 15.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaypointIcon {
+    Camp,
+    Settlement,
+    Landmark,
+    Water,
+    Danger,
+    Cache,
+    Vault,
+    Other,
+}
+
+impl WaypointIcon {
+    pub const ALL: [WaypointIcon; 8] = [
+        WaypointIcon::Camp,
+        WaypointIcon::Settlement,
+        WaypointIcon::Landmark,
+        WaypointIcon::Water,
+        WaypointIcon::Danger,
+        WaypointIcon::Cache,
+        WaypointIcon::Vault,
+        WaypointIcon::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WaypointIcon::Camp => "Camp",
+            WaypointIcon::Settlement => "Settlement",
+            WaypointIcon::Landmark => "Landmark",
+            WaypointIcon::Water => "Water",
+            WaypointIcon::Danger => "Danger",
+            WaypointIcon::Cache => "Cache",
+            WaypointIcon::Vault => "Vault",
+            WaypointIcon::Other => "Other",
+        }
+    }
+
+    // One cell on the map, so it has to read at a glance.
+    pub fn glyph(&self) -> char {
+        match self {
+            WaypointIcon::Camp => '^',
+            WaypointIcon::Settlement => '#',
+            WaypointIcon::Landmark => '!',
+            WaypointIcon::Water => '~',
+            WaypointIcon::Danger => 'X',
+            WaypointIcon::Cache => '$',
+            WaypointIcon::Vault => 'V',
+            WaypointIcon::Other => '+',
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Waypoint {
+    pub id: usize,
+    pub name: String,
+    pub icon: WaypointIcon,
+    pub position: [f64; 2],
+    pub created_at: DateTime<Utc>,
+}
+
+impl Waypoint {
+    pub fn new(name: String, icon: WaypointIcon, position: [f64; 2]) -> Self {
+        Waypoint {
+            id: thread_rng().gen_range(0, 9999999),
+            name,
+            icon,
+            position,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+// What the arrow points at. Usually a saved waypoint, but anything with a
+// position can be navigated to without saving it first.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NavTarget {
+    pub name: String,
+    pub position: [f64; 2],
+    pub waypoint: Option<usize>,
+}
+
+impl NavTarget {
+    pub fn to_waypoint(waypoint: &Waypoint) -> Self {
+        NavTarget {
+            name: waypoint.name.clone(),
+            position: waypoint.position,
+            waypoint: Some(waypoint.id),
+        }
+    }
+
+    // Short arrow, range and name for the status bar.
+    pub fn guidance(&self, position: Option<[f64; 2]>, units: Units) -> String {
+        let Some(from) = position else {
+            return format!("NAV {} --", self.name);
+        };
+        let distance = distance_m(from, self.position);
+        if distance < ARRIVAL_RADIUS_M {
+            return format!("AT {}", self.name);
+        }
+        format!(
+            "{} {} {}",
+            arrow_for_bearing(bearing_deg(from, self.position)),
+            format_distance(distance, units),
+            self.name
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WaypointAction {
+    Navigate,
+    StopNavigating,
+    Rename,
+    ChangeIcon,
+    Delete,
+}
+
+impl WaypointAction {
+    pub fn available(is_target: bool) -> Vec<WaypointAction> {
+        vec![
+            if is_target { WaypointAction::StopNavigating } else { WaypointAction::Navigate },
+            WaypointAction::Rename,
+            WaypointAction::ChangeIcon,
+            WaypointAction::Delete,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WaypointAction::Navigate => "Navigate Here",
+            WaypointAction::StopNavigating => "Stop Navigating",
+            WaypointAction::Rename => "Rename",
+            WaypointAction::ChangeIcon => "Change Icon",
+            WaypointAction::Delete => "Delete",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct WaypointBook {
+    pub waypoints: Vec<Waypoint>,
+    pub target: Option<NavTarget>,
+}
+
+impl WaypointBook {
+    pub fn add(&mut self, waypoint: Waypoint) {
+        self.waypoints.insert(0, waypoint);
+    }
+
+    pub fn is_target(&self, waypoint: &Waypoint) -> bool {
+        self.target.as_ref().is_some_and(|t| t.waypoint == Some(waypoint.id))
+    }
+
+    pub fn rename(&mut self, index: usize, name: String) {
+        let waypoint = &mut self.waypoints[index];
+        waypoint.name = name;
+        if let Some(target) = self.target.as_mut().filter(|t| t.waypoint == Some(waypoint.id)) {
+            target.name = waypoint.name.clone();
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        let waypoint = self.waypoints.remove(index);
+        if self.target.as_ref().is_some_and(|t| t.waypoint == Some(waypoint.id)) {
+            self.target = None;
+        }
+    }
+}
+
+pub fn read_waypoints() -> Result<WaypointBook, Error> {
+    match fs::read_to_string(WAYPOINTS_PATH) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(WaypointBook::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_waypoints(book: &WaypointBook) -> Result<(), Error> {
+    fs::write(WAYPOINTS_PATH, &serde_json::to_vec(book)?)?;
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use chrono::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::files::write_atomic;
+use crate::render_tabs::Item;
+use crate::Error;
+
+const WORKSHOPS_PATH: &str = "./data/workshops.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Workshop {
+    pub id: usize,
+    pub name: String,
+    pub position: Option<[f64; 2]>,
+    pub notes: String,
+    pub stash: Vec<Item>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Workshop {
+    pub fn new(name: String, notes: String, position: Option<[f64; 2]>) -> Self {
+        Workshop {
+            id: thread_rng().gen_range(0, 9999999),
+            name,
+            position,
+            notes,
+            stash: Vec::new(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorkshopAction {
+    Deposit,
+    Withdraw,
+    EditNotes,
+    Rename,
+    UpdatePosition,
+    Delete,
+}
+
+impl WorkshopAction {
+    pub const ALL: [WorkshopAction; 6] = [
+        WorkshopAction::Deposit,
+        WorkshopAction::Withdraw,
+        WorkshopAction::EditNotes,
+        WorkshopAction::Rename,
+        WorkshopAction::UpdatePosition,
+        WorkshopAction::Delete,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkshopAction::Deposit => "Deposit Item",
+            WorkshopAction::Withdraw => "Withdraw Item",
+            WorkshopAction::EditNotes => "Edit Notes",
+            WorkshopAction::Rename => "Rename",
+            WorkshopAction::UpdatePosition => "Set Position Here",
+            WorkshopAction::Delete => "Delete",
+        }
+    }
+}
+
+// Moves `quantity` of `items[index]` into `to`, stacking onto an entry with the
+// same id so an item that goes back and forth stays one entry on both sides.
+pub fn transfer_item(from: &mut Vec<Item>, index: usize, quantity: u32, to: &mut Vec<Item>) {
+    let quantity = quantity.min(from[index].quantity);
+    if quantity == 0 {
+        return;
+    }
+
+    let mut moved = from[index].clone();
+    moved.quantity = quantity;
+    from[index].quantity -= quantity;
+    if from[index].quantity == 0 {
+        from.remove(index);
+    }
+
+    match to.iter_mut().find(|item| item.id == moved.id) {
+        Some(existing) => existing.quantity += quantity,
+        None => to.push(moved),
+    }
+}
+
+pub fn read_workshops() -> Result<Vec<Workshop>, Error> {
+    match fs::read_to_string(WORKSHOPS_PATH) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn write_workshops(workshops: &[Workshop]) -> Result<(), Error> {
+    write_atomic(WORKSHOPS_PATH, &serde_json::to_vec(workshops)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: usize, quantity: u32) -> Item {
+        Item {
+            id,
+            name: format!("Item {}", id),
+            details: String::new(),
+            quantity,
+            category: "Junk".into(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn quantities(items: &[Item]) -> Vec<(usize, u32)> {
+        items.iter().map(|i| (i.id, i.quantity)).collect()
+    }
+
+    #[test]
+    fn partial_transfer_splits_the_stack() {
+        let mut pack = vec![item(1, 10), item(2, 1)];
+        let mut stash = Vec::new();
+        transfer_item(&mut pack, 0, 4, &mut stash);
+        assert_eq!(quantities(&pack), [(1, 6), (2, 1)]);
+        assert_eq!(quantities(&stash), [(1, 4)]);
+    }
+
+    #[test]
+    fn transfers_stack_by_id_both_ways() {
+        let mut pack = vec![item(1, 10), item(2, 1)];
+        let mut stash = vec![item(3, 2), item(1, 5)];
+        transfer_item(&mut pack, 0, 10, &mut stash);
+        // The emptied entry goes, and the rest stack onto the matching one.
+        assert_eq!(quantities(&pack), [(2, 1)]);
+        assert_eq!(quantities(&stash), [(3, 2), (1, 15)]);
+
+        transfer_item(&mut stash, 1, 7, &mut pack);
+        assert_eq!(quantities(&pack), [(2, 1), (1, 7)]);
+        assert_eq!(quantities(&stash), [(3, 2), (1, 8)]);
+    }
+
+    #[test]
+    fn transfers_no_more_than_there_is() {
+        let mut pack = vec![item(1, 3)];
+        let mut stash = Vec::new();
+        transfer_item(&mut pack, 0, 0, &mut stash);
+        assert_eq!((quantities(&pack), stash.len()), (vec![(1, 3)], 0));
+
+        transfer_item(&mut pack, 0, 99, &mut stash);
+        assert!(pack.is_empty());
+        assert_eq!(quantities(&stash), [(1, 3)]);
+    }
+}